@@ -0,0 +1,29 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Linker flags napi-rs needs to build the Node.js addon
+    #[cfg(feature = "node")]
+    napi_build::setup();
+
+    // Keep include/rouge_l.h in step with the C ABI in src/ffi.rs
+    #[cfg(feature = "ffi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("set by cargo");
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        cbindgen::generate(&crate_dir)
+            .expect("cannot generate the C header")
+            .write_to_file(format!("{}/include/rouge_l.h", crate_dir));
+    }
+
+    // Compile the gRPC contract without needing protoc on the build machine
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/rouge_l.proto");
+        let descriptors = protox::compile(["proto/rouge_l.proto"], ["proto"]).expect("invalid proto/rouge_l.proto");
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_fds(descriptors)
+            .expect("cannot generate the gRPC service");
+    }
+}
@@ -0,0 +1,64 @@
+/* ROUGE-L scoring C API, generated by cbindgen from src/ffi.rs: do not edit */
+
+#ifndef ROUGE_L_H
+#define ROUGE_L_H
+
+#include <stdint.h>
+
+// Lowercase and split on whitespace
+#define ROUGE_L_TOKENIZER_WHITESPACE 0
+
+// Lowercase and keep only ASCII alphanumeric runs, as Python `rouge-score`
+#define ROUGE_L_TOKENIZER_ROUGE_SCORE 1
+
+// One LCS over each whole text
+#define ROUGE_L_VARIANT_ROUGE_L 0
+
+// Union LCS over newline-separated sentences (`rougeLsum`)
+#define ROUGE_L_VARIANT_ROUGE_LSUM 1
+
+// Outcome of a call; the result is only written on `Ok`
+typedef enum RougeLStatus {
+  ROUGE_L_STATUS_OK = 0,
+  // A text or the result pointer is null
+  ROUGE_L_STATUS_NULL_POINTER = 1,
+  // A text is not valid UTF-8
+  ROUGE_L_STATUS_INVALID_UTF8 = 2,
+  // The tokenizer is not one of the `ROUGE_L_TOKENIZER_*` constants
+  ROUGE_L_STATUS_UNKNOWN_TOKENIZER = 3,
+  // The variant is not one of the `ROUGE_L_VARIANT_*` constants
+  ROUGE_L_STATUS_UNKNOWN_VARIANT = 4,
+  // Scoring panicked; this is a bug
+  ROUGE_L_STATUS_INTERNAL = 5,
+} RougeLStatus;
+
+// Scores of a candidate against a reference, each between 0 and 1
+typedef struct RougeLScore {
+  double f_measure;
+  double precision;
+  double recall;
+} RougeLScore;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Score the NUL-terminated UTF-8 `candidate` against `reference` and store
+// the scores in `*result`
+//
+// # Safety
+//
+// `candidate` and `reference` must be null or point to NUL-terminated
+// strings, and `result` must be null or point to writable memory for a
+// `RougeLScore`, all valid for the duration of the call.
+enum RougeLStatus rouge_l_score(const char *candidate,
+                                const char *reference,
+                                uint32_t tokenizer,
+                                uint32_t variant,
+                                struct RougeLScore *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ROUGE_L_H */
@@ -0,0 +1,93 @@
+#!/usr/bin/env python3
+"""
+Score candidate/reference records with a Python ROUGE-L implementation, for
+`rouge_l_rust verify`
+
+Usage: python_rouge.py IMPLEMENTATION VARIANT TOKENIZER
+    IMPLEMENTATION  rouge-score or py-rouge
+    VARIANT         rouge-l or rouge-lsum
+    TOKENIZER       whitespace or rouge-score
+
+Reads one {"candidate": str, "references": [str, ...]} record per stdin line
+and writes one {"f_measure", "precision", "recall"} line per record to stdout,
+the scores against the reference with the best F-measure (the first on ties).
+"""
+
+import json
+import sys
+
+# Exit status when the requested implementation is not installed
+NOT_INSTALLED = 3
+
+
+class WhitespaceTokenizer:
+    """Lowercase and split on whitespace, like `--tokenizer whitespace`"""
+
+    def tokenize(self, text):
+        return text.lower().split()
+
+
+def not_installed(package):
+    print(f"{package} is not installed for {sys.executable}; "
+          f"run `{sys.executable} -m pip install {package}` or pass another --python",
+          file=sys.stderr)
+    sys.exit(NOT_INSTALLED)
+
+
+def rouge_score_scorer(variant, tokenizer):
+    try:
+        from rouge_score import rouge_scorer
+    except ImportError:
+        not_installed("rouge-score")
+
+    rouge_type = "rougeLsum" if variant == "rouge-lsum" else "rougeL"
+    options = {"tokenizer": WhitespaceTokenizer()} if tokenizer == "whitespace" else {}
+    scorer = rouge_scorer.RougeScorer([rouge_type], **options)
+
+    def score(candidate, references):
+        # max() keeps the first of equal F-measures
+        scores = [scorer.score(reference, candidate)[rouge_type] for reference in references]
+        best = max(scores, key=lambda s: s.fmeasure)
+        return best.fmeasure, best.precision, best.recall
+
+    return score
+
+
+def py_rouge_scorer(variant, tokenizer):
+    try:
+        import rouge
+    except ImportError:
+        not_installed("py-rouge")
+
+    # py-rouge has its own tokenizer and always scores summary-level LCS
+    if variant != "rouge-lsum" or tokenizer != "rouge-score":
+        print("warning: py-rouge always scores like --variant rouge-lsum --tokenizer rouge-score",
+              file=sys.stderr)
+    evaluator = rouge.Rouge(metrics=["rouge-l"], limit_length=False, apply_avg=False,
+                            apply_best=False, alpha=0.5, stemming=False)
+
+    def score(candidate, references):
+        scores = evaluator.get_scores([candidate], [references])["rouge-l"][0]
+        best = max(range(len(references)), key=lambda i: scores["f"][i])
+        return scores["f"][best], scores["p"][best], scores["r"][best]
+
+    return score
+
+
+def main():
+    if len(sys.argv) != 4:
+        print(__doc__, file=sys.stderr)
+        sys.exit(2)
+    implementation, variant, tokenizer = sys.argv[1:]
+    scorers = {"rouge-score": rouge_score_scorer, "py-rouge": py_rouge_scorer}
+    score = scorers[implementation](variant, tokenizer)
+
+    for line in sys.stdin:
+        record = json.loads(line)
+        f_measure, precision, recall = score(record["candidate"], record["references"])
+        print(json.dumps({"f_measure": f_measure, "precision": precision, "recall": recall}))
+    sys.stdout.flush()
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,57 @@
+#!/usr/bin/env python3
+"""
+Log the results of `rouge_l_rust batch --wandb-project` to a Weights & Biases run
+
+Usage: wandb_log.py < PAYLOAD
+
+Reads one JSON object from stdin:
+    project, entity, run_id, name   where to log; run_id resumes that run
+    prefix                          metric section, rouge_l or rouge_lsum
+    metrics                         {name: value} of the averages
+    signature                       stored in the run config
+    histogram                       {"counts": [...], "edges": [...]} of F-measures
+    worst                           {"columns": [...], "rows": [[...], ...]}
+and prints the URL of the run, or its local directory, on stderr.
+"""
+
+import json
+import sys
+
+# Exit status when wandb is not installed
+NOT_INSTALLED = 3
+
+
+def main():
+    if len(sys.argv) != 1:
+        print(__doc__, file=sys.stderr)
+        sys.exit(2)
+    try:
+        import wandb
+    except ImportError:
+        print(f"wandb is not installed for {sys.executable}; "
+              f"run `{sys.executable} -m pip install wandb` or pass another --wandb-python",
+              file=sys.stderr)
+        sys.exit(NOT_INSTALLED)
+
+    payload = json.load(sys.stdin)
+    run = wandb.init(project=payload["project"], entity=payload["entity"], id=payload["run_id"],
+                     name=payload["name"], resume="allow" if payload["run_id"] else None,
+                     job_type="eval")
+    prefix = payload["prefix"]
+    run.config.update({f"{prefix}_signature": payload["signature"]}, allow_val_change=True)
+
+    logged = {f"{prefix}/{name}": value for name, value in payload["metrics"].items()}
+    histogram = payload["histogram"]
+    logged[f"{prefix}/f_measure_histogram"] = wandb.Histogram(
+        np_histogram=(histogram["counts"], histogram["edges"]))
+    if payload["worst"]["rows"]:
+        logged[f"{prefix}/worst"] = wandb.Table(columns=payload["worst"]["columns"],
+                                                data=payload["worst"]["rows"])
+    run.log(logged)
+    # Offline runs (WANDB_MODE=offline) have no URL until they are synced
+    print(f"wandb: logged to {run.url or run.dir}", file=sys.stderr)
+    run.finish()
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,254 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+use serde_json::json;
+
+use rouge_l_rust::intern::Interner;
+use rouge_l_rust::lcs;
+use rouge_l_rust::summary::{self, SENTENCE_BREAK};
+use rouge_l_rust::truncate::TruncationPolicy;
+
+use crate::batch::ScoredPair;
+use crate::config::Settings;
+use crate::input::Pair;
+use crate::output;
+
+/// An LCS of a candidate and the reference it matches best, the earliest
+/// on ties, over the tokens scored under the token limit
+pub struct Matched {
+    /// Index of the reference among those of the pair
+    pub reference: usize,
+    pub candidate_tokens: Vec<String>,
+    pub reference_tokens: Vec<String>,
+    /// Tokens --max-tokens dropped from the front of the candidate and of
+    /// the reference
+    pub offsets: (usize, usize),
+    pub alignment: lcs::Alignment,
+}
+
+/// One step of an edit script turning a candidate into its reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum EditOp {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// A run of consecutive tokens under the same operation
+#[derive(Serialize)]
+struct Edit {
+    op: EditOp,
+    text: String,
+}
+
+/// Write one JSON line per scored pair with the tokens of an LCS of its
+/// candidate and the reference it matches best, the earliest on ties
+///
+/// Each line holds the pair's `id`, the 0-based index of that `reference`
+/// and the `alignment` as `[candidate_index, reference_index, token]`
+/// triples in text order. Indices count tokens of the whole texts, even
+/// when --max-tokens cut them down for scoring.
+pub fn write_alignments(
+    out: &mut dyn Write,
+    pairs: &[Pair],
+    scores: &[ScoredPair],
+    settings: &Settings,
+) -> io::Result<()> {
+    for scored in scores {
+        let matched = best_match(&pairs[scored.index], settings);
+        let (candidate_offset, reference_offset) = matched.offsets;
+        let alignment: Vec<(usize, usize, &str)> = matched
+            .alignment
+            .iter()
+            .map(|&(i, j)| (i + candidate_offset, j + reference_offset, matched.candidate_tokens[i].as_str()))
+            .collect();
+        let line = json!({ "id": scored.id, "reference": matched.reference, "alignment": alignment });
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Write one JSON line per scored pair with the shortest edit script
+/// turning its candidate into the reference it matches best
+///
+/// The script keeps the LCS and otherwise only deletes candidate tokens and
+/// inserts reference tokens, as `{"op": "keep"|"delete"|"insert", "text"}`
+/// runs of consecutive tokens; within a gap between kept runs, deletions
+/// come first. The line also counts the `kept`, `deleted` and `inserted`
+/// tokens, the post-editing effort being the last two. Only the tokens
+/// scored under --max-tokens are covered.
+pub fn write_edit_scripts(
+    out: &mut dyn Write,
+    pairs: &[Pair],
+    scores: &[ScoredPair],
+    settings: &Settings,
+) -> io::Result<()> {
+    for scored in scores {
+        let matched = best_match(&pairs[scored.index], settings);
+        let edits = edit_script(&matched);
+        let kept = matched.alignment.len();
+        let line = json!({
+            "id": scored.id,
+            "reference": matched.reference,
+            "kept": kept,
+            "deleted": matched.candidate_tokens.len() - kept,
+            "inserted": matched.reference_tokens.len() - kept,
+            "edits": edits,
+        });
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Write one JSON line per scored pair with the ROUGE-Lsum contribution of
+/// every sentence of the reference it matches best, the earliest on ties
+///
+/// Each sentence lists its `text`, its `tokens`, the `union` of its LCSs
+/// with the candidate sentences and the `hits` that union adds to the
+/// score, and the `recall` of the sentence, hits over tokens: the sentences
+/// with a low recall are those the candidate fails to cover. Like the
+/// scores, they cover only the tokens kept under --max-tokens; a sentence
+/// cut short keeps its whole `text`, and those dropped are left out.
+pub fn write_sentence_contributions(
+    out: &mut dyn Write,
+    pairs: &[Pair],
+    scores: &[ScoredPair],
+    settings: &Settings,
+) -> io::Result<()> {
+    for scored in scores {
+        let pair = &pairs[scored.index];
+        let mut interner = Interner::new();
+        let candidate = interner.tokenize_sentences(settings.tokenizer, &pair.candidate);
+        let candidate = kept_sentences(settings, &candidate);
+        let mut best: Option<(usize, f64, Vec<&str>, Vec<u32>)> = None;
+        for (index, text) in pair.references.iter().enumerate() {
+            // Sentences without tokens are dropped, as `summary::sentences` drops them
+            let mut texts = Vec::new();
+            let mut tokens = Vec::new();
+            for sentence in summary::split_sentences(text) {
+                let sentence_tokens = interner.tokenize(settings.tokenizer, sentence);
+                if sentence_tokens.is_empty() {
+                    continue;
+                }
+                if !texts.is_empty() {
+                    tokens.push(SENTENCE_BREAK);
+                }
+                texts.push(sentence.trim());
+                tokens.extend(sentence_tokens);
+            }
+            let tokens = kept_sentences(settings, &tokens).to_vec();
+            let f_measure = summary::rouge_lsum_from_tokens(candidate, &tokens).f_measure;
+            if best.as_ref().is_none_or(|(_, best, _, _)| f_measure > *best) {
+                best = Some((index, f_measure, texts, tokens));
+            }
+        }
+        let (reference, _, texts, tokens) = best.expect("a pair has at least one reference");
+        let contributions = summary::sentence_contributions(candidate, &tokens);
+        // Truncating the head drops the first sentences, the tail the last
+        let texts = match settings.token_limit.map(|limit| limit.policy) {
+            Some(TruncationPolicy::TruncateHead) => &texts[texts.len() - contributions.len()..],
+            _ => &texts[..contributions.len()],
+        };
+        let sentences: Vec<_> = contributions
+            .into_iter()
+            .zip(texts)
+            .map(|(contribution, text)| {
+                let recall = contribution.hits as f64 / contribution.tokens as f64;
+                json!({
+                    "text": text,
+                    "tokens": contribution.tokens,
+                    "union": contribution.union,
+                    "hits": contribution.hits,
+                    "recall": output::json_score(recall, &settings.score_format),
+                })
+            })
+            .collect();
+        let line = json!({ "id": scored.id, "reference": reference, "sentences": sentences });
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Recover an LCS of the candidate with every reference of `pair`, keeping
+/// the one of the reference scored like the pair
+pub fn best_match(pair: &Pair, settings: &Settings) -> Matched {
+    let candidate_tokens = settings.tokenizer.tokenize(&pair.candidate);
+    let candidate: Vec<&str> = candidate_tokens.iter().collect();
+    let (candidate_offset, candidate) = kept(settings, &candidate);
+    let mut best: Option<Matched> = None;
+    let mut best_f = 0.0;
+    for (index, text) in pair.references.iter().enumerate() {
+        let reference_tokens = settings.tokenizer.tokenize(text);
+        let reference: Vec<&str> = reference_tokens.iter().collect();
+        let (reference_offset, reference) = kept(settings, &reference);
+        let alignment = lcs::alignment(candidate, reference);
+        let f_measure = 2.0 * alignment.len() as f64 / (candidate.len() + reference.len()).max(1) as f64;
+        if best.is_none() || f_measure > best_f {
+            best_f = f_measure;
+            best = Some(Matched {
+                reference: index,
+                candidate_tokens: candidate.iter().map(|token| token.to_string()).collect(),
+                reference_tokens: reference.iter().map(|token| token.to_string()).collect(),
+                offsets: (candidate_offset, reference_offset),
+                alignment,
+            });
+        }
+    }
+    best.expect("a pair has at least one reference")
+}
+
+/// Walk the candidate and reference in step, keeping the aligned tokens
+fn edit_script(matched: &Matched) -> Vec<Edit> {
+    let mut edits: Vec<Edit> = Vec::new();
+    let mut push = |op: EditOp, token: &str| match edits.last_mut() {
+        Some(last) if last.op == op => {
+            last.text.push(' ');
+            last.text.push_str(token);
+        }
+        _ => edits.push(Edit {
+            op,
+            text: token.to_string(),
+        }),
+    };
+    let (candidate, reference) = (&matched.candidate_tokens, &matched.reference_tokens);
+    let (mut i, mut j) = (0, 0);
+    let ends = std::iter::once((candidate.len(), reference.len()));
+    for (next_i, next_j) in matched.alignment.iter().copied().chain(ends) {
+        for token in &candidate[i..next_i] {
+            push(EditOp::Delete, token);
+        }
+        for token in &reference[j..next_j] {
+            push(EditOp::Insert, token);
+        }
+        if next_i < candidate.len() {
+            push(EditOp::Keep, &candidate[next_i]);
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    edits
+}
+
+/// The tokens scored under the token limit, after the number dropped from
+/// the front
+fn kept<'a, 't>(settings: &Settings, tokens: &'a [&'t str]) -> (usize, &'a [&'t str]) {
+    match settings.token_limit.map(|limit| (limit.policy, limit.apply(tokens))) {
+        Some((TruncationPolicy::TruncateHead, Ok((kept, _)))) => (tokens.len() - kept.len(), kept),
+        Some((_, Ok((kept, _)))) => (0, kept),
+        // Pairs over a hard limit were refused before scoring
+        Some((_, Err(_))) | None => (0, tokens),
+    }
+}
+
+/// The sentence-split tokens scored under the token limit, as
+/// `batch::prepare_pair` keeps them: sentence breaks do not count
+fn kept_sentences<'a>(settings: &Settings, tokens: &'a [u32]) -> &'a [u32] {
+    match settings.token_limit.map(|limit| limit.apply_counting(tokens, |&token| token != SENTENCE_BREAK)) {
+        Some(Ok((kept, _))) => kept,
+        // Pairs over a hard limit were refused before scoring
+        Some(Err(_)) | None => tokens,
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::alignment::{self, Matched};
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+use crate::input::Pair;
+use crate::output;
+
+/// How often a token was left off the LCS, out of its occurrences
+pub struct TokenCount {
+    pub token: String,
+    /// Occurrences not on the LCS of their pair
+    pub unmatched: usize,
+    pub occurrences: usize,
+}
+
+impl TokenCount {
+    /// Share of the occurrences left off the LCS
+    pub fn rate(&self) -> f64 {
+        self.unmatched as f64 / self.occurrences as f64
+    }
+}
+
+/// Tokens left off the LCS over a corpus, on both sides, the most often
+/// missed first
+pub struct TokenErrors {
+    /// Reference tokens the candidates failed to produce
+    pub missed: Vec<TokenCount>,
+    /// Candidate tokens the references do not hold
+    pub unmatched: Vec<TokenCount>,
+}
+
+/// Align every candidate with the reference it matches best and count, for
+/// every token, how many of its occurrences are off the LCS
+///
+/// Tokens are counted as the tokenizer produces them, over the tokens scored
+/// under --max-tokens. With the `parallel` feature the pairs are aligned on
+/// the rayon thread pool.
+pub fn token_errors(pairs: &[Pair], settings: &Settings) -> TokenErrors {
+    #[cfg(feature = "parallel")]
+    let matched: Vec<Matched> = pairs.par_iter().map(|pair| alignment::best_match(pair, settings)).collect();
+    #[cfg(not(feature = "parallel"))]
+    let matched: Vec<Matched> = pairs.iter().map(|pair| alignment::best_match(pair, settings)).collect();
+
+    let mut missed: HashMap<&str, (usize, usize)> = HashMap::new();
+    let mut unmatched: HashMap<&str, (usize, usize)> = HashMap::new();
+    for matched in &matched {
+        let mut on_candidate = vec![false; matched.candidate_tokens.len()];
+        let mut on_reference = vec![false; matched.reference_tokens.len()];
+        for &(i, j) in &matched.alignment {
+            on_candidate[i] = true;
+            on_reference[j] = true;
+        }
+        for (counts, tokens, aligned) in [
+            (&mut missed, &matched.reference_tokens, &on_reference),
+            (&mut unmatched, &matched.candidate_tokens, &on_candidate),
+        ] {
+            for (token, &aligned) in tokens.iter().zip(aligned) {
+                let count = counts.entry(token.as_str()).or_default();
+                count.0 += usize::from(!aligned);
+                count.1 += 1;
+            }
+        }
+    }
+    TokenErrors {
+        missed: ranked(missed),
+        unmatched: ranked(unmatched),
+    }
+}
+
+/// The tokens off the LCS at least once, the most often first, then by rate
+/// and alphabetically
+fn ranked(counts: HashMap<&str, (usize, usize)>) -> Vec<TokenCount> {
+    let mut ranked: Vec<TokenCount> = counts
+        .into_iter()
+        .filter(|&(_, (unmatched, _))| unmatched > 0)
+        .map(|(token, (unmatched, occurrences))| TokenCount {
+            token: token.to_string(),
+            unmatched,
+            occurrences,
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.unmatched
+            .cmp(&a.unmatched)
+            .then_with(|| b.rate().total_cmp(&a.rate()))
+            .then_with(|| a.token.cmp(&b.token))
+    });
+    ranked
+}
+
+/// Print the `top` most often missed reference tokens and unmatched
+/// candidate tokens, each as a table of counts and rates
+pub fn write_tables(
+    out: &mut dyn Write,
+    errors: &TokenErrors,
+    pairs: usize,
+    top: usize,
+    score_format: &ScoreFormat,
+) -> io::Result<()> {
+    let tables = [
+        ("Reference tokens most often missed", "Missed", &errors.missed),
+        ("Candidate tokens most often unmatched", "Unmatched", &errors.unmatched),
+    ];
+    for (i, (title, column, counts)) in tables.into_iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{} over {} pairs ({} of {} tokens)", title, pairs, counts.len().min(top), counts.len())?;
+        let mut rows = vec![["Token", column, "Occurrences", "Rate"].map(String::from)];
+        for count in counts.iter().take(top) {
+            rows.push([
+                count.token.clone(),
+                count.unmatched.to_string(),
+                count.occurrences.to_string(),
+                score_format.format(count.rate()),
+            ]);
+        }
+        output::write_columns(out, &rows, 1)?;
+    }
+    Ok(())
+}
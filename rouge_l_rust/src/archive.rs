@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::compress;
+use crate::input::{glob_match, InputError};
+
+/// Read every file of a tar or zip archive whose member path matches
+/// `pattern`, keyed by file name
+///
+/// Zip archives are recognized by their `.zip` extension; anything else is
+/// read as a tar archive, gzip- or zstd-compressed or not. A pattern without
+/// a `/` is matched against the file name alone, so `*.txt` selects text
+/// files in every directory of the archive. Two matching members with the
+/// same file name are an error, since pairs are matched by file name.
+pub fn read_files(path: &Path, pattern: Option<&str>) -> Result<BTreeMap<String, String>, InputError> {
+    let invalid = |message: String| InputError::Invalid(path.to_path_buf(), message);
+    let mut files = BTreeMap::new();
+    let mut add = |member: &str, read: &mut dyn Read| {
+        let name = member.rsplit('/').next().unwrap_or(member);
+        let selected = match pattern {
+            Some(pattern) if pattern.contains('/') => glob_match(pattern, member),
+            Some(pattern) => glob_match(pattern, name),
+            None => true,
+        };
+        if !selected {
+            return Ok(());
+        }
+        let mut text = String::new();
+        read.read_to_string(&mut text)
+            .map_err(|e| invalid(format!("member {}: {}", member, e)))?;
+        match files.insert(name.to_string(), text) {
+            Some(_) => Err(invalid(format!("more than one member named {}", name))),
+            None => Ok(()),
+        }
+    };
+
+    if path.extension().is_some_and(|ext| ext == "zip") {
+        let file = File::open(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|e| invalid(e.to_string()))?;
+        for index in 0..archive.len() {
+            let mut member = archive.by_index(index).map_err(|e| invalid(e.to_string()))?;
+            if member.is_file() {
+                let name = member.name().to_string();
+                add(&name, &mut member)?;
+            }
+        }
+    } else {
+        let reader = compress::open(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+        let mut archive = tar::Archive::new(reader);
+        for member in archive.entries().map_err(|e| invalid(e.to_string()))? {
+            let mut member = member.map_err(|e| invalid(e.to_string()))?;
+            if member.header().entry_type().is_file() {
+                let name = member.path().map_err(|e| invalid(e.to_string()))?.to_string_lossy().into_owned();
+                add(&name, &mut member)?;
+            }
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,420 @@
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use rouge_l_rust::lcs::{LcsStrategy, Scratch};
+use rouge_l_rust::reference::ReferenceSet;
+use rouge_l_rust::summary::{self, Variant, SENTENCE_BREAK};
+use rouge_l_rust::truncate::TooManyTokens;
+use rouge_l_rust::{f_measure_bound, RougeLResult};
+
+use crate::cache::{CachedScore, ScoreCache};
+use crate::config::Settings;
+use crate::input::Pair;
+use crate::stats;
+
+/// Score of a single pair in a batch run
+#[derive(Debug, Clone)]
+pub struct ScoredPair {
+    /// Position of the pair in the input
+    pub index: usize,
+    pub id: String,
+    pub candidate_tokens: usize,
+    /// Whether either text was cut down to the token limit
+    pub truncated: bool,
+    /// Score of the candidate, the rank-1 hypothesis of an n-best list
+    pub result: RougeLResult,
+    pub n_best: Option<NBest>,
+}
+
+/// Scores over every hypothesis of an n-best candidate list
+#[derive(Debug, Clone)]
+pub struct NBest {
+    pub hypotheses: usize,
+    /// Highest-scoring hypothesis, the oracle of a reranker
+    pub best: RougeLResult,
+    /// 1-based rank of the best hypothesis; the first wins ties
+    pub best_rank: usize,
+    /// Mean precision, recall and F-measure over the hypotheses
+    pub mean: RougeLResult,
+}
+
+/// A pair refused before scoring because it breaks a configured limit
+#[derive(Debug)]
+pub enum PairError {
+    /// A text over the token limit under the `error` truncation policy
+    TooManyTokens {
+        id: String,
+        /// `candidate` or `reference`
+        text: &'static str,
+        source: TooManyTokens,
+    },
+    /// The strategy's DP table would not fit in the memory limit
+    TableTooLarge { id: String, bytes: usize, limit_mib: u64 },
+}
+
+impl fmt::Display for PairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PairError::TooManyTokens { id, text, source } => write!(
+                f,
+                "pair {}: {} has {} (choose a --truncation policy to score it anyway)",
+                id, text, source
+            ),
+            PairError::TableTooLarge { id, bytes, limit_mib } => write!(
+                f,
+                "pair {}: the DP table would need {} MiB, above the {} MiB --max-table-memory limit \
+                 (use a linear-memory --lcs-strategy such as auto, or cap the input with --max-tokens)",
+                id,
+                bytes.div_ceil(1 << 20),
+                limit_mib
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PairError {}
+
+/// Token IDs of a pair once the limits are applied
+#[derive(Debug)]
+pub struct PreparedPair {
+    pub candidate: Vec<u32>,
+    pub references: Vec<Arc<[u32]>>,
+    /// Lower-ranked hypotheses of an n-best list
+    pub hypotheses: Vec<Vec<u32>>,
+    /// Whether any text was cut down to the token limit
+    pub truncated: bool,
+}
+
+/// Cut every hypothesis and reference down to the token limit, and refuse
+/// pairs whose DP table would not fit in memory rather than let the
+/// allocator abort the process
+pub fn prepare_pair(
+    settings: &Settings,
+    id: &str,
+    candidate: Vec<u32>,
+    references: Vec<Arc<[u32]>>,
+    hypotheses: Vec<Vec<u32>>,
+) -> Result<PreparedPair, PairError> {
+    let mut truncated = false;
+    // The kept tokens when `tokens` had to be cut
+    let mut limit = |text, tokens: &[u32]| {
+        let Some(limit) = settings.token_limit else {
+            return Ok(None);
+        };
+        let (kept, cut) = limit
+            .apply_counting(tokens, |&token| token != SENTENCE_BREAK)
+            .map_err(|source| PairError::TooManyTokens {
+                id: id.to_string(),
+                text,
+                source,
+            })?;
+        truncated |= cut;
+        Ok(cut.then(|| kept.to_vec()))
+    };
+    let candidate = limit("candidate", &candidate)?.unwrap_or(candidate);
+    let hypotheses = hypotheses
+        .into_iter()
+        .map(|hypothesis| Ok(limit("hypothesis", &hypothesis)?.unwrap_or(hypothesis)))
+        .collect::<Result<Vec<_>, PairError>>()?;
+    let references = references
+        .into_iter()
+        .map(|reference| Ok(limit("reference", &reference)?.map(Arc::from).unwrap_or(reference)))
+        .collect::<Result<Vec<_>, PairError>>()?;
+    // ROUGE-Lsum compares sentence by sentence, so only the longest count
+    let longest = std::iter::once(&candidate)
+        .chain(&hypotheses)
+        .map(|hypothesis| summary::longest_sentence(hypothesis))
+        .max()
+        .unwrap_or(0);
+    for reference in &references {
+        check_table_memory(settings, id, longest, summary::longest_sentence(reference))?;
+    }
+    Ok(PreparedPair {
+        candidate,
+        references,
+        hypotheses,
+        truncated,
+    })
+}
+
+/// Refuse a pair whose DP table under the configured strategy would exceed
+/// `--max-table-memory`; ROUGE-Lsum always fills a full table per sentence pair
+pub fn check_table_memory(settings: &Settings, id: &str, len1: usize, len2: usize) -> Result<(), PairError> {
+    let strategy = match settings.variant {
+        Variant::RougeL => settings.lcs_strategy,
+        Variant::RougeLsum => LcsStrategy::Dp,
+    };
+    let bytes = strategy.table_bytes(len1, len2);
+    if bytes as u64 > settings.max_table_memory.saturating_mul(1 << 20) {
+        return Err(PairError::TableTooLarge {
+            id: id.to_string(),
+            bytes,
+            limit_mib: settings.max_table_memory,
+        });
+    }
+    Ok(())
+}
+
+/// Score the candidate of a prepared pair, and every hypothesis when it is an n-best list
+pub fn score_prepared(settings: &Settings, scratch: &mut Scratch, pair: &PreparedPair) -> (RougeLResult, Option<NBest>) {
+    let mut score = |tokens: &[u32]| {
+        settings
+            .variant
+            .best_of_in(scratch, tokens, &pair.references, settings.lcs_strategy)
+    };
+    let result = score(&pair.candidate);
+    if pair.hypotheses.is_empty() {
+        return (result, None);
+    }
+    let mut mean = SummaryBuilder::default();
+    mean.add(&result);
+    let (mut best, mut best_rank) = (result.clone(), 1);
+    for (rank, hypothesis) in (2..).zip(&pair.hypotheses) {
+        let scored = score(hypothesis);
+        mean.add(&scored);
+        if scored.f_measure > best.f_measure {
+            (best, best_rank) = (scored, rank);
+        }
+    }
+    let n_best = NBest {
+        hypotheses: pair.hypotheses.len() + 1,
+        best,
+        best_rank,
+        mean: mean.finish().mean,
+    };
+    (result, Some(n_best))
+}
+
+/// Highest bag-of-words bound on the F-measure over the references of a pair
+pub fn best_bound(pair: &PreparedPair) -> f64 {
+    let candidate = summary::words(&pair.candidate);
+    pair.references
+        .iter()
+        .map(|reference| f_measure_bound(&candidate, &summary::words(reference)))
+        .fold(0.0, f64::max)
+}
+
+/// Corpus-level averages over a batch run
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: RougeLResult,
+    /// Averages had the best hypothesis of every n-best list been chosen,
+    /// the reranking oracle; `None` when no pair has an n-best list
+    pub oracle: Option<RougeLResult>,
+}
+
+impl Summary {
+    pub fn from_scores(scores: &[ScoredPair]) -> Summary {
+        let mut builder = SummaryBuilder::default();
+        for scored in scores {
+            builder.add_scored(scored);
+        }
+        builder.finish()
+    }
+}
+
+/// Running sums for a summary of results that are streamed rather than kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SummaryBuilder {
+    count: usize,
+    f_measure: f64,
+    precision: f64,
+    recall: f64,
+    /// Pairs scored with an n-best list
+    #[serde(default)]
+    n_best: usize,
+    /// F-measure, precision and recall summed over the best hypothesis of
+    /// n-best lists and the only candidate of other pairs
+    #[serde(default)]
+    oracle: [f64; 3],
+}
+
+impl SummaryBuilder {
+    pub fn add(&mut self, result: &RougeLResult) {
+        self.add_best_of(result, None);
+    }
+
+    /// Add the score of a pair, and the best of its n-best list to the oracle
+    pub fn add_scored(&mut self, scored: &ScoredPair) {
+        self.add_best_of(&scored.result, scored.n_best.as_ref());
+    }
+
+    fn add_best_of(&mut self, result: &RougeLResult, n_best: Option<&NBest>) {
+        self.count += 1;
+        self.f_measure += result.f_measure;
+        self.precision += result.precision;
+        self.recall += result.recall;
+        let best = n_best.map_or(result, |n_best| &n_best.best);
+        self.n_best += usize::from(n_best.is_some());
+        self.oracle[0] += best.f_measure;
+        self.oracle[1] += best.precision;
+        self.oracle[2] += best.recall;
+    }
+
+    pub fn finish(&self) -> Summary {
+        if self.count == 0 {
+            return Summary {
+                count: 0,
+                mean: RougeLResult::new(0.0, 0.0, 0.0),
+                oracle: None,
+            };
+        }
+        let n = self.count as f64;
+        let [f_measure, precision, recall] = self.oracle;
+        Summary {
+            count: self.count,
+            mean: RougeLResult::new(self.f_measure / n, self.precision / n, self.recall / n),
+            oracle: (self.n_best > 0).then(|| RougeLResult::new(f_measure / n, precision / n, recall / n)),
+        }
+    }
+}
+
+/// Bootstrap resampling behind the confidence intervals of the ROUGE-1.5.5
+/// output, set with its `-c` and `-r` options
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bootstrap {
+    /// Confidence level in percent
+    pub confidence: f64,
+    pub resamples: usize,
+}
+
+impl Default for Bootstrap {
+    fn default() -> Self {
+        Bootstrap {
+            confidence: 95.0,
+            resamples: 1000,
+        }
+    }
+}
+
+impl Bootstrap {
+    /// Confidence interval of the mean of `values`
+    ///
+    /// As in ROUGE-1.5.5, the bounds are read off the sorted means of
+    /// `resamples` samples drawn with replacement. The samples are drawn with
+    /// seed 0, so the same scores always print the same interval.
+    pub fn interval(&self, values: &[f64]) -> (f64, f64) {
+        stats::bootstrap(values, self.resamples, 0).interval(self.confidence)
+    }
+}
+
+/// Counts reported after scoring a batch
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ScoreStats {
+    pub pairs: usize,
+    /// Pairs the bag-of-words bound ruled out before running the LCS
+    pub skipped: usize,
+    /// Pairs whose score was reused from the on-disk cache
+    pub cached: usize,
+    /// Scored pairs with a text cut down to the token limit
+    pub truncated: usize,
+}
+
+/// State of a pair after the sequential tokenization pass
+enum Work {
+    Cached(CachedScore),
+    Tokenized {
+        cacheable: bool,
+        pair: PreparedPair,
+    },
+}
+
+/// Score every pair in input order, tokenizing each distinct reference once
+///
+/// A pair with several references keeps its best score over them. Pairs
+/// found in `cache` are not tokenized or scored again, and new scores are
+/// added to it. With `min_f`, pairs whose bag-of-words bound on the
+/// F-measure is below it are dropped without running the LCS. Every pair
+/// goes through [`prepare_pair`] first. Interning is sequential; with the
+/// `parallel` feature the pairs are then scored on the rayon thread pool.
+#[tracing::instrument(level = "info", skip_all, fields(pairs = pairs.len()))]
+pub fn score_pairs(
+    pairs: &[Pair],
+    settings: &Settings,
+    min_f: Option<f64>,
+    cache: Option<&mut ScoreCache>,
+) -> Result<(Vec<ScoredPair>, ScoreStats), PairError> {
+    let mut references = ReferenceSet::with_variant(settings.tokenizer, settings.variant);
+    let tokenizing = tracing::debug_span!("tokenize").entered();
+    let work = pairs
+        .iter()
+        .map(|pair| {
+            // The cache holds single scores, so n-best lists are always scored
+            let cacheable = pair.hypotheses.is_empty();
+            let lookup = cache.as_deref().filter(|_| cacheable);
+            if let Some(hit) = lookup.and_then(|cache| cache.get(&pair.candidate, &pair.references)) {
+                return Ok(Work::Cached(hit));
+            }
+            let candidate = references.candidate(&pair.candidate);
+            let tokenized = pair.references.iter().map(|text| references.reference(text)).collect();
+            let hypotheses = pair.hypotheses.iter().map(|text| references.candidate(text)).collect();
+            Ok(Work::Tokenized {
+                cacheable,
+                pair: prepare_pair(settings, &pair.id, candidate, tokenized, hypotheses)?,
+            })
+        })
+        .collect::<Result<Vec<Work>, PairError>>()?;
+    drop(tokenizing);
+
+    // Entered by every worker, so the per-pair spans nest under it
+    let scoring = tracing::debug_span!("score");
+    let score = |scratch: &mut Scratch, (index, work): (usize, &Work)| {
+        let _scoring = scoring.enter();
+        let (candidate_tokens, truncated, result, n_best) = match work {
+            Work::Cached(hit) => (hit.candidate_tokens, hit.truncated, hit.result.clone(), None),
+            Work::Tokenized { pair, .. } => {
+                if min_f.is_some_and(|min_f| best_bound(pair) < min_f) {
+                    return None;
+                }
+                let (result, n_best) = score_prepared(settings, scratch, pair);
+                (summary::token_count(&pair.candidate), pair.truncated, result, n_best)
+            }
+        };
+        Some(ScoredPair {
+            index,
+            id: pairs[index].id.clone(),
+            candidate_tokens,
+            truncated,
+            result,
+            n_best,
+        })
+    };
+    // Each worker reuses one set of LCS buffers across the pairs it scores
+    #[cfg(feature = "parallel")]
+    let scores: Vec<ScoredPair> = work.par_iter().enumerate().map_init(Scratch::default, score).flatten().collect();
+    #[cfg(not(feature = "parallel"))]
+    let scores: Vec<ScoredPair> = {
+        let mut scratch = Scratch::default();
+        work.iter().enumerate().filter_map(|item| score(&mut scratch, item)).collect()
+    };
+
+    if let Some(cache) = cache {
+        for scored in &scores {
+            if let Work::Tokenized { cacheable: true, .. } = work[scored.index] {
+                let pair = &pairs[scored.index];
+                cache.insert(
+                    &pair.candidate,
+                    &pair.references,
+                    CachedScore {
+                        candidate_tokens: scored.candidate_tokens,
+                        truncated: scored.truncated,
+                        result: scored.result.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    let stats = ScoreStats {
+        pairs: pairs.len(),
+        skipped: pairs.len() - scores.len(),
+        cached: work.iter().filter(|work| matches!(work, Work::Cached(_))).count(),
+        truncated: scores.iter().filter(|scored| scored.truncated).count(),
+    };
+    Ok((scores, stats))
+}
@@ -0,0 +1,134 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rouge_l_rust::intern::Vocab;
+use rouge_l_rust::summary;
+
+use crate::batch::{self, PairError};
+use crate::config::Settings;
+use crate::input::Pair;
+
+/// Timings collected by `bench`
+#[derive(Debug)]
+pub struct BenchReport {
+    pub pairs: usize,
+    pub tokens: usize,
+    pub warmup: usize,
+    pub iterations: u32,
+    pub strategy: &'static str,
+    /// Wall time of each timed pass over the whole corpus
+    pub passes: Vec<Duration>,
+    /// Latency of every pair scored during the timed passes, sorted
+    pub latencies: Vec<Duration>,
+}
+
+impl BenchReport {
+    pub fn total(&self) -> Duration {
+        self.passes.iter().sum()
+    }
+
+    pub fn pairs_per_second(&self) -> f64 {
+        (self.pairs as f64 * self.passes.len() as f64) / self.total().as_secs_f64()
+    }
+
+    pub fn tokens_per_second(&self) -> f64 {
+        (self.tokens as f64 * self.passes.len() as f64) / self.total().as_secs_f64()
+    }
+
+    /// Nearest-rank percentile of the per-pair latencies
+    pub fn latency_percentile(&self, percentile: f64) -> Duration {
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = (percentile / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        self.latencies[rank.clamp(1, self.latencies.len()) - 1]
+    }
+}
+
+/// Score the corpus `warmup` times untimed, then `iterations` times while
+/// timing every pass and every pair
+///
+/// Pairs whose DP table would exceed the memory limit are refused up front.
+pub fn run(pairs: &[Pair], settings: &Settings, warmup: usize, iterations: u32) -> Result<BenchReport, PairError> {
+    let Settings {
+        tokenizer,
+        lcs_strategy,
+        variant,
+        ..
+    } = *settings;
+    let mut vocab = Vocab::with_variant(tokenizer, variant);
+    let mut tokens = 0;
+    for pair in pairs {
+        let candidate = vocab.tokenize(&pair.candidate);
+        tokens += summary::token_count(&candidate);
+        for reference in &pair.references {
+            let reference = vocab.tokenize(reference);
+            let (len1, len2) = (summary::longest_sentence(&candidate), summary::longest_sentence(&reference));
+            batch::check_table_memory(settings, &pair.id, len1, len2)?;
+            tokens += summary::token_count(&reference);
+        }
+    }
+
+    for _ in 0..warmup {
+        for pair in pairs {
+            for reference in &pair.references {
+                black_box(variant.calculate(&pair.candidate, reference, tokenizer, lcs_strategy));
+            }
+        }
+    }
+
+    let mut passes = Vec::with_capacity(iterations as usize);
+    let mut latencies = Vec::with_capacity(pairs.len() * iterations as usize);
+    for _ in 0..iterations {
+        let pass_start = Instant::now();
+        for pair in pairs {
+            let start = Instant::now();
+            for reference in &pair.references {
+                black_box(variant.calculate(&pair.candidate, reference, tokenizer, lcs_strategy));
+            }
+            latencies.push(start.elapsed());
+        }
+        passes.push(pass_start.elapsed());
+    }
+    latencies.sort_unstable();
+
+    Ok(BenchReport {
+        pairs: pairs.len(),
+        tokens,
+        warmup,
+        iterations,
+        strategy: lcs_strategy.name(),
+        passes,
+        latencies,
+    })
+}
+
+pub fn print_report(report: &BenchReport, signature: &str) {
+    let mean_pass = report.total() / report.iterations;
+    let min_pass = report.passes.iter().min().copied().unwrap_or_default();
+    let max_pass = report.passes.iter().max().copied().unwrap_or_default();
+
+    println!("=== ROUGE-L Benchmark ===\n");
+    println!("Signature:   {}", signature);
+    println!(
+        "Corpus:      {} pairs, {} tokens ({} warmup + {} timed passes)",
+        report.pairs, report.tokens, report.warmup, report.iterations
+    );
+    println!("LCS:         {}", report.strategy);
+    println!(
+        "Throughput:  {:.1} pairs/s, {:.0} tokens/s",
+        report.pairs_per_second(),
+        report.tokens_per_second()
+    );
+    println!(
+        "Pass time:   mean {:?}, min {:?}, max {:?}",
+        mean_pass, min_pass, max_pass
+    );
+    println!(
+        "Per pair:    p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+        report.latency_percentile(50.0),
+        report.latency_percentile(90.0),
+        report.latency_percentile(99.0),
+        report.latency_percentile(100.0)
+    );
+}
@@ -0,0 +1,75 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+use crate::batch::{ScoredPair, SummaryBuilder};
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+use crate::input::Pair;
+use crate::output;
+
+/// Property the pairs are grouped by with --breakdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Breakdown {
+    /// Reference length in tokens, in the ranges ending at --length-buckets
+    Length,
+}
+
+/// Pairs sharing a value of the breakdown property, with their averages
+pub struct Group {
+    pub label: String,
+    pub summary: SummaryBuilder,
+}
+
+/// Group the scored pairs by the length of their reference in tokens, into
+/// ranges ending at each of `edges` and one past the last
+///
+/// A pair with several references counts their mean length, rounded down.
+pub fn by_reference_length(pairs: &[Pair], scores: &[ScoredPair], settings: &Settings, edges: &[usize]) -> Vec<Group> {
+    let mut edges = edges.to_vec();
+    edges.sort_unstable();
+    edges.dedup();
+    let mut groups: Vec<Group> = Vec::with_capacity(edges.len() + 1);
+    let mut low = 0;
+    for &high in &edges {
+        groups.push(Group {
+            label: format!("{}-{}", low, high),
+            summary: SummaryBuilder::default(),
+        });
+        low = high + 1;
+    }
+    groups.push(Group {
+        label: format!("{}+", low),
+        summary: SummaryBuilder::default(),
+    });
+
+    for scored in scores {
+        let references = &pairs[scored.index].references;
+        let tokens: usize = references.iter().map(|text| settings.tokenizer.tokenize(text).len()).sum();
+        let length = tokens / references.len();
+        let group = edges.partition_point(|&edge| edge < length);
+        groups[group].summary.add(&scored.result);
+    }
+    groups
+}
+
+/// Print the number of pairs and the averages of every group, one row each
+pub fn write_table(out: &mut dyn Write, heading: &str, groups: &[Group], score_format: &ScoreFormat) -> io::Result<()> {
+    let mut rows = vec![[heading, "Pairs", "F-Measure", "Precision", "Recall"].map(String::from)];
+    for group in groups {
+        let summary = group.summary.finish();
+        let mean = |value| match summary.count {
+            0 => "-".to_string(),
+            _ => score_format.format(value),
+        };
+        rows.push([
+            group.label.clone(),
+            summary.count.to_string(),
+            mean(summary.mean.f_measure),
+            mean(summary.mean.precision),
+            mean(summary.mean.recall),
+        ]);
+    }
+    writeln!(out, "\nBreakdown by {}", heading.to_lowercase())?;
+    output::write_columns(out, &rows, 1)
+}
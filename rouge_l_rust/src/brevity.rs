@@ -0,0 +1,140 @@
+use std::io::{self, Write};
+
+use crate::batch::{ScoredPair, SummaryBuilder};
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+use crate::input::Pair;
+use crate::output;
+
+/// Candidates under this share of their reference length count as short
+const SHORT: f64 = 0.5;
+/// Candidates over this multiple of their reference length count as long
+const LONG: f64 = 2.0;
+/// Mean length ratio beyond which, or under whose inverse, a gap between
+/// recall and precision is put down to length
+const BIAS_RATIO: f64 = 1.5;
+/// Gap between mean recall and mean precision that counts as lopsided
+const BIAS_GAP: f64 = 0.1;
+
+/// How candidate lengths compare with their references' over a batch
+pub struct Brevity {
+    /// Pairs with a reference of at least one token
+    pub pairs: usize,
+    /// Mean over pairs of the candidate length over the reference length
+    pub mean_ratio: f64,
+    /// Candidate tokens over reference tokens of the whole batch
+    pub corpus_ratio: f64,
+    /// Averages of the short, matched and long candidates
+    pub short: SummaryBuilder,
+    pub matched: SummaryBuilder,
+    pub long: SummaryBuilder,
+    /// Averages of every pair
+    pub all: SummaryBuilder,
+}
+
+impl Brevity {
+    /// Why the averages may owe more to candidate length than to content:
+    /// recall well above precision with candidates much longer than their
+    /// references, or the reverse with much shorter ones
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.pairs == 0 {
+            return warnings;
+        }
+        let mean = self.all.finish().mean;
+        if self.mean_ratio > BIAS_RATIO && mean.recall - mean.precision > BIAS_GAP {
+            warnings.push(format!(
+                "recall ({:.4}) is well above precision ({:.4}) and candidates are {:.2}x as long as their \
+                 references: high recall may come from long candidates",
+                mean.recall, mean.precision, self.mean_ratio
+            ));
+        }
+        if self.mean_ratio < 1.0 / BIAS_RATIO && mean.precision - mean.recall > BIAS_GAP {
+            warnings.push(format!(
+                "precision ({:.4}) is well above recall ({:.4}) and candidates are {:.2}x as long as their \
+                 references: high precision may come from short candidates",
+                mean.precision, mean.recall, self.mean_ratio
+            ));
+        }
+        warnings
+    }
+}
+
+/// Compare the length of every scored candidate with its reference's, in
+/// tokens, and average the scores of short, matched and long candidates
+///
+/// A pair with several references counts their mean length, rounded down;
+/// pairs whose references have no tokens are left out.
+pub fn analyze(pairs: &[Pair], scores: &[ScoredPair], settings: &Settings) -> Brevity {
+    let mut brevity = Brevity {
+        pairs: 0,
+        mean_ratio: 0.0,
+        corpus_ratio: 0.0,
+        short: SummaryBuilder::default(),
+        matched: SummaryBuilder::default(),
+        long: SummaryBuilder::default(),
+        all: SummaryBuilder::default(),
+    };
+    let (mut ratios, mut candidate_tokens, mut reference_tokens) = (0.0, 0, 0);
+    for scored in scores {
+        let pair = &pairs[scored.index];
+        let candidate = settings.tokenizer.tokenize(&pair.candidate).len();
+        let tokens: usize = pair.references.iter().map(|text| settings.tokenizer.tokenize(text).len()).sum();
+        let reference = tokens / pair.references.len();
+        if reference == 0 {
+            continue;
+        }
+        let ratio = candidate as f64 / reference as f64;
+        let group = match ratio {
+            ratio if ratio < SHORT => &mut brevity.short,
+            ratio if ratio > LONG => &mut brevity.long,
+            _ => &mut brevity.matched,
+        };
+        group.add(&scored.result);
+        brevity.all.add(&scored.result);
+        brevity.pairs += 1;
+        ratios += ratio;
+        candidate_tokens += candidate;
+        reference_tokens += reference;
+    }
+    if brevity.pairs > 0 {
+        brevity.mean_ratio = ratios / brevity.pairs as f64;
+        brevity.corpus_ratio = candidate_tokens as f64 / reference_tokens as f64;
+    }
+    brevity
+}
+
+/// Print the length ratios, the averages of short, matched and long
+/// candidates, and any length-bias warning
+pub fn write_report(out: &mut dyn Write, brevity: &Brevity, score_format: &ScoreFormat) -> io::Result<()> {
+    writeln!(
+        out,
+        "\nLength ratio over {} pairs (candidate / reference tokens): mean {:.2}, corpus {:.2}",
+        brevity.pairs, brevity.mean_ratio, brevity.corpus_ratio
+    )?;
+    let mut rows = vec![["Candidates", "Pairs", "F-Measure", "Precision", "Recall"].map(String::from)];
+    let groups = [
+        (format!("Short (< {}x)", SHORT), &brevity.short),
+        (format!("Matched ({}x-{}x)", SHORT, LONG), &brevity.matched),
+        (format!("Long (> {}x)", LONG), &brevity.long),
+    ];
+    for (label, group) in groups {
+        let summary = group.finish();
+        let mean = |value| match summary.count {
+            0 => "-".to_string(),
+            _ => score_format.format(value),
+        };
+        rows.push([
+            label,
+            summary.count.to_string(),
+            mean(summary.mean.f_measure),
+            mean(summary.mean.precision),
+            mean(summary.mean.recall),
+        ]);
+    }
+    output::write_columns(out, &rows, 1)?;
+    for warning in brevity.warnings() {
+        writeln!(out, "warning: {}", warning)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use rouge_l_rust::RougeLResult;
+
+/// Score of a pair as stored in the cache
+#[derive(Debug, Clone)]
+pub struct CachedScore {
+    pub candidate_tokens: usize,
+    pub truncated: bool,
+    pub result: RougeLResult,
+}
+
+/// Error raised while reading or writing the score cache
+#[derive(Debug)]
+pub enum CacheError {
+    Io(PathBuf, io::Error),
+    Corrupt { path: PathBuf, line: usize },
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(path, err) => write!(f, "cannot access cache {}: {}", path.display(), err),
+            CacheError::Corrupt { path, line } => {
+                write!(f, "{}:{}: corrupt cache entry (delete the file to rebuild it)", path.display(), line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+/// Scores memoized on disk by pair content and configuration
+///
+/// Each configuration signature gets its own append-only file in the cache
+/// directory, with one JSON line per pair: its candidate and reference texts,
+/// the candidate length and the exact bits of the three scores. Entries are
+/// looked up by [`content_hash`], which stays the same across toolchains, and
+/// only returned when their texts match, so a hash collision is a miss.
+#[derive(Debug)]
+pub struct ScoreCache {
+    path: PathBuf,
+    entries: HashMap<CacheKey, Entry>,
+    added: Vec<CacheKey>,
+    /// Length of the file up to a last line left incomplete, cut off before
+    /// appending
+    partial: Option<u64>,
+}
+
+/// `(candidate hash, references hash)` locating an entry
+type CacheKey = (u64, u64);
+
+/// A cached score with the texts it was computed from
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    candidate: String,
+    references: Vec<String>,
+    candidate_tokens: usize,
+    truncated: bool,
+    /// Bits of the F-measure, precision and recall
+    scores: [u64; 3],
+}
+
+impl Entry {
+    fn matches(&self, candidate: &str, references: &[String]) -> bool {
+        self.candidate == candidate && self.references == references
+    }
+}
+
+impl ScoreCache {
+    /// Load the cache for `signature` from `dir`, creating the directory if needed
+    pub fn open(dir: &Path, signature: &str) -> Result<ScoreCache, CacheError> {
+        fs::create_dir_all(dir).map_err(|e| CacheError::Io(dir.to_path_buf(), e))?;
+        let path = dir.join(format!("scores-{:016x}.jsonl", content_hash(signature.as_bytes())));
+
+        let mut entries = HashMap::new();
+        let mut partial = None;
+        match File::open(&path) {
+            Ok(file) => {
+                let mut reader = BufReader::new(file);
+                let (mut line, mut index, mut complete) = (String::new(), 0, 0);
+                loop {
+                    line.clear();
+                    let read = reader.read_line(&mut line).map_err(|e| CacheError::Io(path.clone(), e))?;
+                    if read == 0 {
+                        break;
+                    }
+                    index += 1;
+                    // An append cut short by an interrupted run is a miss,
+                    // dropped on the next save so new entries start a line
+                    let Some(entry) = line.strip_suffix('\n') else {
+                        partial = Some(complete);
+                        break;
+                    };
+                    let entry: Entry = serde_json::from_str(entry).map_err(|_| CacheError::Corrupt {
+                        path: path.clone(),
+                        line: index,
+                    })?;
+                    entries.insert(key(&entry.candidate, &entry.references), entry);
+                    complete += read as u64;
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(CacheError::Io(path, err)),
+        }
+        Ok(ScoreCache {
+            path,
+            entries,
+            added: Vec::new(),
+            partial,
+        })
+    }
+
+    /// The cached score of a candidate against its references
+    pub fn get(&self, candidate: &str, references: &[String]) -> Option<CachedScore> {
+        let entry = self
+            .entries
+            .get(&key(candidate, references))
+            .filter(|entry| entry.matches(candidate, references))?;
+        let [f_measure, precision, recall] = entry.scores.map(f64::from_bits);
+        Some(CachedScore {
+            candidate_tokens: entry.candidate_tokens,
+            truncated: entry.truncated,
+            result: RougeLResult::new(f_measure, precision, recall),
+        })
+    }
+
+    /// Remember a freshly computed score until the next [`ScoreCache::save`]
+    ///
+    /// A pair whose key is already taken by other texts is left uncached.
+    pub fn insert(&mut self, candidate: &str, references: &[String], score: CachedScore) {
+        let key = key(candidate, references);
+        if let hash_map::Entry::Vacant(entry) = self.entries.entry(key) {
+            let result = &score.result;
+            entry.insert(Entry {
+                candidate: candidate.to_string(),
+                references: references.to_vec(),
+                candidate_tokens: score.candidate_tokens,
+                truncated: score.truncated,
+                scores: [result.f_measure, result.precision, result.recall].map(f64::to_bits),
+            });
+            self.added.push(key);
+        }
+    }
+
+    /// Append the scores inserted since the last save to the cache file
+    pub fn save(&mut self) -> Result<(), CacheError> {
+        if self.added.is_empty() {
+            return Ok(());
+        }
+        let io_error = |e| CacheError::Io(self.path.clone(), e);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(io_error)?;
+        if let Some(len) = self.partial.take() {
+            file.set_len(len).map_err(io_error)?;
+        }
+        let mut out = BufWriter::new(file);
+        for key in &self.added {
+            serde_json::to_writer(&mut out, &self.entries[key]).map_err(|e| io_error(e.into()))?;
+            writeln!(out).map_err(io_error)?;
+        }
+        out.flush().map_err(io_error)?;
+        self.added.clear();
+        Ok(())
+    }
+}
+
+/// Key of a candidate and its references, hashing the references as their
+/// count followed by the length and bytes of each, so no two lists share an
+/// encoding
+fn key(candidate: &str, references: &[String]) -> CacheKey {
+    let mut encoded = (references.len() as u64).to_le_bytes().to_vec();
+    for reference in references {
+        encoded.extend((reference.len() as u64).to_le_bytes());
+        encoded.extend(reference.as_bytes());
+    }
+    (content_hash(candidate.as_bytes()), content_hash(&encoded))
+}
+
+/// 64-bit FNV-1a hash of some bytes
+///
+/// Unlike `DefaultHasher`, the value is fixed across Rust releases and
+/// platforms, so it can name and key data kept on disk.
+fn content_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes
+        .iter()
+        .fold(OFFSET_BASIS, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
@@ -0,0 +1,99 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::batch::{ScoreStats, SummaryBuilder};
+
+/// Error raised while saving or resuming from a checkpoint
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(PathBuf, io::Error),
+    Corrupt(PathBuf, serde_json::Error),
+    /// The checkpoint was saved for another input or other settings
+    Mismatch { path: PathBuf, what: &'static str },
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(path, err) => write!(f, "cannot access checkpoint {}: {}", path.display(), err),
+            CheckpointError::Corrupt(path, err) => write!(f, "corrupt checkpoint {}: {}", path.display(), err),
+            CheckpointError::Mismatch { path, what } => write!(
+                f,
+                "checkpoint {} was saved for a different {}; rerun without --resume to start over",
+                path.display(),
+                what
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+/// Progress of a `--stream` run, saved after every chunk of results so an
+/// interrupted run can pick up where it stopped
+///
+/// `position` counts the records read, malformed ones included; those before
+/// it have been scored and their results written, and the running summary
+/// and counts cover exactly those records, so a resumed run ends with the
+/// same averages as an uninterrupted one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub input: PathBuf,
+    pub signature: String,
+    pub position: usize,
+    pub summary: SummaryBuilder,
+    pub stats: ScoreStats,
+}
+
+impl Checkpoint {
+    /// A checkpoint for a run starting at the first record of `input`
+    pub fn new(input: &Path, signature: &str) -> Checkpoint {
+        Checkpoint {
+            input: absolute(input),
+            signature: signature.to_string(),
+            position: 0,
+            summary: SummaryBuilder::default(),
+            stats: ScoreStats::default(),
+        }
+    }
+
+    /// Load the checkpoint at `path`, refusing one saved for another input
+    /// file or under other settings
+    pub fn resume(path: &Path, input: &Path, signature: &str) -> Result<Checkpoint, CheckpointError> {
+        let text = fs::read_to_string(path).map_err(|e| CheckpointError::Io(path.to_path_buf(), e))?;
+        let checkpoint: Checkpoint =
+            serde_json::from_str(&text).map_err(|e| CheckpointError::Corrupt(path.to_path_buf(), e))?;
+        let mismatch = |what| CheckpointError::Mismatch {
+            path: path.to_path_buf(),
+            what,
+        };
+        if checkpoint.input != absolute(input) {
+            return Err(mismatch("input file"));
+        }
+        if checkpoint.signature != signature {
+            return Err(mismatch("configuration signature"));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Replace the checkpoint file, through a temporary file renamed over it
+    /// so an interruption never leaves it half-written
+    pub fn save(&self, path: &Path) -> Result<(), CheckpointError> {
+        let mut temporary = OsString::from(path);
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        let text = serde_json::to_string(self).map_err(|e| CheckpointError::Corrupt(path.to_path_buf(), e))?;
+        fs::write(&temporary, text).map_err(|e| CheckpointError::Io(temporary.clone(), e))?;
+        fs::rename(&temporary, path).map_err(|e| CheckpointError::Io(path.to_path_buf(), e))
+    }
+}
+
+/// `path` made absolute, so a run resumed from another directory still matches
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
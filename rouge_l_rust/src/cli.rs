@@ -0,0 +1,1001 @@
+#[cfg(feature = "server")]
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
+
+use rouge_l_rust::lcs::LcsStrategy;
+use rouge_l_rust::summary::Variant;
+use rouge_l_rust::tokenize::Tokenizer;
+use rouge_l_rust::truncate::TruncationPolicy;
+
+use crate::breakdown::Breakdown;
+use crate::compare::SignificanceTest;
+use crate::filter::{Filter, ScoreField};
+use crate::format::RoundingMode;
+use crate::input::{InputFormat, OnError, TextFiles};
+use crate::oracle::OracleMetric;
+use crate::output::{OutputFormat, OutputTarget, BUCKET_WIDTH};
+use crate::rouge155::Rouge155Args;
+#[cfg(feature = "server")]
+use crate::server::SocketPath;
+use crate::verify::PythonRouge;
+
+/// ROUGE-L scoring; runs the built-in example suite when no subcommand is given
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Configuration file (defaults to ./rouge.toml when present)
+    #[arg(long, env = "ROUGE_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// ROUGE-L over whole texts, or ROUGE-Lsum over their sentences [default: rouge-l]
+    #[arg(long, value_enum, env = "ROUGE_VARIANT", global = true)]
+    pub variant: Option<Variant>,
+
+    /// How text is split into tokens [default: whitespace]
+    #[arg(long, value_enum, env = "ROUGE_TOKENIZER", global = true)]
+    pub tokenizer: Option<Tokenizer>,
+
+    /// Number of decimal places used when printing scores [default: 4]
+    #[arg(long, env = "ROUGE_PRECISION", global = true)]
+    pub precision: Option<usize>,
+
+    /// How scores are rounded to the requested precision [default: half-even]
+    #[arg(long, value_enum, env = "ROUGE_ROUNDING", global = true)]
+    pub rounding: Option<RoundingMode>,
+
+    /// LCS algorithm; fixed choices are for benchmarking and never change scores [default: auto]
+    #[arg(long, value_enum, env = "ROUGE_LCS_STRATEGY", global = true)]
+    pub lcs_strategy: Option<LcsStrategy>,
+
+    /// Most tokens scored per text, guarding against quadratic blowups on huge inputs [default: no limit]
+    #[arg(long, env = "ROUGE_MAX_TOKENS", global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_tokens: Option<u32>,
+
+    /// What to do with texts over --max-tokens [default: error]
+    #[arg(long, value_enum, env = "ROUGE_TRUNCATION", global = true)]
+    pub truncation: Option<TruncationPolicy>,
+
+    /// Largest DP table, in MiB, a pair may allocate before it is refused [default: 2048]
+    #[arg(long, value_name = "MIB", env = "ROUGE_MAX_TABLE_MEMORY", global = true)]
+    pub max_table_memory: Option<u64>,
+
+    /// Worker threads used to score pairs [default: one per CPU core]
+    #[cfg(feature = "parallel")]
+    #[arg(long, env = "ROUGE_THREADS", global = true, value_parser = clap::value_parser!(u32).range(1..))]
+    pub threads: Option<u32>,
+
+    /// OTLP/HTTP collector receiving the spans of the scoring stages, e.g. http://localhost:4318
+    #[cfg(feature = "otlp")]
+    #[arg(long, value_name = "URL", env = "OTEL_EXPORTER_OTLP_ENDPOINT", global = true)]
+    pub otlp_endpoint: Option<String>,
+
+    /// ROUGE-1.5.5 options, e.g. '-n 2 -m -c 95 -r 1000 -f A -p 0.5 -l 100', mapped onto
+    /// these settings; explicit flags still win
+    #[arg(long, global = true, allow_hyphen_values = true, value_name = "ARGS")]
+    pub rouge155_args: Option<Rouge155Args>,
+
+    /// Columns a text previewed on the console is cut down to [default: 80]
+    #[arg(
+        long,
+        value_name = "COLUMNS",
+        env = "ROUGE_PREVIEW_WIDTH",
+        global = true,
+        value_parser = clap::value_parser!(u16).range(4..)
+    )]
+    pub preview_width: Option<u16>,
+
+    /// TOML or JSON file of leveled examples to score instead of the built-in suite
+    #[arg(long)]
+    pub examples_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Score one candidate against one or more references given on the command line
+    Score(ScoreArgs),
+    /// Score a JSONL file, or every candidate file against the reference file of the same name
+    Batch(Box<BatchArgs>),
+    /// Like `batch`, but rescore and reprint whenever an input file changes
+    Watch(WatchArgs),
+    /// Measure scoring throughput and latency over a corpus
+    Bench(BenchArgs),
+    /// Score a submission CSV against a solution CSV joined on an id column, like a leaderboard
+    Grade(GradeArgs),
+    /// Compare every pair's scores with Python `rouge-score` or `py-rouge` and report the differences
+    Verify(VerifyArgs),
+    /// Rank the reference tokens candidates most often miss and the candidate tokens most often unmatched
+    Analyze(AnalyzeArgs),
+    /// Test whether a system's scores differ significantly from a baseline's on the same pairs
+    Compare(CompareArgs),
+    /// Measure how much of each candidate is copied verbatim from its source document
+    CopyRate(CopyRateArgs),
+    /// Correlate the scores of a batch input with human ratings of the same pairs
+    Correlate(CorrelateArgs),
+    /// Find the pairs of records whose texts are near-duplicates under ROUGE-L, or cluster them
+    Dedup(DedupArgs),
+    /// Join two saved result files on their ids and report the pairs that improved, regressed or stayed unchanged
+    DiffResults(DiffResultsArgs),
+    /// Score several system outputs against the same references and rank them by F-measure
+    Leaderboard(LeaderboardArgs),
+    /// Find the references of a pool closest to every candidate under ROUGE-L
+    Nearest(NearestArgs),
+    /// Score every line of one file against every line of another, writing the full matrix of F-measures
+    Matrix(MatrixArgs),
+    /// Pick the sampled candidate of each input with the highest expected ROUGE-L against the other samples
+    Mbr(MbrArgs),
+    /// Greedily pick the source sentences that best match each reference, building the extractive oracle
+    Oracle(OracleArgs),
+    /// Show the score trend of the runs recorded with --track, and the largest per-pair changes between two of them
+    #[cfg(feature = "sqlite")]
+    History(HistoryArgs),
+    /// Score pairs sent as JSON over HTTP with `POST /score` and `POST /batch`, or over gRPC
+    #[cfg(feature = "server")]
+    Serve(ServeArgs),
+    /// Answer JSON-RPC 2.0 `score` and `batch` requests read line by line from stdin, on stdout
+    Daemon,
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the roff man page to stdout, or write one page per subcommand
+    Man {
+        /// Directory to write `rouge_l_rust.1` and the subcommand pages into
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+/// Where batch inputs come from: a JSONL, Parquet or SQLite input, a pair of
+/// directories, line-per-document files or id-keyed files, SQuAD gold and
+/// prediction files, or a ROUGE-1.5.5 XML configuration
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// JSON Lines file with one candidate/reference record per line, a
+    /// Parquet file with one per row, or `sqlite:DB` to read them with --query
+    #[arg(
+        long,
+        conflicts_with_all = ["candidates", "squad_gold", "candidate_lines", "candidate_file", "rouge155_config"]
+    )]
+    #[cfg_attr(
+        not(feature = "hf"),
+        arg(required_unless_present_any = [
+            "candidates", "squad_gold", "candidate_lines", "candidate_file", "rouge155_config"
+        ])
+    )]
+    #[cfg_attr(
+        feature = "hf",
+        arg(required_unless_present_any = [
+            "candidates", "squad_gold", "candidate_lines", "candidate_file", "rouge155_config", "hf_dataset"
+        ])
+    )]
+    pub input: Option<PathBuf>,
+
+    /// Layout of the --input file [default: parquet for *.parquet, else jsonl]
+    #[arg(long, value_enum, requires = "input")]
+    pub input_format: Option<InputFormat>,
+
+    /// Stop at the first malformed --input record, or skip it and log it to
+    /// --error-file [default: fail]
+    #[arg(long, value_enum, requires = "input")]
+    pub on_error: Option<OnError>,
+
+    /// JSON Lines file receiving the records skipped by --on-error skip [default: errors.jsonl]
+    #[arg(long, requires = "on_error")]
+    pub error_file: Option<PathBuf>,
+
+    /// SQL query returning one candidate/reference pair per row from a SQLite --input
+    #[cfg(feature = "sqlite")]
+    #[arg(long, requires = "input")]
+    pub query: Option<String>,
+
+    /// Directory holding one candidate text per file, or with the `archive`
+    /// feature a tar or zip archive, optionally as ARCHIVE::PATTERN (e.g. out.tar.gz::*.txt)
+    #[arg(
+        long,
+        requires = "references",
+        conflicts_with_all = ["squad_gold", "candidate_lines", "candidate_file", "rouge155_config"]
+    )]
+    pub candidates: Option<TextFiles>,
+
+    /// Directory or archive holding the reference texts, matched to candidates by file name
+    #[arg(long, requires = "candidates")]
+    pub references: Option<TextFiles>,
+
+    /// Text file with one candidate document per line
+    #[arg(long, requires = "reference_lines", conflicts_with_all = ["squad_gold", "candidate_file", "rouge155_config"])]
+    pub candidate_lines: Option<PathBuf>,
+
+    /// Text file with one reference document per line, paired with --candidate-lines by line number
+    #[arg(long, requires = "candidate_lines")]
+    pub reference_lines: Option<PathBuf>,
+
+    /// Score the lines both files have when their line counts differ, instead of failing
+    #[arg(long, requires = "candidate_lines")]
+    pub lenient: bool,
+
+    /// JSONL or CSV file of candidate records, each identified by --id-field
+    #[arg(long, requires = "reference_file", conflicts_with_all = ["squad_gold", "rouge155_config"])]
+    pub candidate_file: Option<PathBuf>,
+
+    /// JSONL or CSV file of reference records, joined to --candidate-file on --id-field
+    #[arg(long, requires = "candidate_file")]
+    pub reference_file: Option<PathBuf>,
+
+    /// SQuAD v1.1 or v2.0 dataset whose answers are the references of each question
+    #[arg(long, requires = "squad_predictions")]
+    pub squad_gold: Option<PathBuf>,
+
+    /// JSON object mapping SQuAD question ids to predicted answers
+    #[arg(long, requires = "squad_gold")]
+    pub squad_predictions: Option<PathBuf>,
+
+    /// ROUGE-1.5.5 XML evaluation configuration pairing peer summaries with model summaries
+    #[arg(long, conflicts_with = "squad_gold")]
+    pub rouge155_config: Option<PathBuf>,
+
+    /// Hugging Face Hub dataset holding the references: cnn_dailymail, xsum
+    /// or any repository id, e.g. abisee/cnn_dailymail
+    #[cfg(feature = "hf")]
+    #[arg(
+        long,
+        requires = "hf_predictions",
+        conflicts_with_all = [
+            "input", "candidates", "squad_gold", "candidate_lines", "candidate_file", "rouge155_config"
+        ]
+    )]
+    pub hf_dataset: Option<String>,
+
+    /// Config of the --hf-dataset [default: the benchmark's, or the dataset's only one]
+    #[cfg(feature = "hf")]
+    #[arg(long, requires = "hf_dataset")]
+    pub hf_config: Option<String>,
+
+    /// Split of the --hf-dataset scored [default: test]
+    #[cfg(feature = "hf")]
+    #[arg(long, requires = "hf_dataset")]
+    pub hf_split: Option<String>,
+
+    /// Predictions for the --hf-split, one per line in row order, or as .jsonl
+    /// or .csv records joined to the rows on --id-field
+    #[cfg(feature = "hf")]
+    #[arg(long, requires = "hf_dataset")]
+    pub hf_predictions: Option<PathBuf>,
+
+    /// Column of the --hf-dataset holding the reference summary [default: the benchmark's]
+    #[cfg(feature = "hf")]
+    #[arg(long, requires = "hf_dataset")]
+    pub hf_reference_column: Option<String>,
+
+    /// Column of the --hf-dataset identifying each row [default: id, else the row number]
+    #[cfg(feature = "hf")]
+    #[arg(long, requires = "hf_dataset")]
+    pub hf_id_column: Option<String>,
+
+    /// JSONL field or Parquet/SQLite column holding the candidate text; JSONL
+    /// fields starting with `/` are JSON Pointers, e.g. /outputs/0/text
+    #[arg(long, default_value = "candidate")]
+    pub candidate_field: String,
+
+    /// JSONL field (a string or a list of strings) or Parquet/SQLite column
+    /// holding the reference text
+    #[arg(long, default_value = "reference")]
+    pub reference_field: String,
+
+    /// JSONL field or Parquet/SQLite/CSV column identifying the record
+    /// (defaults to the line or row number when absent) and joining
+    /// --candidate-file to --reference-file
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+}
+
+impl InputArgs {
+    /// The `--input` path and its format, which a `sqlite:` prefix sets to
+    /// SQLite and is otherwise detected from the extension unless given
+    pub fn input_source(&self) -> Option<(&Path, InputFormat)> {
+        let input = self.input.as_deref()?;
+        #[cfg(feature = "sqlite")]
+        if let Some(database) = input.to_str().and_then(|input| input.strip_prefix(crate::sqlite::PREFIX)) {
+            return Some((Path::new(database), InputFormat::Sqlite));
+        }
+        Some((input, self.input_format.unwrap_or_else(|| InputFormat::detect(input))))
+    }
+
+    /// The `--query` run on a SQLite input
+    pub fn query(&self) -> Option<&str> {
+        #[cfg(feature = "sqlite")]
+        return self.query.as_deref();
+        #[cfg(not(feature = "sqlite"))]
+        None
+    }
+
+    /// Files or directories the inputs are read from
+    pub fn paths(&self) -> Vec<&Path> {
+        let input = self.input_source().map(|(path, _)| path);
+        let dirs = [&self.candidates, &self.references].into_iter().flatten().map(TextFiles::path);
+        #[cfg(feature = "hf")]
+        let predictions = self.hf_predictions.as_deref();
+        #[cfg(not(feature = "hf"))]
+        let predictions = None;
+        input
+            .into_iter()
+            .chain(dirs)
+            .chain(predictions)
+            .chain(
+                [
+                    &self.candidate_lines,
+                    &self.reference_lines,
+                    &self.candidate_file,
+                    &self.reference_file,
+                    &self.squad_gold,
+                    &self.squad_predictions,
+                    &self.rouge155_config,
+                ]
+                .into_iter()
+                .flatten()
+                .map(PathBuf::as_path),
+            )
+            .collect()
+    }
+}
+
+/// Sorting, filtering and caching applied to per-pair results before they are printed
+#[derive(Debug, Args)]
+pub struct SelectArgs {
+    /// Sort results by this field instead of keeping input order
+    #[arg(long, value_enum)]
+    pub sort_by: Option<ScoreField>,
+
+    /// Sort from highest to lowest
+    #[arg(long, requires = "sort_by")]
+    pub descending: bool,
+
+    /// Only keep results matching `FIELD OP VALUE`, e.g. 'f < 0.3' (repeatable)
+    #[arg(long = "filter", value_name = "EXPR")]
+    pub filters: Vec<Filter>,
+
+    /// Directory caching scores by pair content and settings, so reruns only score changed pairs
+    #[arg(long, env = "ROUGE_CACHE_DIR")]
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// How batch results are printed
+#[derive(Debug, Args)]
+pub struct OutputArgs {
+    /// Output format [default: text]
+    #[arg(long, value_enum, env = "ROUGE_OUTPUT_FORMAT")]
+    pub format: Option<OutputFormat>,
+
+    /// Also write the results to a file as FORMAT:PATH, e.g. ndjson:scores.jsonl (repeatable)
+    #[arg(long = "output", value_name = "FORMAT:PATH")]
+    pub outputs: Vec<OutputTarget>,
+
+    /// Also write an HTML report of the results to this file, like --output html:PATH
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+
+    /// Also write a page per pair into a directory beside the --report, e.g.
+    /// report_pairs/, showing the candidate and reference side by side with
+    /// their LCS highlighted, and link the report's pairs to them
+    #[arg(long, requires = "report")]
+    pub report_details: bool,
+
+    /// Print an ASCII histogram of the F-measures after the results
+    #[arg(long)]
+    pub histogram: bool,
+
+    /// Width of the F-measure buckets counted by --histogram, the JSON
+    /// summary and the HTML report, in (0, 1]
+    #[arg(long, default_value_t = BUCKET_WIDTH)]
+    pub bucket_width: f64,
+
+    /// Print the mean, standard deviation, min, quartiles and max of every
+    /// score after the results
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Also print the number of pairs and the averages of each group of
+    /// pairs, e.g. with `length` of each range of reference lengths
+    #[arg(long, value_enum)]
+    pub breakdown: Option<Breakdown>,
+
+    /// Token counts ending the reference length ranges of --breakdown length
+    #[arg(long, value_delimiter = ',', default_values_t = [10, 25, 50, 100, 200], requires = "breakdown")]
+    pub length_buckets: Vec<usize>,
+
+    /// Also print how long candidates are against their references, the
+    /// averages of short and long candidates, and a warning when recall or
+    /// precision looks driven by length
+    #[arg(long)]
+    pub brevity: bool,
+
+    /// Also write the tokens of every pair's LCS to this JSON Lines file, as
+    /// [candidate_index, reference_index, token] triples
+    #[arg(long, value_name = "PATH")]
+    pub export_alignments: Option<PathBuf>,
+
+    /// Also write the edit script turning every candidate into its reference
+    /// to this JSON Lines file, as keep/delete/insert runs of tokens
+    #[arg(long, value_name = "PATH")]
+    pub export_edits: Option<PathBuf>,
+
+    /// Also write how much every reference sentence adds to ROUGE-Lsum to
+    /// this JSON Lines file, showing the sentences the candidate misses;
+    /// needs --variant rouge-lsum
+    #[arg(long, value_name = "PATH")]
+    pub export_sentences: Option<PathBuf>,
+
+    /// Also store the per-pair scores in this table of the SQLite --input database
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "TABLE", requires = "query")]
+    pub write_scores: Option<String>,
+}
+
+impl OutputArgs {
+    /// Files to write results to besides the standard output, the --report last
+    pub fn targets(&self) -> Vec<OutputTarget> {
+        let report = self.report.iter().map(|path| OutputTarget {
+            format: OutputFormat::Html,
+            path: path.clone(),
+        });
+        self.outputs.iter().cloned().chain(report).collect()
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ScoreArgs {
+    /// Candidate text
+    pub candidate: String,
+
+    /// Reference texts; the candidate keeps its best score
+    #[arg(required = true)]
+    pub references: Vec<String>,
+
+    /// Show the candidate and the reference it matches best in two columns, aligned on their LCS
+    #[arg(long)]
+    pub side_by_side: bool,
+
+    /// Width of the side-by-side view in characters [default: $COLUMNS, else 80]
+    #[arg(long, requires = "side_by_side", value_parser = clap::value_parser!(u16).range(16..))]
+    pub width: Option<u16>,
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub select: SelectArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// Score a JSONL file in a bounded-memory pipeline, printing results as they are scored
+    #[arg(
+        long,
+        requires = "input",
+        conflicts_with_all = [
+            "sort_by", "histogram", "stats", "breakdown", "brevity", "cache_dir", "export_alignments", "export_edits",
+            "export_sentences", "report_details"
+        ]
+    )]
+    #[cfg_attr(feature = "sqlite", arg(conflicts_with = "write_scores"))]
+    pub stream: bool,
+
+    /// Save the progress of --stream to this file after every chunk of results
+    #[arg(long, value_name = "PATH", requires = "stream")]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Continue the --stream run saved in --checkpoint, printing only the remaining results
+    #[arg(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// Records held in memory at once by --stream
+    #[arg(long, default_value_t = 4096, requires = "stream", value_parser = clap::value_parser!(u32).range(1..))]
+    pub in_flight: u32,
+
+    /// Fail the run, after printing the results, when the mean F-measure is
+    /// below this [default: `[threshold] f-measure` in the config, if any]
+    #[arg(long, value_name = "SCORE", value_parser = unit_interval)]
+    pub min_f_measure: Option<f64>,
+
+    /// Fail the run when the mean precision is below this
+    #[arg(long, value_name = "SCORE", value_parser = unit_interval)]
+    pub min_precision: Option<f64>,
+
+    /// Fail the run when the mean recall is below this
+    #[arg(long, value_name = "SCORE", value_parser = unit_interval)]
+    pub min_recall: Option<f64>,
+
+    /// Browse the results in an interactive terminal dashboard
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with_all = ["format", "stream"])]
+    #[cfg_attr(feature = "sqlite", arg(conflicts_with = "write_scores"))]
+    pub tui: bool,
+
+    /// MLflow tracking server to log the averages to after the run, e.g. http://localhost:5000
+    #[cfg(feature = "mlflow")]
+    #[arg(long, value_name = "URL", requires = "mlflow_run_id")]
+    #[cfg_attr(feature = "tui", arg(conflicts_with = "tui"))]
+    pub mlflow_uri: Option<String>,
+
+    /// MLflow run the averages are logged to
+    #[cfg(feature = "mlflow")]
+    #[arg(long, value_name = "ID", env = "MLFLOW_RUN_ID")]
+    pub mlflow_run_id: Option<String>,
+
+    /// Also upload the per-pair results to the MLflow run as rouge_l/scores.jsonl
+    #[cfg(feature = "mlflow")]
+    #[arg(long, requires = "mlflow_uri", conflicts_with = "stream")]
+    pub mlflow_artifacts: bool,
+
+    /// Also write the averages as TensorBoard scalars to a new event file in this directory
+    #[arg(long, value_name = "DIR")]
+    #[cfg_attr(feature = "tui", arg(conflicts_with = "tui"))]
+    pub tb_logdir: Option<PathBuf>,
+
+    /// Training step the TensorBoard scalars are recorded at
+    #[arg(long, default_value_t = 0, requires = "tb_logdir")]
+    pub step: i64,
+
+    /// Weights & Biases project to log the averages, an F-measure histogram
+    /// and the worst pairs to after the run, through the Python `wandb` package
+    #[cfg(feature = "wandb")]
+    #[arg(long, value_name = "PROJECT", conflicts_with = "stream")]
+    #[cfg_attr(feature = "tui", arg(conflicts_with = "tui"))]
+    pub wandb_project: Option<String>,
+
+    /// W&B user or team owning --wandb-project [default: the logged-in user's]
+    #[cfg(feature = "wandb")]
+    #[arg(long, requires = "wandb_project")]
+    pub wandb_entity: Option<String>,
+
+    /// Existing W&B run to log to, e.g. the training run being evaluated;
+    /// without it a new run is created
+    #[cfg(feature = "wandb")]
+    #[arg(long, value_name = "ID", requires = "wandb_project")]
+    pub wandb_run_id: Option<String>,
+
+    /// Display name of a new W&B run
+    #[cfg(feature = "wandb")]
+    #[arg(long, requires = "wandb_project", conflicts_with = "wandb_run_id")]
+    pub wandb_name: Option<String>,
+
+    /// Lowest-scoring pairs logged in the W&B table of examples
+    #[cfg(feature = "wandb")]
+    #[arg(long, value_name = "K", default_value_t = 20)]
+    pub wandb_worst: usize,
+
+    /// Python interpreter with wandb installed, e.g. venv/bin/python
+    #[cfg(feature = "wandb")]
+    #[arg(long, env = "ROUGE_PYTHON", default_value = "python3")]
+    pub wandb_python: String,
+
+    /// Record the averages and per-pair scores of the run in this SQLite run registry, created if needed
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "PATH", conflicts_with = "stream")]
+    #[cfg_attr(feature = "tui", arg(conflicts_with = "tui"))]
+    pub track: Option<PathBuf>,
+
+    /// Name of the run in --track, e.g. a model version; recording a tag again replaces its run
+    #[cfg(feature = "sqlite")]
+    #[arg(long, requires = "track")]
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub select: SelectArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Untimed passes over the corpus before measuring
+    #[arg(long, default_value_t = 3)]
+    pub warmup: usize,
+
+    /// Timed passes over the corpus
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    pub iterations: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct GradeArgs {
+    /// CSV file holding the reference text of every row
+    #[arg(long)]
+    pub solution: PathBuf,
+
+    /// CSV file holding one prediction for every solution row
+    #[arg(long)]
+    pub submission: PathBuf,
+
+    /// Column joining submission rows to solution rows
+    #[arg(long, default_value = "id")]
+    pub id_column: String,
+
+    /// Column holding the text in both files
+    #[arg(long, default_value = "target")]
+    pub target_column: String,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Python implementation to compare against
+    #[arg(long, value_enum, default_value_t)]
+    pub against: PythonRouge,
+
+    /// Python interpreter with that implementation installed, e.g. venv/bin/python
+    #[arg(long, env = "ROUGE_PYTHON", default_value = "python3")]
+    pub python: String,
+
+    /// Largest difference in any score still counted as a match
+    #[arg(long, default_value_t = 1e-9)]
+    pub tolerance: f64,
+
+    /// Mismatching pairs listed in the report
+    #[arg(long, default_value_t = 20)]
+    pub show: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct AnalyzeArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Tokens listed in each table
+    #[arg(long, default_value_t = 20)]
+    pub top: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    /// JSONL or Parquet file of the baseline's pairs
+    pub baseline: PathBuf,
+
+    /// JSONL or Parquet file of the compared system's pairs, joined to the
+    /// baseline's on --id-field
+    pub system: PathBuf,
+
+    /// Significance test run on the per-pair score differences
+    #[arg(long, value_enum, default_value_t)]
+    pub test: SignificanceTest,
+
+    /// Bootstrap resamples, or random sign flips with --test randomization
+    #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+    pub resamples: u32,
+
+    /// Seed of the resamples or sign flips; the same seed always gives the same intervals and p-values
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+
+    /// Confidence level of the intervals, in percent
+    #[arg(long, default_value_t = 95.0)]
+    pub confidence: f64,
+
+    /// Field or column holding the candidate text
+    #[arg(long, default_value = "candidate")]
+    pub candidate_field: String,
+
+    /// Field (a string or a list of strings) or column holding the reference text
+    #[arg(long, default_value = "reference")]
+    pub reference_field: String,
+
+    /// Field or column identifying the record (defaults to the line or row number when absent)
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+}
+
+#[derive(Debug, Args)]
+pub struct CopyRateArgs {
+    /// JSONL or CSV file of records holding a candidate and the source document it was written from
+    #[arg(long)]
+    pub input: PathBuf,
+
+    /// Field (a string or a list of strings) or column holding the source document
+    #[arg(long, default_value = "source")]
+    pub source_field: String,
+
+    /// Field or column holding the candidate text
+    #[arg(long, default_value = "candidate")]
+    pub candidate_field: String,
+
+    /// Field or column identifying the record; records without one are numbered from 1
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+
+    /// Shortest run of tokens shared with the source that counts as copied
+    #[arg(long, value_name = "TOKENS", default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    pub min_length: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct CorrelateArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// CSV file with a header and one human rating per pair
+    #[arg(long)]
+    pub human: PathBuf,
+
+    /// Column of --human holding the id of the rated pair
+    #[arg(long, default_value = "id")]
+    pub human_id_column: String,
+
+    /// Column of --human holding the rating, a number
+    #[arg(long, default_value = "rating")]
+    pub rating_column: String,
+}
+
+#[derive(Debug, Args)]
+pub struct DedupArgs {
+    /// JSONL or CSV file of records, one text each
+    #[arg(long)]
+    pub input: PathBuf,
+
+    /// Field or column holding the text; a list of strings is joined one per line
+    #[arg(long, default_value = "text")]
+    pub text_field: String,
+
+    /// Field or column identifying the record
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+
+    /// Lowest F-measure at which two texts count as near-duplicates
+    #[arg(long, default_value_t = 0.8)]
+    pub threshold: f64,
+
+    /// Print the cluster of every record instead of the pairs, linking records through chains of near-duplicates
+    #[arg(long)]
+    pub clusters: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffResultsArgs {
+    /// Results of the earlier run, as written by `batch` with --format json, ndjson or csv
+    pub old: PathBuf,
+
+    /// Results of the later run, joined to the old ones on their ids
+    pub new: PathBuf,
+
+    /// Largest improvements and regressions listed
+    #[arg(long, default_value_t = 10)]
+    pub top: usize,
+
+    /// F-measure change up to which a pair counts as unchanged
+    #[arg(long, value_name = "DELTA", default_value_t = 0.0)]
+    pub tolerance: f64,
+
+    /// JSONL or CSV input the old results were scored from, to show the texts of the largest regressions
+    #[arg(long, value_name = "PATH")]
+    pub old_input: Option<PathBuf>,
+
+    /// JSONL or CSV input the new results were scored from, to show the texts of the largest regressions
+    #[arg(long, value_name = "PATH")]
+    pub new_input: Option<PathBuf>,
+
+    /// Field or column of the inputs holding the candidate text
+    #[arg(long, default_value = "candidate")]
+    pub candidate_field: String,
+
+    /// Field (a string or a list of strings) or column of the inputs holding the reference text
+    #[arg(long, default_value = "reference")]
+    pub reference_field: String,
+
+    /// Field or column of the inputs identifying the record
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+}
+
+#[derive(Debug, Args)]
+pub struct LeaderboardArgs {
+    /// JSONL or CSV files of candidates, one per system; a file name may hold `*` and `?`, as in 'runs/*.jsonl'
+    #[arg(long, required = true, num_args = 1..)]
+    pub systems: Vec<String>,
+
+    /// JSONL or CSV file of references, joined to every system's candidates on --id-field
+    #[arg(long)]
+    pub references: PathBuf,
+
+    /// Field or column holding the candidate text
+    #[arg(long, default_value = "candidate")]
+    pub candidate_field: String,
+
+    /// Field (a string or a list of strings) or column holding the reference text
+    #[arg(long, default_value = "reference")]
+    pub reference_field: String,
+
+    /// Field or column identifying the record in both files
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+}
+
+#[derive(Debug, Args)]
+pub struct MatrixArgs {
+    /// Text file with one row text per line, scored as the candidate
+    #[arg(long)]
+    pub rows: PathBuf,
+
+    /// Text file with one column text per line, scored as the reference
+    #[arg(long)]
+    pub cols: PathBuf,
+
+    /// Write the matrix to this file instead of the standard output, as Parquet when it ends in .parquet
+    #[arg(long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Scores held in memory at once, in MiB; rows are scored and written in chunks of this size
+    #[arg(long, value_name = "MIB", default_value_t = 64, value_parser = clap::value_parser!(u32).range(1..))]
+    pub chunk_memory: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct MbrArgs {
+    /// JSONL file of records holding a list of sampled candidates each
+    #[arg(long)]
+    pub input: PathBuf,
+
+    /// Field holding the list of samples
+    #[arg(long, default_value = "candidate")]
+    pub candidate_field: String,
+
+    /// Field identifying the record
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+}
+
+#[derive(Debug, Args)]
+pub struct NearestArgs {
+    /// JSONL or CSV file of candidate records
+    #[arg(long)]
+    pub candidates: PathBuf,
+
+    /// JSONL or CSV file of the reference pool searched for every candidate
+    #[arg(long)]
+    pub references: PathBuf,
+
+    /// Field or column holding the candidate text
+    #[arg(long, default_value = "candidate")]
+    pub candidate_field: String,
+
+    /// Field or column holding the reference text; a list of strings is joined one per line
+    #[arg(long, default_value = "reference")]
+    pub reference_field: String,
+
+    /// Field or column identifying the record in both files
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+
+    /// References listed for every candidate
+    #[arg(long, value_name = "K", default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    pub top: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct OracleArgs {
+    /// JSONL or CSV file of records holding a source document and its reference summary
+    #[arg(long)]
+    pub input: PathBuf,
+
+    /// Read the references from this file instead, joined to the sources on --id-field
+    #[arg(long, value_name = "PATH")]
+    pub references: Option<PathBuf>,
+
+    /// Field or column holding the source document, one sentence per line or between <s> tags
+    #[arg(long, default_value = "source")]
+    pub source_field: String,
+
+    /// Field (a string or a list of strings) or column holding the reference summary
+    #[arg(long, default_value = "reference")]
+    pub reference_field: String,
+
+    /// Field or column identifying the record
+    #[arg(long, default_value = "id")]
+    pub id_field: String,
+
+    /// Score the picked sentences are chosen to maximize
+    #[arg(long, value_enum, default_value_t)]
+    pub metric: OracleMetric,
+
+    /// Pick at most this many sentences [default: until no sentence raises the score]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_sentences: Option<u32>,
+}
+
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// Run registry written by `batch --track`
+    pub database: PathBuf,
+
+    /// Tag of the earlier run to list the largest per-pair changes from
+    #[arg(long, value_name = "TAG", requires = "to")]
+    pub from: Option<String>,
+
+    /// Tag of the later run to list the largest per-pair changes to
+    #[arg(long, value_name = "TAG", requires = "from")]
+    pub to: Option<String>,
+
+    /// Pairs listed with --from and --to
+    #[arg(long, value_name = "K", default_value_t = 10, requires = "from")]
+    pub top: usize,
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to listen on; use 0.0.0.0 to accept connections from other hosts
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: IpAddr,
+
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    pub port: u16,
+
+    /// Serve HTTP on a Unix socket instead of --host and --port, given as
+    /// unix://PATH (e.g. unix:///tmp/rouge.sock)
+    #[arg(long, value_name = "URL", conflicts_with = "port")]
+    pub listen: Option<SocketPath>,
+
+    /// Most pairs accepted in one `POST /batch` request
+    #[arg(long, default_value_t = 10_000, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_batch_pairs: u32,
+
+    /// Largest request body accepted, in MiB
+    #[arg(long, value_name = "MIB", default_value_t = 16, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_request_size: u32,
+
+    /// `POST /batch` requests scored at once, each on every --threads worker;
+    /// later ones wait for a turn
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..))]
+    pub batch_concurrency: u32,
+
+    /// File of bearer tokens, one per line, that requests must carry in
+    /// their `Authorization` header; without it every request is served
+    #[arg(long, value_name = "FILE", env = "ROUGE_AUTH_TOKEN_FILE")]
+    pub auth_token_file: Option<PathBuf>,
+
+    /// Scoring requests a minute accepted from each client address, in
+    /// bursts of up to as many; further ones are refused until the budget refills
+    #[arg(long, value_name = "REQUESTS", value_parser = clap::value_parser!(u32).range(1..))]
+    pub rate_limit: Option<u32>,
+
+    /// Scoring requests and RPCs answered at once across all clients;
+    /// further ones are refused until one finishes
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_concurrent_requests: u32,
+
+    /// Also serve the gRPC service of proto/rouge_l.proto on this port
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    pub grpc_port: Option<u16>,
+}
+
+/// A score between 0 and 1
+fn unit_interval(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(score) if (0.0..=1.0).contains(&score) => Ok(score),
+        Ok(score) => Err(format!("must be between 0 and 1, got {}", score)),
+        Err(e) => Err(e.to_string()),
+    }
+}
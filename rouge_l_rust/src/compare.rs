@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+use crate::batch::ScoredPair;
+use crate::format::ScoreFormat;
+use crate::input::Pair;
+use crate::output;
+use crate::stats::{self, Distribution, Resampled};
+
+/// Pairs of a baseline and a system output on the same inputs
+pub struct Joined {
+    /// Baseline pairs with an id the system also has, in baseline order
+    pub baseline: Vec<Pair>,
+    /// The system pairs of the same ids, in the same order
+    pub system: Vec<Pair>,
+    /// Ids only the baseline has
+    pub baseline_only: Vec<String>,
+    /// Ids only the system has
+    pub system_only: Vec<String>,
+}
+
+/// Paired significance test run on the per-pair score differences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SignificanceTest {
+    /// Resample the pairs with replacement; gives confidence intervals and a
+    /// one-sided p-value
+    #[default]
+    Bootstrap,
+    /// Flip the sign of each difference at random (approximate
+    /// randomization); gives a two-sided p-value
+    Randomization,
+}
+
+impl SignificanceTest {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SignificanceTest::Bootstrap => "Paired bootstrap",
+            SignificanceTest::Randomization => "Approximate randomization",
+        }
+    }
+}
+
+/// Difference of one score between the two systems
+pub struct Difference {
+    pub name: &'static str,
+    pub baseline: f64,
+    pub system: f64,
+    /// Mean of the per-pair differences, system minus baseline
+    pub delta: f64,
+    /// Bootstrap confidence interval of `delta`
+    pub interval: Option<(f64, f64)>,
+    /// One-sided for the bootstrap: the share of resamples whose mean
+    /// difference is 0 or has the other sign than `delta`. Two-sided for
+    /// approximate randomization.
+    pub p_value: f64,
+}
+
+/// Keep the pairs of the ids both outputs have; the first record of a
+/// repeated id is used
+pub fn join(baseline: Vec<Pair>, system: Vec<Pair>) -> Joined {
+    let mut by_id: HashMap<String, Pair> = HashMap::new();
+    let mut system_order = Vec::new();
+    for pair in system {
+        if !by_id.contains_key(&pair.id) {
+            system_order.push(pair.id.clone());
+            by_id.insert(pair.id.clone(), pair);
+        }
+    }
+    let mut joined = Joined {
+        baseline: Vec::new(),
+        system: Vec::new(),
+        baseline_only: Vec::new(),
+        system_only: Vec::new(),
+    };
+    for pair in baseline {
+        match by_id.remove(&pair.id) {
+            Some(other) => {
+                joined.baseline.push(pair);
+                joined.system.push(other);
+            }
+            None => joined.baseline_only.push(pair.id),
+        }
+    }
+    joined.system_only = system_order.into_iter().filter(|id| by_id.contains_key(id)).collect();
+    joined
+}
+
+/// Compare every score of the system with the baseline's with `test` over
+/// the per-pair differences
+///
+/// `baseline` and `system` hold the scores of the same pairs in the same
+/// order. The `iters` resamples or sign flips are drawn from `seed`, so a
+/// comparison can be repeated exactly.
+pub fn compare(
+    baseline: &[ScoredPair],
+    system: &[ScoredPair],
+    test: SignificanceTest,
+    iters: usize,
+    seed: u64,
+    confidence: f64,
+) -> Vec<Difference> {
+    stats::SCORE_FIELDS
+        .into_iter()
+        .map(|(name, field)| {
+            let differences: Vec<f64> = baseline
+                .iter()
+                .zip(system)
+                .map(|(before, after)| field.value(after) - field.value(before))
+                .collect();
+            let mean = |scores: &[ScoredPair]| Distribution::of(scores, field).mean();
+            let (interval, p_value) = match test {
+                SignificanceTest::Bootstrap => {
+                    let resampled = stats::bootstrap(&differences, iters, seed);
+                    (Some(resampled.interval(confidence)), bootstrap_p_value(&resampled))
+                }
+                SignificanceTest::Randomization => (None, stats::approximate_randomization(&differences, iters, seed)),
+            };
+            Difference {
+                name,
+                baseline: mean(baseline),
+                system: mean(system),
+                delta: Distribution::new(differences).mean(),
+                interval,
+                p_value,
+            }
+        })
+        .collect()
+}
+
+/// Share of the resampled means that are 0 or on the other side of 0 than
+/// the observed mean
+fn bootstrap_p_value(resampled: &Resampled) -> f64 {
+    let against = resampled
+        .means
+        .iter()
+        .filter(|&&mean| if resampled.mean >= 0.0 { mean <= 0.0 } else { mean >= 0.0 })
+        .count();
+    against as f64 / resampled.means.len().max(1) as f64
+}
+
+/// Print the averages of both systems, their difference with its confidence
+/// interval, if any, and the p-value of `test`, one row per score
+pub fn write_report(
+    out: &mut dyn Write,
+    differences: &[Difference],
+    test: SignificanceTest,
+    pairs: usize,
+    confidence: f64,
+    score_format: &ScoreFormat,
+) -> io::Result<()> {
+    let score = |value| score_format.format(value);
+    let signed = |value: f64| if value < 0.0 { score(value) } else { format!("+{}", score(value)) };
+    let interval = format!("{}% CI", confidence);
+    let mut rows = vec![["", "Baseline", "System", "Delta", interval.as_str(), "p"].map(String::from)];
+    for difference in differences {
+        rows.push([
+            difference.name.to_string(),
+            score(difference.baseline),
+            score(difference.system),
+            signed(difference.delta),
+            match difference.interval {
+                Some((low, high)) => format!("[{}, {}]", signed(low), signed(high)),
+                None => "-".to_string(),
+            },
+            score(difference.p_value),
+        ]);
+    }
+    writeln!(out, "{} over {} pairs", test.name(), pairs)?;
+    output::write_columns(out, &rows, 1)
+}
@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Compression of an input file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Compression announced by the magic bytes at the start of `reader`,
+    /// which are left unread, or else by the extension of `path`
+    pub fn detect(path: &Path, reader: &mut impl BufRead) -> io::Result<Option<Compression>> {
+        let start = reader.fill_buf()?;
+        if start.starts_with(&[0x1f, 0x8b]) {
+            return Ok(Some(Compression::Gzip));
+        }
+        if start.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            return Ok(Some(Compression::Zstd));
+        }
+        Ok(match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Some(Compression::Gzip),
+            Some("zst") => Some(Compression::Zstd),
+            _ => None,
+        })
+    }
+
+    /// Decompress `reader` as it is read
+    #[cfg(feature = "compression")]
+    pub fn decoder(self, reader: impl BufRead + Send + 'static) -> io::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+        })
+    }
+
+    #[cfg(not(feature = "compression"))]
+    pub fn decoder(self, _reader: impl BufRead + Send + 'static) -> io::Result<Box<dyn Read + Send>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} input needs the `compression` feature", self.name()),
+        ))
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn name(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+/// Open a file for buffered reading, decompressing gzip and zstd files on the fly
+pub fn open(path: &Path) -> io::Result<Box<dyn BufRead + Send>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(match Compression::detect(path, &mut reader)? {
+        Some(compression) => Box::new(BufReader::new(compression.decoder(reader)?)),
+        None => Box::new(reader),
+    })
+}
+
+/// Read a whole file, decompressed, into a string
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    let mut text = String::new();
+    open(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use rouge_l_rust::lcs::LcsStrategy;
+use rouge_l_rust::summary::Variant;
+use rouge_l_rust::tokenize::Tokenizer;
+use rouge_l_rust::truncate::{TokenLimit, TruncationPolicy};
+
+use crate::batch::Bootstrap;
+use crate::cli::Cli;
+use crate::filter::Filter;
+use crate::format::{RoundingMode, ScoreFormat};
+use crate::output::OutputFormat;
+
+/// File picked up from the working directory when `--config` is not given
+pub const DEFAULT_CONFIG_FILE: &str = "rouge.toml";
+
+/// Contents of a `rouge.toml` evaluation configuration
+///
+/// Every value is optional; anything left out falls back to the built-in
+/// default, while `ROUGE_*` environment variables and command-line flags
+/// always win over the file. Values are checked against the same ranges as
+/// the matching flags when the file is loaded.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub variant: Option<Variant>,
+    /// Metrics scored, as variants; a run scores one, so this may list a
+    /// single variant, as an alternative to `variant`
+    pub metrics: Option<Vec<Variant>>,
+    pub tokenizer: Option<Tokenizer>,
+    pub lcs_strategy: Option<LcsStrategy>,
+    pub max_tokens: Option<u32>,
+    pub truncation: Option<TruncationPolicy>,
+    pub max_table_memory: Option<u64>,
+    #[cfg(feature = "parallel")]
+    pub threads: Option<u32>,
+    pub output: OutputConfig,
+    pub threshold: ThresholdConfig,
+}
+
+/// The `[output]` table of the configuration file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct OutputConfig {
+    pub format: Option<OutputFormat>,
+    pub precision: Option<usize>,
+    pub rounding: Option<RoundingMode>,
+    pub preview_width: Option<u16>,
+}
+
+/// The `[threshold]` table of the configuration file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ThresholdConfig {
+    /// Lowest mean F-measure, precision and recall a batch run may end
+    /// with, like --min-f-measure, --min-precision and --min-recall
+    pub f_measure: Option<f64>,
+    pub precision: Option<f64>,
+    pub recall: Option<f64>,
+    /// Per-pair conditions applied when no --filter is given
+    pub filter: Vec<Filter>,
+}
+
+/// Error raised while reading or parsing a configuration file
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    /// A value out of the range its flag accepts
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, err) => write!(f, "cannot read {}: {}", path.display(), err),
+            ConfigError::Parse(path, err) => write!(f, "invalid config {}: {}", path.display(), err),
+            ConfigError::Invalid(path, message) => write!(f, "invalid config {}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Read and parse a configuration file, then check its values
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        let config: Config = toml::from_str(&text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+        config.validate().map_err(|message| ConfigError::Invalid(path.to_path_buf(), message))?;
+        Ok(config)
+    }
+
+    /// Refuse the values the matching flags would: a zero `max-tokens` or
+    /// `threads`, a `preview-width` under 4, a threshold outside [0, 1], and
+    /// a metric list other than a single variant agreeing with `variant`
+    fn validate(&self) -> Result<(), String> {
+        if self.max_tokens == Some(0) {
+            return Err("max-tokens must be at least 1".to_string());
+        }
+        #[cfg(feature = "parallel")]
+        if self.threads == Some(0) {
+            return Err("threads must be at least 1".to_string());
+        }
+        if let Some(width) = self.output.preview_width.filter(|&width| width < 4) {
+            return Err(format!("[output] preview-width must be at least 4, got {}", width));
+        }
+        let threshold = &self.threshold;
+        let bounds = [
+            ("f-measure", threshold.f_measure),
+            ("precision", threshold.precision),
+            ("recall", threshold.recall),
+        ];
+        for (key, bound) in bounds {
+            if let Some(bound) = bound.filter(|bound| !(0.0..=1.0).contains(bound)) {
+                return Err(format!("[threshold] {} must be between 0 and 1, got {}", key, bound));
+            }
+        }
+        match self.metrics.as_deref() {
+            None => {}
+            Some([]) => return Err("metrics lists no variant".to_string()),
+            Some([metric]) => {
+                if let Some(variant) = self.variant.filter(|variant| variant != metric) {
+                    return Err(format!("metrics lists {} but variant is {}", metric.name(), variant.name()));
+                }
+            }
+            Some(metrics) => {
+                return Err(format!(
+                    "metrics lists {} variants, but a run scores one; run once per variant",
+                    metrics.len()
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// The variant set by `variant` or by the metric list
+    pub fn variant(&self) -> Option<Variant> {
+        self.variant.or_else(|| self.metrics.as_deref()?.first().copied())
+    }
+
+    /// Load the explicitly requested file, or `./rouge.toml` if it exists
+    pub fn discover(explicit: Option<&Path>) -> Result<Config, ConfigError> {
+        match explicit {
+            Some(path) => Config::load(path),
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => {
+                Config::load(Path::new(DEFAULT_CONFIG_FILE))
+            }
+            None => Ok(Config::default()),
+        }
+    }
+}
+
+/// Effective settings after layering defaults, the config file, `ROUGE_*`
+/// environment variables and CLI flags
+///
+/// clap already folds the environment into the parsed flags, so a value
+/// present on `Cli` came from either the command line or the environment.
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    pub variant: Variant,
+    pub tokenizer: Tokenizer,
+    pub score_format: ScoreFormat,
+    pub lcs_strategy: LcsStrategy,
+    pub token_limit: Option<TokenLimit>,
+    /// Largest DP table in MiB that a pair may allocate
+    pub max_table_memory: u64,
+    /// Confidence intervals of the ROUGE-1.5.5 report
+    pub bootstrap: Bootstrap,
+    /// Columns a text previewed on the console is cut down to
+    pub preview_width: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            variant: Variant::default(),
+            tokenizer: Tokenizer::default(),
+            score_format: ScoreFormat::default(),
+            lcs_strategy: LcsStrategy::default(),
+            token_limit: None,
+            max_table_memory: 2048,
+            bootstrap: Bootstrap::default(),
+            preview_width: 80,
+        }
+    }
+}
+
+impl Settings {
+    /// Layer the settings; options translated from `--rouge155-args` sit
+    /// between the config file and the other flags
+    pub fn resolve(cli: &Cli, config: &Config) -> Settings {
+        let defaults = Settings::default();
+        let rouge155 = cli.rouge155_args.as_ref();
+        Settings {
+            variant: cli
+                .variant
+                .or(rouge155.map(|args| args.variant))
+                .or(config.variant())
+                .unwrap_or(defaults.variant),
+            tokenizer: cli
+                .tokenizer
+                .or(rouge155.map(|args| args.tokenizer))
+                .or(config.tokenizer)
+                .unwrap_or(defaults.tokenizer),
+            score_format: ScoreFormat::new(
+                cli.precision
+                    .or(config.output.precision)
+                    .unwrap_or(defaults.score_format.precision),
+                cli.rounding
+                    .or(config.output.rounding)
+                    .unwrap_or(defaults.score_format.rounding),
+            ),
+            lcs_strategy: cli
+                .lcs_strategy
+                .or(config.lcs_strategy)
+                .unwrap_or(defaults.lcs_strategy),
+            token_limit: match (cli.max_tokens, rouge155.and_then(|args| args.token_limit)) {
+                (None, Some(limit)) => Some(TokenLimit {
+                    policy: cli.truncation.unwrap_or(limit.policy),
+                    ..limit
+                }),
+                (max_tokens, _) => max_tokens.or(config.max_tokens).map(|max_tokens| TokenLimit {
+                    max_tokens: max_tokens as usize,
+                    policy: cli.truncation.or(config.truncation).unwrap_or_default(),
+                }),
+            },
+            max_table_memory: cli
+                .max_table_memory
+                .or(config.max_table_memory)
+                .unwrap_or(defaults.max_table_memory),
+            bootstrap: rouge155.map_or(defaults.bootstrap, |args| args.bootstrap),
+            preview_width: cli
+                .preview_width
+                .or(config.output.preview_width)
+                .map_or(defaults.preview_width, usize::from),
+        }
+    }
+
+    /// sacrebleu-style description of everything that affects the scores,
+    /// e.g. `rouge-l|tok:whitespace|stem:no|case:lower|ver:0.1.0`, starting
+    /// with the variant and with a `max:N` component after the tokenizer when
+    /// a token limit is set
+    pub fn signature(&self) -> String {
+        let limit = self.token_limit.map(|limit| format!("|{}", limit.name())).unwrap_or_default();
+        format!(
+            "{}|tok:{}{}|stem:no|case:lower|ver:{}",
+            self.variant.name(),
+            self.tokenizer.name(),
+            limit,
+            env!("CARGO_PKG_VERSION")
+        )
+    }
+}
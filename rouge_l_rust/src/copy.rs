@@ -0,0 +1,111 @@
+//! How much of a candidate is copied verbatim from its source document,
+//! from the fragments they share as contiguous runs of tokens rather than as
+//! a subsequence
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::intern::Interner;
+use crate::summary::SENTENCE_BREAK;
+use crate::tokenize::Tokenizer;
+
+/// A run of consecutive candidate tokens found verbatim in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fragment {
+    /// Position of the run in the candidate
+    pub candidate: usize,
+    /// Position of the run in the source
+    pub source: usize,
+    pub len: usize,
+}
+
+/// Extractive statistics of a candidate against its source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CopyRate {
+    /// Share of the candidate tokens in fragments of at least the minimum
+    /// length
+    pub copy_rate: f64,
+    /// Share of the candidate tokens in any fragment, single shared tokens
+    /// included
+    pub coverage: f64,
+    /// Mean length of the fragment each candidate token falls in, 0 for
+    /// tokens in none; high when the candidate copies long passages
+    pub density: f64,
+    /// Tokens of the longest fragment
+    pub longest: usize,
+}
+
+/// Split the candidate greedily into the fragments it shares with the source:
+/// from each position, the longest run of tokens found verbatim anywhere in
+/// the source, the earliest on ties; tokens found nowhere are skipped
+///
+/// These are the extractive fragments of Grusky et al. (2018), the longest
+/// common substrings rather than the subsequence ROUGE-L aligns.
+pub fn fragments<T: Eq + Hash>(candidate: &[T], source: &[T]) -> Vec<Fragment> {
+    let mut positions: HashMap<&T, Vec<usize>> = HashMap::new();
+    for (j, token) in source.iter().enumerate() {
+        positions.entry(token).or_default().push(j);
+    }
+    let mut fragments = Vec::new();
+    let mut i = 0;
+    while i < candidate.len() {
+        let mut best: Option<Fragment> = None;
+        for &j in positions.get(&candidate[i]).into_iter().flatten() {
+            let len = candidate[i..].iter().zip(&source[j..]).take_while(|(a, b)| a == b).count();
+            if best.is_none_or(|best| len > best.len) {
+                best = Some(Fragment {
+                    candidate: i,
+                    source: j,
+                    len,
+                });
+            }
+        }
+        match best {
+            Some(fragment) => {
+                i += fragment.len;
+                fragments.push(fragment);
+            }
+            None => i += 1,
+        }
+    }
+    fragments
+}
+
+/// Copy statistics of a candidate of `tokens` tokens split into `fragments`,
+/// counting only fragments of at least `min_length` tokens in the copy rate
+pub fn from_fragments(fragments: &[Fragment], tokens: usize, min_length: usize) -> CopyRate {
+    if tokens == 0 {
+        return CopyRate {
+            copy_rate: 0.0,
+            coverage: 0.0,
+            density: 0.0,
+            longest: 0,
+        };
+    }
+    let n = tokens as f64;
+    let copied: usize = fragments.iter().filter(|f| f.len >= min_length).map(|f| f.len).sum();
+    let covered: usize = fragments.iter().map(|f| f.len).sum();
+    let squares: usize = fragments.iter().map(|f| f.len * f.len).sum();
+    CopyRate {
+        copy_rate: copied as f64 / n,
+        coverage: covered as f64 / n,
+        density: squares as f64 / n,
+        longest: fragments.iter().map(|f| f.len).max().unwrap_or(0),
+    }
+}
+
+/// Copy statistics of `candidate` against one or more source documents,
+/// tokenized with `tokenizer`; no fragment spans two sources
+pub fn copy_rate<S: AsRef<str>>(candidate: &str, sources: &[S], tokenizer: Tokenizer, min_length: usize) -> CopyRate {
+    let mut interner = Interner::new();
+    let candidate = interner.tokenize(tokenizer, candidate);
+    let mut source = Vec::new();
+    for (i, text) in sources.iter().enumerate() {
+        if i > 0 {
+            // Never a candidate token, so runs stop at the boundary
+            source.push(SENTENCE_BREAK);
+        }
+        source.extend(interner.tokenize(tokenizer, text.as_ref()));
+    }
+    from_fragments(&fragments(&candidate, &source), candidate.len(), min_length)
+}
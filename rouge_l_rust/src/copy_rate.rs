@@ -0,0 +1,75 @@
+use std::io::{self, Write};
+
+use serde_json::json;
+
+use rouge_l_rust::copy::{self, CopyRate};
+
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+use crate::input::Pair;
+use crate::output;
+
+/// Copy statistics of a corpus
+pub struct CorpusRate {
+    pub records: usize,
+    /// Means of the copy rate, coverage and density over the records, and
+    /// the longest fragment of any of them
+    pub mean: CopyRate,
+}
+
+/// Write the copy statistics of every record's candidate against its source
+/// documents, its references, as one JSON object per line, and return those
+/// of the whole corpus
+pub fn write_rates(
+    out: &mut dyn Write,
+    pairs: &[Pair],
+    min_length: usize,
+    settings: &Settings,
+) -> io::Result<CorpusRate> {
+    let mut total = CopyRate {
+        copy_rate: 0.0,
+        coverage: 0.0,
+        density: 0.0,
+        longest: 0,
+    };
+    let json_score = |value| output::json_score(value, &settings.score_format);
+    for pair in pairs {
+        let rate = copy::copy_rate(&pair.candidate, &pair.references, settings.tokenizer, min_length);
+        let line = json!({
+            "id": pair.id,
+            "copy_rate": json_score(rate.copy_rate),
+            "coverage": json_score(rate.coverage),
+            "density": json_score(rate.density),
+            "longest": rate.longest,
+        });
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+        total.copy_rate += rate.copy_rate;
+        total.coverage += rate.coverage;
+        total.density += rate.density;
+        total.longest = total.longest.max(rate.longest);
+    }
+    let n = pairs.len().max(1) as f64;
+    Ok(CorpusRate {
+        records: pairs.len(),
+        mean: CopyRate {
+            copy_rate: total.copy_rate / n,
+            coverage: total.coverage / n,
+            density: total.density / n,
+            longest: total.longest,
+        },
+    })
+}
+
+/// Print the corpus means and the longest copied run on one line
+pub fn write_summary(out: &mut dyn Write, corpus: &CorpusRate, score_format: &ScoreFormat) -> io::Result<()> {
+    writeln!(
+        out,
+        "copy-rate: over {} records, mean copy rate {}, coverage {}, density {}; longest copied run {} tokens",
+        corpus.records,
+        score_format.format(corpus.mean.copy_rate),
+        score_format.format(corpus.mean.coverage),
+        score_format.format(corpus.mean.density),
+        corpus.mean.longest
+    )
+}
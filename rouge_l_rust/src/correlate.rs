@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::batch::ScoredPair;
+use crate::format::ScoreFormat;
+use crate::input::{self, InputError};
+use crate::output;
+use crate::stats;
+
+/// Scored pairs joined to their human ratings
+pub struct Rated<'a> {
+    pub scores: Vec<&'a ScoredPair>,
+    pub ratings: Vec<f64>,
+    /// Ids of scored pairs without a rating
+    pub unrated: Vec<String>,
+    /// Ids of ratings without a scored pair
+    pub unscored: Vec<String>,
+}
+
+/// Read the `rating_column` of every row of a CSV file, keyed by its
+/// `id_column`
+pub fn read_ratings(path: &Path, id_column: &str, rating_column: &str) -> Result<Vec<(String, f64)>, InputError> {
+    input::read_csv_rows(path, id_column, rating_column)?
+        .into_iter()
+        .map(|(id, rating)| match rating.trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok((id, value)),
+            _ => Err(InputError::Invalid(
+                path.to_path_buf(),
+                format!("rating `{}` of id {} is not a number", rating, id),
+            )),
+        })
+        .collect()
+}
+
+/// Pair every score with the rating of the same id, in score order
+pub fn join<'a>(scores: &'a [ScoredPair], ratings: Vec<(String, f64)>) -> Rated<'a> {
+    let order: Vec<String> = ratings.iter().map(|(id, _)| id.clone()).collect();
+    let mut by_id: HashMap<String, f64> = ratings.into_iter().collect();
+    let mut rated = Rated {
+        scores: Vec::new(),
+        ratings: Vec::new(),
+        unrated: Vec::new(),
+        unscored: Vec::new(),
+    };
+    for scored in scores {
+        match by_id.remove(&scored.id) {
+            Some(rating) => {
+                rated.scores.push(scored);
+                rated.ratings.push(rating);
+            }
+            None => rated.unrated.push(scored.id.clone()),
+        }
+    }
+    rated.unscored = order.into_iter().filter(|id| by_id.contains_key(id)).collect();
+    rated
+}
+
+/// Print the Pearson, Spearman and Kendall tau correlations of every score
+/// with the human ratings, one row per score
+///
+/// A correlation that is undefined because a side is constant prints as `-`.
+pub fn write_report(out: &mut dyn Write, rated: &Rated, score_format: &ScoreFormat) -> io::Result<()> {
+    let correlation = |value: f64| match value.is_finite() {
+        true => score_format.format(value),
+        false => "-".to_string(),
+    };
+    let mut rows = vec![["", "Pearson", "Spearman", "Kendall tau"].map(String::from)];
+    for (name, field) in stats::SCORE_FIELDS {
+        let metric: Vec<f64> = rated.scores.iter().map(|scored| field.value(scored)).collect();
+        rows.push([
+            name.to_string(),
+            correlation(stats::pearson(&metric, &rated.ratings)),
+            correlation(stats::spearman(&metric, &rated.ratings)),
+            correlation(stats::kendall_tau(&metric, &rated.ratings)),
+        ]);
+    }
+    writeln!(out, "Correlation with human ratings over {} pairs", rated.ratings.len())?;
+    output::write_columns(out, &rows, 1)
+}
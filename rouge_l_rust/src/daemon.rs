@@ -0,0 +1,138 @@
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::batch;
+use crate::config::Settings;
+use crate::input::{self, Pair, RecordFields};
+use crate::output::{self, OutputFormat};
+
+// Error codes defined by JSON-RPC 2.0
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// A pair breaks the token or DP table limits, in the range JSON-RPC leaves
+/// to servers
+const PAIR_REFUSED: i64 = -32000;
+
+/// A JSON-RPC error object
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Answer JSON-RPC 2.0 requests, one per stdin line, with one response line
+/// each on stdout until stdin closes
+///
+/// `score` takes a record shaped like a line of a JSONL `--input` and
+/// returns its NDJSON result, and `batch` takes an array of such records and
+/// returns the JSON output of `batch`, like `POST /score` and `POST /batch`
+/// of `serve`. Notifications, requests without an id, are scored but not
+/// answered, and a line holding an array of requests gets an array of
+/// responses. Each response is flushed as soon as it is written, so a client
+/// can wait for it before sending the next request.
+pub fn run(settings: &Settings) -> io::Result<()> {
+    let signature = settings.signature();
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(Value::Array(requests)) if !requests.is_empty() => {
+                let responses: Vec<Value> = requests
+                    .iter()
+                    .filter_map(|request| answer(request, settings, &signature))
+                    .collect();
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            Ok(request) => answer(&request, settings, &signature),
+            Err(err) => Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, format!("invalid JSON: {}", err)),
+            )),
+        };
+        if let Some(response) = response {
+            serde_json::to_writer(&mut stdout, &response)?;
+            writeln!(stdout)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to one request, or none for a notification
+fn answer(request: &Value, settings: &Settings, signature: &str) -> Option<Value> {
+    let (Some(request), Some(method)) = (
+        request.as_object().filter(|request| request.get("jsonrpc") == Some(&json!("2.0"))),
+        request.get("method").and_then(Value::as_str),
+    ) else {
+        return Some(error_response(
+            request.get("id").cloned().unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, "expected a JSON-RPC 2.0 request object with a method"),
+        ));
+    };
+    let result = call(method, request.get("params"), settings, signature);
+    let id = request.get("id")?.clone();
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => error_response(id, err),
+    })
+}
+
+fn error_response(id: Value, err: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": err.code, "message": err.message },
+    })
+}
+
+fn call(method: &str, params: Option<&Value>, settings: &Settings, signature: &str) -> Result<Value, RpcError> {
+    let invalid_params = |message: String| RpcError::new(INVALID_PARAMS, message);
+    let (pairs, format) = match (method, params) {
+        ("score", Some(record @ Value::Object(_))) => (
+            vec![input::record_pair(record, &RecordFields::default(), 1).map_err(invalid_params)?],
+            OutputFormat::Ndjson,
+        ),
+        ("score", _) => return Err(invalid_params("expected a candidate/reference record".into())),
+        ("batch", Some(Value::Array(records))) => (
+            records
+                .iter()
+                .enumerate()
+                .map(|(index, record)| {
+                    input::record_pair(record, &RecordFields::default(), index + 1)
+                        .map_err(|message| invalid_params(format!("record {}: {}", index + 1, message)))
+                })
+                .collect::<Result<Vec<Pair>, RpcError>>()?,
+            OutputFormat::Json,
+        ),
+        ("batch", _) => return Err(invalid_params("expected an array of candidate/reference records".into())),
+        _ => return Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+    };
+    let (scores, _) =
+        batch::score_pairs(&pairs, settings, None, None).map_err(|err| RpcError::new(PAIR_REFUSED, err.to_string()))?;
+    let mut body = Vec::new();
+    output::write_results(
+        vec![(&mut body, format)],
+        &scores,
+        &settings.score_format,
+        signature,
+        settings.token_limit.is_some(),
+        settings.bootstrap,
+    )
+    .map_err(|err| RpcError::new(INTERNAL_ERROR, err.to_string()))?;
+    serde_json::from_slice(&body).map_err(|err| RpcError::new(INTERNAL_ERROR, err.to_string()))
+}
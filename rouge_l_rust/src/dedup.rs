@@ -0,0 +1,175 @@
+use std::io::{self, Write};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde_json::json;
+
+use rouge_l_rust::f_measure_bound;
+use rouge_l_rust::intern::Vocab;
+use rouge_l_rust::lcs::Scratch;
+use rouge_l_rust::summary;
+
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+use crate::output;
+
+/// Two records whose texts reach the similarity threshold
+pub struct Duplicate {
+    /// Positions of the records in the input, the earlier first
+    pub first: usize,
+    pub second: usize,
+    pub f_measure: f64,
+}
+
+/// How many comparisons the bounds ruled out
+#[derive(Debug, Default)]
+pub struct DedupStats {
+    /// Pairs of records in the corpus
+    pub comparisons: usize,
+    /// Pairs ruled out by their lengths alone
+    pub by_length: usize,
+    /// Pairs ruled out by their bag-of-words overlap
+    pub by_overlap: usize,
+}
+
+/// Every pair of `texts` whose F-measure is at least `threshold`, most
+/// similar first
+///
+/// Only pairs whose lengths and bag-of-words overlap allow reaching the
+/// threshold are scored: the F-measure never exceeds `2 × shorter / (m + n)`,
+/// so with the texts in order of length each one is compared to the
+/// following ones until they grow too long. The earlier record of a pair is
+/// scored as the candidate. With the `parallel` feature the records are
+/// compared on the rayon thread pool.
+pub fn near_duplicates(texts: &[String], threshold: f64, settings: &Settings) -> (Vec<Duplicate>, DedupStats) {
+    let mut vocab = Vocab::with_variant(settings.tokenizer, settings.variant);
+    let tokens: Vec<Vec<u32>> = texts.iter().map(|text| vocab.tokenize(text)).collect();
+    let words: Vec<Vec<u32>> = tokens.iter().map(|tokens| summary::words(tokens).into_owned()).collect();
+    let mut by_length: Vec<usize> = (0..texts.len()).collect();
+    by_length.sort_by_key(|&i| words[i].len());
+
+    let compare = |scratch: &mut Scratch, position: usize| {
+        let mut stats = DedupStats::default();
+        let mut duplicates = Vec::new();
+        let i = by_length[position];
+        let shorter = words[i].len();
+        for (k, &j) in by_length.iter().enumerate().skip(position + 1) {
+            let longer = words[j].len();
+            let length_bound = 2.0 * shorter as f64 / (shorter + longer).max(1) as f64;
+            if length_bound < threshold {
+                stats.by_length += by_length.len() - k;
+                break;
+            }
+            if f_measure_bound(&words[i], &words[j]) < threshold {
+                stats.by_overlap += 1;
+                continue;
+            }
+            let (first, second) = (i.min(j), i.max(j));
+            let reference = std::slice::from_ref(&tokens[second]);
+            let result = settings.variant.best_of_in(scratch, &tokens[first], reference, settings.lcs_strategy);
+            if result.f_measure >= threshold {
+                duplicates.push(Duplicate {
+                    first,
+                    second,
+                    f_measure: result.f_measure,
+                });
+            }
+        }
+        (duplicates, stats)
+    };
+    #[cfg(feature = "parallel")]
+    let found: Vec<(Vec<Duplicate>, DedupStats)> =
+        (0..texts.len()).into_par_iter().map_init(Scratch::default, compare).collect();
+    #[cfg(not(feature = "parallel"))]
+    let found: Vec<(Vec<Duplicate>, DedupStats)> = {
+        let mut scratch = Scratch::default();
+        (0..texts.len()).map(|position| compare(&mut scratch, position)).collect()
+    };
+
+    let mut stats = DedupStats {
+        comparisons: texts.len() * texts.len().saturating_sub(1) / 2,
+        ..DedupStats::default()
+    };
+    let mut duplicates = Vec::new();
+    for (found, found_stats) in found {
+        duplicates.extend(found);
+        stats.by_length += found_stats.by_length;
+        stats.by_overlap += found_stats.by_overlap;
+    }
+    duplicates.sort_by(|a, b| {
+        b.f_measure
+            .total_cmp(&a.f_measure)
+            .then_with(|| (a.first, a.second).cmp(&(b.first, b.second)))
+    });
+    (duplicates, stats)
+}
+
+/// Cluster number of every record: the connected components of the graph
+/// linking near-duplicates, i.e. single-linkage clustering cut at the
+/// threshold
+///
+/// Clusters are numbered from 0 in the order of their first record.
+pub fn clusters(records: usize, duplicates: &[Duplicate]) -> Vec<usize> {
+    let mut parent: Vec<usize> = (0..records).collect();
+    for duplicate in duplicates {
+        let (a, b) = (root(&mut parent, duplicate.first), root(&mut parent, duplicate.second));
+        // The earlier record stays the root, so roots are first records
+        parent[a.max(b)] = a.min(b);
+    }
+    let mut numbers = vec![usize::MAX; records];
+    let mut assigned = Vec::with_capacity(records);
+    let mut next = 0;
+    for i in 0..records {
+        let root = root(&mut parent, i);
+        if numbers[root] == usize::MAX {
+            numbers[root] = next;
+            next += 1;
+        }
+        assigned.push(numbers[root]);
+    }
+    assigned
+}
+
+/// Root of the tree holding `i` in a union-find forest, halving the path
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Write the cluster of every record as a JSON line with its id, the
+/// cluster number and the number of records in the cluster
+pub fn write_clusters(out: &mut dyn Write, clusters: &[usize], ids: &[String]) -> io::Result<()> {
+    let mut sizes = vec![0usize; clusters.len()];
+    for &cluster in clusters {
+        sizes[cluster] += 1;
+    }
+    for (id, &cluster) in ids.iter().zip(clusters) {
+        let line = json!({ "id": id, "cluster": cluster, "size": sizes[cluster] });
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Write every near-duplicate pair as a JSON line with the ids of both
+/// records and their F-measure
+pub fn write_pairs(
+    out: &mut dyn Write,
+    duplicates: &[Duplicate],
+    ids: &[String],
+    score_format: &ScoreFormat,
+) -> io::Result<()> {
+    for duplicate in duplicates {
+        let line = json!({
+            "first": ids[duplicate.first],
+            "second": ids[duplicate.second],
+            "f_measure": output::json_score(duplicate.f_measure, score_format),
+        });
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde_json::Value;
+
+use rouge_l_rust::{lcs, RougeLResult};
+
+use crate::compress;
+use crate::format::ScoreFormat;
+use crate::input::{InputError, Pair};
+use crate::output;
+
+/// Per-pair scores saved by `batch`, read back without their texts
+pub struct SavedResults {
+    /// Id and scores of every pair, in file order
+    pub results: Vec<(String, RougeLResult)>,
+    /// Signature the scores were computed under, when the file records it
+    pub signature: Option<String>,
+}
+
+/// The scored pairs behind a result file, by id
+pub type Texts = HashMap<String, Pair>;
+
+/// Scores of one id in both result files
+pub struct Change {
+    pub id: String,
+    pub old: RougeLResult,
+    pub new: RougeLResult,
+}
+
+impl Change {
+    /// F-measure gained from the old results to the new ones
+    pub fn delta(&self) -> f64 {
+        self.new.f_measure - self.old.f_measure
+    }
+}
+
+/// Two result files joined on their ids
+pub struct Delta {
+    /// Ids whose F-measure rose, the largest gain first
+    pub improved: Vec<Change>,
+    /// Ids whose F-measure fell, the largest drop first
+    pub regressed: Vec<Change>,
+    /// Ids whose F-measure moved by at most the tolerance, in old order
+    pub unchanged: Vec<Change>,
+    /// Ids only the old results have
+    pub old_only: Vec<String>,
+    /// Ids only the new results have
+    pub new_only: Vec<String>,
+}
+
+impl Delta {
+    /// Every shared id, in no particular order
+    pub fn shared(&self) -> impl Iterator<Item = &Change> {
+        self.improved.iter().chain(&self.regressed).chain(&self.unchanged)
+    }
+}
+
+/// Read the results `batch` wrote with `--format json`, `ndjson` or `csv`,
+/// refusing duplicate ids
+///
+/// A missing or null score reads as NaN, as non-finite scores are written;
+/// a CSV file without an `f_measure` column is refused.
+pub fn read_results(path: &Path) -> Result<SavedResults, InputError> {
+    let invalid = |message: String| InputError::Invalid(path.to_path_buf(), message);
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let name = name
+        .strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zst"))
+        .unwrap_or(name);
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
+    let mut add = |id: String, result: RougeLResult| {
+        if !seen.insert(id.clone()) {
+            return Err(format!("duplicate id {}", id));
+        }
+        results.push((id, result));
+        Ok(())
+    };
+
+    if name.ends_with(".csv") {
+        let file = compress::open(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+        let mut reader = csv::Reader::from_reader(file);
+        let headers = reader.headers().map_err(|e| invalid(e.to_string()))?.clone();
+        let column = |name: &str| headers.iter().position(|header| header == name);
+        let id = column("id").ok_or_else(|| invalid("no column `id`".to_string()))?;
+        // Without it every pair would read as NaN and compare as unchanged
+        if column("f_measure").is_none() {
+            return Err(invalid("no column `f_measure`".to_string()));
+        }
+        let scores = ["f_measure", "precision", "recall"].map(column);
+        let mut signature = None;
+        for record in reader.records() {
+            let record = record.map_err(|e| invalid(e.to_string()))?;
+            let score = |index: Option<usize>| {
+                let cell = index.and_then(|index| record.get(index)).unwrap_or_default();
+                cell.trim().parse().unwrap_or(f64::NAN)
+            };
+            let [f_measure, precision, recall] = scores.map(score);
+            let line = record.position().map_or(0, |position| position.line() as usize);
+            add(record.get(id).unwrap_or_default().to_string(), RougeLResult::new(f_measure, precision, recall))
+                .map_err(|message| parse_error(path, line, message, None))?;
+            if signature.is_none() {
+                signature = column("signature").and_then(|index| record.get(index)).map(str::to_string);
+            }
+        }
+        return Ok(SavedResults { results, signature });
+    }
+
+    let text = compress::read_to_string(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+    // `--format json` writes one document holding the results and the summary
+    if let Ok(Value::Object(document)) = serde_json::from_str::<Value>(&text) {
+        if let Some(Value::Array(records)) = document.get("results") {
+            for record in records {
+                let (id, result) = saved_result(record).map_err(invalid)?;
+                add(id, result).map_err(invalid)?;
+            }
+            let signature = document.get("signature").and_then(Value::as_str).map(str::to_string);
+            return Ok(SavedResults { results, signature });
+        }
+    }
+    let mut signature = None;
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = |message: String| parse_error(path, index + 1, message, Some(line.to_string()));
+        let record: Value = serde_json::from_str(line).map_err(|e| error(e.to_string()))?;
+        let (id, result) = saved_result(&record).map_err(error)?;
+        add(id, result).map_err(error)?;
+        if signature.is_none() {
+            signature = record.get("signature").and_then(Value::as_str).map(str::to_string);
+        }
+    }
+    Ok(SavedResults { results, signature })
+}
+
+fn parse_error(path: &Path, line: usize, message: String, record: Option<String>) -> InputError {
+    InputError::Parse {
+        path: path.to_path_buf(),
+        line,
+        message,
+        record,
+    }
+}
+
+/// The id and scores of one result written as JSON
+fn saved_result(record: &Value) -> Result<(String, RougeLResult), String> {
+    let id = match record.get("id") {
+        Some(Value::String(id)) => id.clone(),
+        Some(Value::Number(id)) => id.to_string(),
+        _ => return Err("missing field `id`".to_string()),
+    };
+    let score = |field: &str| record.get(field).and_then(Value::as_f64).unwrap_or(f64::NAN);
+    Ok((id, RougeLResult::new(score("f_measure"), score("precision"), score("recall"))))
+}
+
+/// Join the old and new results on their ids and sort the shared ones into
+/// improvements, regressions and unchanged pairs
+///
+/// An F-measure moving by `tolerance` or less counts as unchanged, as do
+/// two NaN scores; a score turning NaN counts as a regression.
+pub fn diff(old: Vec<(String, RougeLResult)>, new: Vec<(String, RougeLResult)>, tolerance: f64) -> Delta {
+    let mut new_order = Vec::with_capacity(new.len());
+    let mut by_id: HashMap<String, RougeLResult> = HashMap::with_capacity(new.len());
+    for (id, result) in new {
+        new_order.push(id.clone());
+        by_id.insert(id, result);
+    }
+    let mut delta = Delta {
+        improved: Vec::new(),
+        regressed: Vec::new(),
+        unchanged: Vec::new(),
+        old_only: Vec::new(),
+        new_only: Vec::new(),
+    };
+    for (id, old) in old {
+        let Some(new) = by_id.remove(&id) else {
+            delta.old_only.push(id);
+            continue;
+        };
+        let change = Change { id, old, new };
+        let gain = change.delta();
+        if gain > tolerance {
+            delta.improved.push(change);
+        } else if gain < -tolerance {
+            delta.regressed.push(change);
+        } else if gain.is_nan() && change.old.f_measure.is_nan() != change.new.f_measure.is_nan() {
+            // A score appearing counts as a gain, one vanishing as a drop
+            match change.new.f_measure.is_nan() {
+                true => delta.regressed.push(change),
+                false => delta.improved.push(change),
+            }
+        } else {
+            delta.unchanged.push(change);
+        }
+    }
+    delta.new_only = new_order.into_iter().filter(|id| by_id.contains_key(id)).collect();
+    let by_gain = |a: &Change, b: &Change| b.delta().total_cmp(&a.delta());
+    delta.improved.sort_by(by_gain);
+    delta.regressed.sort_by(|a, b| by_gain(b, a));
+    delta
+}
+
+/// Print the counts of improved, regressed and unchanged ids, the mean
+/// F-measure of both files over the shared ids, and the `top` largest
+/// improvements and regressions
+pub fn write_report(out: &mut dyn Write, delta: &Delta, top: usize, score_format: &ScoreFormat) -> io::Result<()> {
+    let shared = delta.improved.len() + delta.regressed.len() + delta.unchanged.len();
+    writeln!(
+        out,
+        "{} shared ids: {} improved, {} regressed, {} unchanged",
+        shared,
+        delta.improved.len(),
+        delta.regressed.len(),
+        delta.unchanged.len()
+    )?;
+    if shared > 0 {
+        let mean = |score: fn(&Change) -> f64| delta.shared().map(score).sum::<f64>() / shared as f64;
+        let (old, new) = (mean(|change| change.old.f_measure), mean(|change| change.new.f_measure));
+        writeln!(
+            out,
+            "Mean F-Measure: {} -> {} ({})",
+            score_format.format(old),
+            score_format.format(new),
+            signed(new - old, score_format)
+        )?;
+    }
+    for (title, changes) in [("improvements", &delta.improved), ("regressions", &delta.regressed)] {
+        if changes.is_empty() || top == 0 {
+            continue;
+        }
+        let mut rows = vec![["Id", "Old", "New", "Change"].map(String::from)];
+        for change in changes.iter().take(top) {
+            rows.push([
+                change.id.clone(),
+                score_format.format(change.old.f_measure),
+                score_format.format(change.new.f_measure),
+                signed(change.delta(), score_format),
+            ]);
+        }
+        writeln!(out, "\nLargest {} ({} of {})", title, rows.len() - 1, changes.len())?;
+        output::write_columns(out, &rows, 1)?;
+    }
+    Ok(())
+}
+
+/// Print the `top` largest regressions with their texts: the references
+/// and both candidates, taken from the inputs of whichever runs are given,
+/// and a word diff from the old candidate to the new one when both are
+///
+/// The diff marks the words of the old candidate the new one dropped as
+/// `[-...-]` and those it added as `{+...+}`.
+pub fn write_drill_down(
+    out: &mut dyn Write,
+    delta: &Delta,
+    top: usize,
+    (old, new): (Option<&Texts>, Option<&Texts>),
+    score_format: &ScoreFormat,
+) -> io::Result<()> {
+    let shown = delta.regressed.len().min(top);
+    for (rank, change) in delta.regressed.iter().take(top).enumerate() {
+        writeln!(
+            out,
+            "\nRegression {} of {}: {}, F-Measure {} -> {} ({})",
+            rank + 1,
+            shown,
+            change.id,
+            score_format.format(change.old.f_measure),
+            score_format.format(change.new.f_measure),
+            signed(change.delta(), score_format)
+        )?;
+        let old = old.and_then(|pairs| pairs.get(&change.id));
+        let new = new.and_then(|pairs| pairs.get(&change.id));
+        let Some(either) = new.or(old) else {
+            writeln!(out, "  no texts for this id")?;
+            continue;
+        };
+        for reference in &either.references {
+            writeln!(out, "  reference: {}", reference)?;
+        }
+        if let Some(old) = old {
+            writeln!(out, "  old:       {}", old.candidate)?;
+        }
+        if let Some(new) = new {
+            writeln!(out, "  new:       {}", new.candidate)?;
+        }
+        if let (Some(old), Some(new)) = (old, new) {
+            writeln!(out, "  diff:      {}", word_diff(&old.candidate, &new.candidate))?;
+        }
+    }
+    Ok(())
+}
+
+/// The words of `new`, with those of `old` it lacks marked `[-...-]` and
+/// its own additions `{+...+}`, over an LCS of the two
+fn word_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.split_whitespace().collect();
+    let new: Vec<&str> = new.split_whitespace().collect();
+    let mut parts = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let ends = std::iter::once((old.len(), new.len()));
+    for (next_i, next_j) in lcs::alignment(&old, &new).into_iter().chain(ends) {
+        if i < next_i {
+            parts.push(format!("[-{}-]", old[i..next_i].join(" ")));
+        }
+        if j < next_j {
+            parts.push(format!("{{+{}+}}", new[j..next_j].join(" ")));
+        }
+        if next_j < new.len() {
+            parts.push(new[next_j].to_string());
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    parts.join(" ")
+}
+
+fn signed(value: f64, score_format: &ScoreFormat) -> String {
+    match value < 0.0 {
+        true => score_format.format(value),
+        false => format!("+{}", score_format.format(value)),
+    }
+}
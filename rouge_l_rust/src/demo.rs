@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use serde::Deserialize;
+
+use crate::config::Settings;
+use crate::display;
+use crate::input::InputError;
+
+/// Examples scored when no `--examples-file` is given
+const BUILTIN_EXAMPLES: &str = include_str!("../data/examples.toml");
+
+/// Leveled candidate/reference examples, progressing from basic to advanced
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExampleSuite {
+    pub levels: Vec<Level>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub name: String,
+    pub examples: Vec<Example>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Example {
+    pub candidate: String,
+    pub reference: String,
+}
+
+impl ExampleSuite {
+    /// The suite bundled from `data/examples.toml`
+    pub fn builtin() -> ExampleSuite {
+        toml::from_str(BUILTIN_EXAMPLES).expect("bundled data/examples.toml is valid")
+    }
+
+    /// Load a suite from a `.json` file, or from TOML for any other extension
+    pub fn load(path: &Path) -> Result<ExampleSuite, InputError> {
+        let text = fs::read_to_string(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+        let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        };
+        parsed.map_err(|message| InputError::Invalid(path.to_path_buf(), message))
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.iter().map(|level| level.examples.len()).sum()
+    }
+}
+
+/// Score the examples and print them grouped by level
+pub fn run(suite: &ExampleSuite, settings: &Settings) {
+    let score_format = &settings.score_format;
+
+    println!("=== ROUGE-L Rust Implementation ===\n");
+    println!("Testing {} examples (Basic to Advanced)", suite.len());
+    println!("Signature: {}\n", settings.signature());
+
+    let mut number = 0;
+    for (level_index, level) in suite.levels.iter().enumerate() {
+        println!("--- Level {}: {} ---", level_index + 1, level.name);
+
+        for example in &level.examples {
+            let (candidate, reference) = (&example.candidate, &example.reference);
+
+            let start = Instant::now();
+            let result = settings
+                .variant
+                .calculate(candidate, reference, settings.tokenizer, settings.lcs_strategy);
+            let duration = start.elapsed();
+
+            let candidate_display = display::truncate(candidate, settings.preview_width);
+            let reference_display = display::truncate(reference, settings.preview_width);
+
+            number += 1;
+            println!("Example {}:", number);
+            println!("  Candidate: {}", candidate_display);
+            println!("  Reference: {}", reference_display);
+            println!("  Result:    F-Measure: {}, Precision: {}, Recall: {}", 
+                     score_format.format(result.f_measure),
+                     score_format.format(result.precision),
+                     score_format.format(result.recall));
+            println!("  Time:      {:?}\n", duration);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Marks a preview cut short
+const ELLIPSIS: &str = "...";
+
+/// Columns `text` takes up on a terminal: wide characters, such as CJK
+/// ideographs and most emoji, take two and combining marks none
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+/// `text` cut down to at most `max_width` columns, ending in `...` when cut
+///
+/// Cuts fall between grapheme clusters, so a character and its combining
+/// marks, or an emoji sequence, stay whole; a wide character that would
+/// straddle the limit is left out.
+pub fn truncate(text: &str, max_width: usize) -> Cow<'_, str> {
+    if width(text) <= max_width {
+        return Cow::Borrowed(text);
+    }
+    let budget = max_width.saturating_sub(ELLIPSIS.len());
+    let mut end = 0;
+    let mut used = 0;
+    for (start, grapheme) in text.grapheme_indices(true) {
+        let grapheme_width = width(grapheme);
+        if used + grapheme_width > budget {
+            break;
+        }
+        used += grapheme_width;
+        end = start + grapheme.len();
+    }
+    let ellipsis = &ELLIPSIS[..max_width.min(ELLIPSIS.len())];
+    Cow::Owned(format!("{}{}", &text[..end], ellipsis))
+}
+
+/// `text` split into pieces of at most `max_width` columns, between grapheme
+/// clusters; a cluster wider than `max_width` gets a piece of its own
+pub fn split(text: &str, max_width: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let (mut start, mut used) = (0, 0);
+    for (at, grapheme) in text.grapheme_indices(true) {
+        let grapheme_width = width(grapheme);
+        if used > 0 && used + grapheme_width > max_width {
+            pieces.push(&text[start..at]);
+            (start, used) = (at, 0);
+        }
+        used += grapheme_width;
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+/// `text` followed by the spaces that bring it to `min_width` columns
+pub fn pad_right(text: &str, min_width: usize) -> String {
+    format!("{}{}", text, " ".repeat(min_width.saturating_sub(width(text))))
+}
+
+/// `text` after the spaces that bring it to `min_width` columns
+pub fn pad_left(text: &str, min_width: usize) -> String {
+    format!("{}{}", " ".repeat(min_width.saturating_sub(width(text))), text)
+}
@@ -0,0 +1,101 @@
+//! C ABI for services written in C, C++, Go (cgo) or anything else that can
+//! call into a shared library
+//!
+//! Built with the `ffi` feature, which also writes the matching declarations
+//! to `include/rouge_l.h` with cbindgen. Scoring is synchronous and keeps no
+//! state between calls, so `rouge_l_score` may be called from any thread.
+
+use std::ffi::{c_char, CStr};
+use std::panic;
+
+use crate::lcs::LcsStrategy;
+use crate::reference::ReferenceSet;
+use crate::summary::Variant;
+use crate::tokenize::Tokenizer;
+
+/// Lowercase and split on whitespace
+pub const ROUGE_L_TOKENIZER_WHITESPACE: u32 = 0;
+/// Lowercase and keep only ASCII alphanumeric runs, as Python `rouge-score`
+pub const ROUGE_L_TOKENIZER_ROUGE_SCORE: u32 = 1;
+
+/// One LCS over each whole text
+pub const ROUGE_L_VARIANT_ROUGE_L: u32 = 0;
+/// Union LCS over newline-separated sentences (`rougeLsum`)
+pub const ROUGE_L_VARIANT_ROUGE_LSUM: u32 = 1;
+
+/// Outcome of a call; the result is only written on `Ok`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RougeLStatus {
+    Ok = 0,
+    /// A text or the result pointer is null
+    NullPointer = 1,
+    /// A text is not valid UTF-8
+    InvalidUtf8 = 2,
+    /// The tokenizer is not one of the `ROUGE_L_TOKENIZER_*` constants
+    UnknownTokenizer = 3,
+    /// The variant is not one of the `ROUGE_L_VARIANT_*` constants
+    UnknownVariant = 4,
+    /// Scoring panicked; this is a bug
+    Internal = 5,
+}
+
+/// Scores of a candidate against a reference, each between 0 and 1
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RougeLScore {
+    pub f_measure: f64,
+    pub precision: f64,
+    pub recall: f64,
+}
+
+/// Score the NUL-terminated UTF-8 `candidate` against `reference` and store
+/// the scores in `*result`
+///
+/// # Safety
+///
+/// `candidate` and `reference` must be null or point to NUL-terminated
+/// strings, and `result` must be null or point to writable memory for a
+/// `RougeLScore`, all valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn rouge_l_score(
+    candidate: *const c_char,
+    reference: *const c_char,
+    tokenizer: u32,
+    variant: u32,
+    result: *mut RougeLScore,
+) -> RougeLStatus {
+    if candidate.is_null() || reference.is_null() || result.is_null() {
+        return RougeLStatus::NullPointer;
+    }
+    let (Ok(candidate), Ok(reference)) = (CStr::from_ptr(candidate).to_str(), CStr::from_ptr(reference).to_str())
+    else {
+        return RougeLStatus::InvalidUtf8;
+    };
+    let tokenizer = match tokenizer {
+        ROUGE_L_TOKENIZER_WHITESPACE => Tokenizer::Whitespace,
+        ROUGE_L_TOKENIZER_ROUGE_SCORE => Tokenizer::RougeScore,
+        _ => return RougeLStatus::UnknownTokenizer,
+    };
+    let variant = match variant {
+        ROUGE_L_VARIANT_ROUGE_L => Variant::RougeL,
+        ROUGE_L_VARIANT_ROUGE_LSUM => Variant::RougeLsum,
+        _ => return RougeLStatus::UnknownVariant,
+    };
+
+    // Unwinding into C is undefined behavior
+    let scored = panic::catch_unwind(|| {
+        ReferenceSet::with_variant(tokenizer, variant).score(candidate, reference, LcsStrategy::Auto)
+    });
+    match scored {
+        Ok(scored) => {
+            result.write(RougeLScore {
+                f_measure: scored.f_measure,
+                precision: scored.precision,
+                recall: scored.recall,
+            });
+            RougeLStatus::Ok
+        }
+        Err(_) => RougeLStatus::Internal,
+    }
+}
@@ -0,0 +1,159 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::batch::ScoredPair;
+
+/// Per-pair value that results can be sorted or filtered on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ScoreField {
+    #[value(alias = "f-measure")]
+    F,
+    Precision,
+    Recall,
+    /// Candidate length in tokens
+    Length,
+}
+
+impl ScoreField {
+    pub fn value(&self, scored: &ScoredPair) -> f64 {
+        match self {
+            ScoreField::F => scored.result.f_measure,
+            ScoreField::Precision => scored.result.precision,
+            ScoreField::Recall => scored.result.recall,
+            ScoreField::Length => scored.candidate_tokens as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Lt => ordering == Ordering::Less,
+            Comparison::Le => ordering != Ordering::Greater,
+            Comparison::Gt => ordering == Ordering::Greater,
+            Comparison::Ge => ordering != Ordering::Less,
+            Comparison::Eq => ordering == Ordering::Equal,
+            Comparison::Ne => ordering != Ordering::Equal,
+        }
+    }
+}
+
+/// A `FIELD OP VALUE` condition such as `f < 0.3` or `length >= 100`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Filter {
+    field: ScoreField,
+    comparison: Comparison,
+    value: f64,
+}
+
+impl Filter {
+    pub fn matches(&self, scored: &ScoredPair) -> bool {
+        self.field
+            .value(scored)
+            .partial_cmp(&self.value)
+            .is_some_and(|ordering| self.comparison.holds(ordering))
+    }
+}
+
+/// Error for a `--filter` expression that cannot be parsed
+#[derive(Debug)]
+pub struct FilterParseError(String);
+
+impl fmt::Display for FilterParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FilterParseError {}
+
+impl FromStr for Filter {
+    type Err = FilterParseError;
+
+    fn from_str(expr: &str) -> Result<Filter, FilterParseError> {
+        const OPERATORS: [(&str, Comparison); 6] = [
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ];
+        let (field, comparison, value) = OPERATORS
+            .iter()
+            .find_map(|&(op, comparison)| {
+                expr.split_once(op).map(|(field, value)| (field, comparison, value))
+            })
+            .ok_or_else(|| {
+                FilterParseError(format!("expected `FIELD OP VALUE` with OP one of < <= > >= == !=, got `{}`", expr))
+            })?;
+
+        let field = ScoreField::from_str(field.trim(), true).map_err(|_| {
+            FilterParseError(format!("unknown field `{}` (expected f, precision, recall or length)", field.trim()))
+        })?;
+        let value = value
+            .trim()
+            .parse()
+            .map_err(|_| FilterParseError(format!("`{}` is not a number", value.trim())))?;
+        Ok(Filter {
+            field,
+            comparison,
+            value,
+        })
+    }
+}
+
+impl TryFrom<String> for Filter {
+    type Error = FilterParseError;
+
+    fn try_from(expr: String) -> Result<Filter, FilterParseError> {
+        expr.parse()
+    }
+}
+
+/// Smallest F-measure any pair passing `filters` can have, if they imply one
+pub fn min_f_measure(filters: &[Filter]) -> Option<f64> {
+    filters
+        .iter()
+        .filter(|filter| filter.field == ScoreField::F)
+        .filter(|filter| matches!(filter.comparison, Comparison::Gt | Comparison::Ge | Comparison::Eq))
+        .map(|filter| filter.value)
+        .reduce(f64::max)
+}
+
+/// Keep the pairs matching every filter, then optionally sort them
+///
+/// Sorting is stable, so pairs with equal keys keep their input order.
+pub fn select(
+    scores: &mut Vec<ScoredPair>,
+    filters: &[Filter],
+    sort_by: Option<ScoreField>,
+    descending: bool,
+) {
+    scores.retain(|scored| filters.iter().all(|filter| filter.matches(scored)));
+    if let Some(field) = sort_by {
+        scores.sort_by(|a, b| {
+            let ordering = field.value(a).total_cmp(&field.value(b));
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+}
@@ -0,0 +1,99 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Rounding applied when a score is printed with a fixed number of decimals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoundingMode {
+    /// Round the exact binary value, ties to even (Rust `{:.N}`, Python `format`)
+    #[default]
+    HalfEven,
+    /// Round the shortest decimal representation, ties away from zero (Java `String.format`)
+    HalfUp,
+    /// Drop the digits beyond the requested precision
+    Truncate,
+}
+
+/// Formatting settings shared by every score printed by the CLI
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreFormat {
+    pub precision: usize,
+    pub rounding: RoundingMode,
+}
+
+impl Default for ScoreFormat {
+    fn default() -> Self {
+        ScoreFormat {
+            precision: 4,
+            rounding: RoundingMode::HalfEven,
+        }
+    }
+}
+
+impl ScoreFormat {
+    pub fn new(precision: usize, rounding: RoundingMode) -> Self {
+        ScoreFormat { precision, rounding }
+    }
+
+    /// Render a score with the configured precision and rounding mode
+    pub fn format(&self, value: f64) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        match self.rounding {
+            RoundingMode::HalfEven => format!("{:.*}", self.precision, value),
+            RoundingMode::HalfUp => round_decimal(value, self.precision, true),
+            RoundingMode::Truncate => round_decimal(value, self.precision, false),
+        }
+    }
+
+    /// The score as [`format`](Self::format) prints it, for outputs that
+    /// store numbers rather than text
+    pub fn round(&self, value: f64) -> f64 {
+        self.format(value).parse().unwrap_or(value)
+    }
+}
+
+/// Round the shortest round-trip decimal representation of `value` to
+/// `precision` places, working on the digits so no binary error creeps in
+fn round_decimal(value: f64, precision: usize, half_up: bool) -> String {
+    let repr = value.abs().to_string();
+    let (int_part, frac_part) = repr.split_once('.').unwrap_or((&repr, ""));
+
+    let mut digits: Vec<u8> = int_part
+        .bytes()
+        .chain(frac_part.bytes().chain(std::iter::repeat(b'0')).take(precision))
+        .map(|b| b - b'0')
+        .collect();
+
+    let round_up = half_up && frac_part.as_bytes().get(precision).is_some_and(|&b| b >= b'5');
+    if round_up {
+        let mut i = digits.len();
+        loop {
+            if i == 0 {
+                digits.insert(0, 1);
+                break;
+            }
+            i -= 1;
+            if digits[i] == 9 {
+                digits[i] = 0;
+            } else {
+                digits[i] += 1;
+                break;
+            }
+        }
+    }
+
+    let int_len = digits.len() - precision;
+    let mut out = String::with_capacity(digits.len() + 2);
+    if value.is_sign_negative() && digits.iter().any(|&d| d != 0) {
+        out.push('-');
+    }
+    for (i, d) in digits.iter().enumerate() {
+        if i == int_len {
+            out.push('.');
+        }
+        out.push((b'0' + d) as char);
+    }
+    out
+}
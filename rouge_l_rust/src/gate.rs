@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::cli::ServeArgs;
+
+/// Clients tracked by the rate limiter before those back to a full bucket
+/// are forgotten
+const TRACKED_CLIENTS: usize = 10_000;
+
+/// What every scoring request and RPC passes before it is read, over HTTP and
+/// gRPC alike
+pub struct Gate {
+    tokens: Option<AuthTokens>,
+    rate: Option<RateLimiter>,
+    /// One permit per scoring request or RPC being answered
+    requests: Arc<Semaphore>,
+}
+
+/// Why a request was turned away
+#[derive(Debug)]
+pub enum Refusal {
+    /// No bearer token of `--auth-token-file` was sent
+    Unauthorized,
+    /// The client is over `--rate-limit`, and may retry after the delay
+    RateLimited(Duration),
+    /// `--max-concurrent-requests` are already being answered
+    Busy,
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::Unauthorized => f.write_str("missing or unknown bearer token"),
+            Refusal::RateLimited(retry) => write!(f, "rate limit exceeded; retry in {}s", retry_secs(*retry)),
+            Refusal::Busy => f.write_str("too many requests in flight; retry shortly"),
+        }
+    }
+}
+
+impl std::error::Error for Refusal {}
+
+/// Whole seconds to wait for a `Retry-After` header, at least one
+pub fn retry_secs(retry: Duration) -> u64 {
+    retry.as_secs_f64().ceil().max(1.0) as u64
+}
+
+impl Gate {
+    pub fn new(args: &ServeArgs) -> io::Result<Self> {
+        Ok(Gate {
+            tokens: args.auth_token_file.as_deref().map(AuthTokens::load).transpose()?,
+            rate: args.rate_limit.map(RateLimiter::per_minute),
+            requests: Arc::new(Semaphore::new(args.max_concurrent_requests as usize)),
+        })
+    }
+
+    /// Check the rate of `client` and then its `Authorization` header value,
+    /// so guessing tokens counts against the rate too
+    pub fn admit(&self, client: Option<IpAddr>, authorization: Option<&str>) -> Result<(), Refusal> {
+        if let Some(rate) = &self.rate {
+            rate.take(client).map_err(Refusal::RateLimited)?;
+        }
+        match &self.tokens {
+            Some(tokens) if !tokens.allows(authorization) => Err(Refusal::Unauthorized),
+            _ => Ok(()),
+        }
+    }
+
+    /// A slot among the requests in flight, held until the request is answered
+    pub fn slot(&self) -> Result<OwnedSemaphorePermit, Refusal> {
+        self.requests.clone().try_acquire_owned().map_err(|_| Refusal::Busy)
+    }
+}
+
+/// The bearer tokens of `--auth-token-file`, one of which every scoring
+/// request must carry
+struct AuthTokens(Vec<String>);
+
+impl AuthTokens {
+    /// One token per line; blank lines and `#` comments are skipped, so the
+    /// old and new token can both be listed while clients are rotated
+    fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("cannot read {}: {}", path.display(), e)))?;
+        let tokens: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect();
+        if tokens.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("no tokens in {}", path.display()),
+            ));
+        }
+        Ok(AuthTokens(tokens))
+    }
+
+    /// Whether an `Authorization` header value is `Bearer` and one of the tokens
+    fn allows(&self, authorization: Option<&str>) -> bool {
+        let Some((scheme, token)) = authorization.and_then(|value| value.split_once(' ')) else {
+            return false;
+        };
+        scheme.eq_ignore_ascii_case("Bearer")
+            && self
+                .0
+                .iter()
+                .fold(false, |found, known| found | constant_time_eq(known.as_bytes(), token.as_bytes()))
+    }
+}
+
+/// Looks at every byte whatever the first mismatch, so response times do not
+/// tell how much of a guessed token was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// A token bucket per client address, holding up to a minute's worth of
+/// requests and refilled evenly over the minute; clients without an address
+/// share one bucket
+struct RateLimiter {
+    per_minute: f64,
+    buckets: Mutex<HashMap<Option<IpAddr>, Bucket>>,
+}
+
+struct Bucket {
+    requests: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Requests available at `now`
+    fn refilled(&self, now: Instant, per_minute: f64) -> f64 {
+        (self.requests + now.duration_since(self.updated).as_secs_f64() * per_minute / 60.0).min(per_minute)
+    }
+}
+
+impl RateLimiter {
+    fn per_minute(requests: u32) -> Self {
+        RateLimiter {
+            per_minute: requests as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a request from the bucket of `client`, or tell how long until
+    /// one is available
+    fn take(&self, client: Option<IpAddr>) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() >= TRACKED_CLIENTS {
+            buckets.retain(|_, bucket| bucket.refilled(now, self.per_minute) < self.per_minute);
+        }
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            requests: self.per_minute,
+            updated: now,
+        });
+        bucket.requests = bucket.refilled(now, self.per_minute);
+        bucket.updated = now;
+        if bucket.requests < 1.0 {
+            return Err(Duration::from_secs_f64((1.0 - bucket.requests) * 60.0 / self.per_minute));
+        }
+        bucket.requests -= 1.0;
+        Ok(())
+    }
+}
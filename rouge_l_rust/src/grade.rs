@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::batch::{Bootstrap, ScoredPair, Summary};
+use crate::config::Settings;
+use crate::display;
+use crate::input::{self, InputError, Pair};
+use crate::output::{OutputFormat, ResultWriter};
+
+/// Join the rows of a solution CSV with the rows of a submission CSV that
+/// have the same id, in solution order
+///
+/// Both files need a header naming `id_column` and `target_column`, and ids
+/// must be unique in each. As on a competition leaderboard, a submission
+/// that misses a solution id or adds one is rejected instead of scored.
+pub fn read_pairs(
+    solution: &Path,
+    submission: &Path,
+    id_column: &str,
+    target_column: &str,
+) -> Result<Vec<Pair>, InputError> {
+    let solution_rows = input::read_csv_rows(solution, id_column, target_column)?;
+    let mut predictions: HashMap<String, String> = input::read_csv_rows(submission, id_column, target_column)?
+        .into_iter()
+        .collect();
+
+    let mut pairs = Vec::with_capacity(solution_rows.len());
+    let mut missing = Vec::new();
+    for (id, target) in solution_rows {
+        match predictions.remove(&id) {
+            Some(candidate) => pairs.push(Pair {
+                id,
+                candidate,
+                references: vec![target],
+                hypotheses: Vec::new(),
+            }),
+            None => missing.push(id),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(rejected(submission, "missing", &missing));
+    }
+    if !predictions.is_empty() {
+        let mut unexpected: Vec<String> = predictions.into_keys().collect();
+        unexpected.sort();
+        return Err(rejected(submission, "unexpected", &unexpected));
+    }
+    Ok(pairs)
+}
+
+fn rejected(submission: &Path, problem: &str, ids: &[String]) -> InputError {
+    InputError::Invalid(
+        submission.to_path_buf(),
+        format!("{} {} ids: {}", ids.len(), problem, input::list_ids(ids)),
+    )
+}
+
+/// Print the per-row feedback followed by the leaderboard score, the mean
+/// F-measure over every solution row
+pub fn write_report(out: &mut dyn Write, scores: &[ScoredPair], settings: &Settings) -> io::Result<()> {
+    let signature = settings.signature();
+    let id_width = scores.iter().map(|s| display::width(&s.id)).max().unwrap_or(0);
+    let mut rows = ResultWriter::new(
+        &mut *out,
+        OutputFormat::Text,
+        &settings.score_format,
+        &signature,
+        id_width,
+        settings.token_limit.is_some(),
+        Bootstrap::default(),
+    )?;
+    for scored in scores {
+        rows.write(scored)?;
+    }
+    // Text rows need no closing, and the averages are replaced by the score
+    drop(rows);
+
+    let summary = Summary::from_scores(scores);
+    writeln!(
+        out,
+        "\nLeaderboard score: {} (mean F-measure over {} rows)",
+        settings.score_format.format(summary.mean.f_measure),
+        summary.count
+    )?;
+    writeln!(out, "Signature: {}", signature)
+}
@@ -0,0 +1,149 @@
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::slice;
+use std::sync::Arc;
+
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::batch;
+use crate::config::Settings;
+use crate::gate::{Gate, Refusal};
+use crate::input::Pair;
+
+mod proto {
+    tonic::include_proto!("rouge_l.v1");
+}
+
+use proto::rouge_l_server::{RougeL, RougeLServer};
+use proto::{ScoreRequest, ScoreResponse};
+
+/// Responses a `ScoreStream` call scores ahead of a client reading them
+const STREAM_BUFFER: usize = 64;
+
+/// The `rouge_l.v1.RougeL` service of `proto/rouge_l.proto`
+struct ScoringService {
+    settings: Settings,
+    signature: String,
+    gate: Arc<Gate>,
+}
+
+/// Serve the gRPC service on `addr` until `shutdown` resolves and the calls
+/// in flight are answered, passing every call through the `gate` with its
+/// `authorization` metadata
+pub async fn serve(
+    addr: SocketAddr,
+    settings: Settings,
+    gate: Arc<Gate>,
+    shutdown: impl Future<Output = ()>,
+) -> io::Result<()> {
+    let service = ScoringService {
+        settings,
+        signature: settings.signature(),
+        gate: gate.clone(),
+    };
+    let authorize = move |request: Request<()>| {
+        let authorization = request.metadata().get("authorization").and_then(|value| value.to_str().ok());
+        gate.admit(request.remote_addr().map(|addr| addr.ip()), authorization)
+            .map_err(refused)?;
+        Ok(request)
+    };
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    eprintln!("gRPC listening on {}", listener.local_addr()?);
+    tonic::transport::Server::builder()
+        .add_service(RougeLServer::with_interceptor(service, authorize))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
+        .await
+        .map_err(io::Error::other)
+}
+
+#[tonic::async_trait]
+impl RougeL for ScoringService {
+    #[tracing::instrument(name = "Score", skip_all)]
+    async fn score(&self, request: Request<ScoreRequest>) -> Result<Response<ScoreResponse>, Status> {
+        let _slot = self.gate.slot().map_err(refused)?;
+        let pair = request_pair(request.into_inner(), 1)?;
+        let (settings, signature) = (self.settings, self.signature.clone());
+        let span = tracing::Span::current();
+        let response = tokio::task::spawn_blocking(move || span.in_scope(|| score_pair(&settings, &signature, &pair)))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))??;
+        Ok(Response::new(response))
+    }
+
+    type ScoreStreamStream = ReceiverStream<Result<ScoreResponse, Status>>;
+
+    /// Score on a blocking thread that pulls requests as the client sends
+    /// them, so a slow reader holds back at most `STREAM_BUFFER` responses
+    async fn score_stream(
+        &self,
+        request: Request<Streaming<ScoreRequest>>,
+    ) -> Result<Response<Self::ScoreStreamStream>, Status> {
+        let slot = self.gate.slot().map_err(refused)?;
+        let mut requests = request.into_inner();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        let (settings, signature) = (self.settings, self.signature.clone());
+        let runtime = Handle::current();
+        let span = tracing::info_span!("ScoreStream");
+        tokio::task::spawn_blocking(move || {
+            let _stream = span.enter();
+            let _slot = slot;
+            for number in 1.. {
+                let response = match runtime.block_on(requests.message()) {
+                    Ok(Some(request)) => {
+                        request_pair(request, number).and_then(|pair| score_pair(&settings, &signature, &pair))
+                    }
+                    Ok(None) => return,
+                    Err(status) => Err(status),
+                };
+                let failed = response.is_err();
+                if tx.blocking_send(response).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+fn refused(refusal: Refusal) -> Status {
+    match refusal {
+        Refusal::Unauthorized => Status::unauthenticated(refusal.to_string()),
+        Refusal::RateLimited(_) => Status::resource_exhausted(refusal.to_string()),
+        Refusal::Busy => Status::unavailable(refusal.to_string()),
+    }
+}
+
+/// The pair of a request, identified by `number` when it has no id
+fn request_pair(request: ScoreRequest, number: usize) -> Result<Pair, Status> {
+    if request.references.is_empty() {
+        return Err(Status::invalid_argument(format!("request {} has no references", number)));
+    }
+    Ok(Pair {
+        id: if request.id.is_empty() { number.to_string() } else { request.id },
+        candidate: request.candidate,
+        references: request.references,
+        hypotheses: Vec::new(),
+    })
+}
+
+fn score_pair(settings: &Settings, signature: &str, pair: &Pair) -> Result<ScoreResponse, Status> {
+    // The pair is over the token or DP table limits of the server
+    let (scores, _) = batch::score_pairs(slice::from_ref(pair), settings, None, None)
+        .map_err(|err| Status::resource_exhausted(err.to_string()))?;
+    let [scored] = &scores[..] else {
+        unreachable!("one pair scored without a prefilter");
+    };
+    let round = |value| settings.score_format.round(value);
+    Ok(ScoreResponse {
+        id: scored.id.clone(),
+        f_measure: round(scored.result.f_measure),
+        precision: round(scored.result.precision),
+        recall: round(scored.result.recall),
+        truncated: scored.truncated,
+        signature: signature.to_string(),
+    })
+}
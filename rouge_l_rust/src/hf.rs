@@ -0,0 +1,315 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use arrow_array::cast::AsArray;
+use arrow_array::Array;
+use arrow_schema::DataType;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ProjectionMask;
+
+use crate::compress;
+use crate::input::{self, InputError, Pair, RecordFields};
+
+/// Hub the datasets are fetched from unless `HF_ENDPOINT` names a mirror
+const DEFAULT_ENDPOINT: &str = "https://huggingface.co";
+
+/// Written into a split's cache directory once all of its files are there,
+/// holding their number
+const COMPLETE_MARKER: &str = "complete";
+
+/// A summarization benchmark known by its short name, whose config and
+/// columns need not be given
+struct Benchmark {
+    name: &'static str,
+    repo: &'static str,
+    config: &'static str,
+    reference: &'static str,
+    id: &'static str,
+}
+
+const BENCHMARKS: &[Benchmark] = &[
+    Benchmark {
+        name: "cnn_dailymail",
+        repo: "abisee/cnn_dailymail",
+        config: "3.0.0",
+        reference: "highlights",
+        id: "id",
+    },
+    Benchmark {
+        name: "xsum",
+        repo: "EdinburghNLP/xsum",
+        config: "default",
+        reference: "summary",
+        id: "id",
+    },
+];
+
+/// The split of a Hub dataset holding the references, and its columns
+#[derive(Debug)]
+pub struct HubSplit {
+    /// Repository id, e.g. `abisee/cnn_dailymail`
+    pub repo: String,
+    /// Config to read, or `None` for the only one the dataset has
+    pub config: Option<String>,
+    pub split: String,
+    pub reference: String,
+    /// Column identifying each row; rows are numbered from 1 without it
+    pub id: String,
+}
+
+impl HubSplit {
+    /// The split of `dataset`, a known benchmark's short name or repository
+    /// id, whose config and columns default to the benchmark's; other
+    /// datasets need the reference column
+    pub fn new(
+        dataset: &str,
+        config: Option<&str>,
+        split: Option<&str>,
+        reference: Option<&str>,
+        id: Option<&str>,
+    ) -> Result<HubSplit, InputError> {
+        let benchmark = BENCHMARKS
+            .iter()
+            .find(|benchmark| benchmark.name == dataset || benchmark.repo == dataset);
+        let Some(reference) = reference.or(benchmark.map(|benchmark| benchmark.reference)) else {
+            let known: Vec<&str> = BENCHMARKS.iter().map(|benchmark| benchmark.name).collect();
+            return Err(hub_error(
+                dataset,
+                format!("not a known benchmark ({}); give its --hf-reference-column", known.join(", ")),
+            ));
+        };
+        Ok(HubSplit {
+            repo: benchmark.map_or(dataset, |benchmark| benchmark.repo).to_string(),
+            config: config.or(benchmark.map(|benchmark| benchmark.config)).map(String::from),
+            split: split.unwrap_or("test").to_string(),
+            reference: reference.to_string(),
+            id: id.or(benchmark.map(|benchmark| benchmark.id)).unwrap_or("id").to_string(),
+        })
+    }
+}
+
+/// Rows of a Hub split paired with their predictions
+#[derive(Debug, Default)]
+pub struct HubPairs {
+    pub pairs: Vec<Pair>,
+    /// Ids of the rows with no prediction, scored as an empty candidate
+    pub unpredicted: Vec<String>,
+    /// Prediction ids the split lacks, left unscored
+    pub unmatched: Vec<String>,
+}
+
+/// Pair every row of `split` with its prediction
+///
+/// Predictions are one per line in row order, or with a `.jsonl` or `.csv`
+/// name (before any `.gz` or `.zst`) records joined to the rows on the id
+/// field and read from the candidate field of `fields`, as with
+/// `--candidate-file`. Rows follow the dataset order either way.
+pub fn read_pairs(split: &HubSplit, predictions: &Path, fields: &RecordFields) -> Result<HubPairs, InputError> {
+    let rows = read_references(&fetch(split)?, split)?;
+    let mut result = HubPairs::default();
+
+    let name = predictions.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let name = name
+        .strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zst"))
+        .unwrap_or(name);
+    if !name.ends_with(".jsonl") && !name.ends_with(".csv") {
+        let text = compress::read_to_string(predictions).map_err(|e| InputError::Io(predictions.to_path_buf(), e))?;
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.len() != rows.len() {
+            return Err(InputError::Invalid(
+                predictions.to_path_buf(),
+                format!("has {} lines, but the {} split has {} rows", lines.len(), split.split, rows.len()),
+            ));
+        }
+        result.pairs = rows
+            .into_iter()
+            .zip(lines)
+            .map(|((id, reference), candidate)| Pair {
+                id,
+                candidate: candidate.to_string(),
+                references: vec![reference],
+                hypotheses: Vec::new(),
+            })
+            .collect();
+        return Ok(result);
+    }
+
+    let keyed = input::read_keyed(predictions, &fields.id, &fields.candidate)?;
+    let prediction_ids: Vec<String> = keyed.iter().map(|(id, _)| id.clone()).collect();
+    let mut predicted: HashMap<String, Vec<String>> = keyed.into_iter().collect();
+    for (id, reference) in rows {
+        let mut texts = predicted.remove(&id).unwrap_or_else(|| {
+            result.unpredicted.push(id.clone());
+            vec![String::new()]
+        });
+        // An n-best list holds the best-ranked hypothesis first
+        let candidate = texts.remove(0);
+        result.pairs.push(Pair {
+            id,
+            candidate,
+            references: vec![reference],
+            hypotheses: texts,
+        });
+    }
+    result.unmatched = prediction_ids
+        .into_iter()
+        .filter(|id| predicted.contains_key(id))
+        .collect();
+    Ok(result)
+}
+
+/// The Parquet files of the split, downloaded into the cache the first time
+///
+/// The Hub serves every public dataset converted to Parquet. A split whose
+/// config is known and whose files are all cached is read without going
+/// online; `HF_TOKEN` is sent to reach gated or private datasets.
+fn fetch(split: &HubSplit) -> Result<Vec<PathBuf>, InputError> {
+    if let Some(files) = split.config.as_deref().and_then(|config| cached(&cache_dir(split, config))) {
+        return Ok(files);
+    }
+
+    let listing: BTreeMap<String, BTreeMap<String, Vec<String>>> = {
+        let url = format!("{}/api/datasets/{}/parquet", endpoint(), split.repo);
+        let body = get(&url)
+            .and_then(|response| response.into_body().read_to_string())
+            .map_err(|err| request_error(split, err))?;
+        serde_json::from_str(&body).map_err(|e| hub_error(&split.repo, format!("unexpected Parquet listing: {}", e)))?
+    };
+    let configs: Vec<&str> = listing.keys().map(String::as_str).collect();
+    let config = match (&split.config, &configs[..]) {
+        (Some(config), _) => config.clone(),
+        (None, [config]) => config.to_string(),
+        (None, _) => {
+            return Err(hub_error(
+                &split.repo,
+                format!("has several configs; pick one with --hf-config: {}", configs.join(", ")),
+            ))
+        }
+    };
+    let Some(splits) = listing.get(&config) else {
+        return Err(hub_error(&split.repo, format!("no config `{}`; it has {}", config, configs.join(", "))));
+    };
+    let Some(urls) = splits.get(&split.split) else {
+        let names: Vec<&str> = splits.keys().map(String::as_str).collect();
+        return Err(hub_error(
+            &split.repo,
+            format!("no split `{}` in config `{}`; it has {}", split.split, config, names.join(", ")),
+        ));
+    };
+
+    let dir = cache_dir(split, &config);
+    if let Some(files) = cached(&dir) {
+        return Ok(files);
+    }
+    fs::create_dir_all(&dir).map_err(|e| InputError::Io(dir.clone(), e))?;
+    let mut files = Vec::new();
+    for (index, url) in urls.iter().enumerate() {
+        let path = dir.join(format!("{:04}.parquet", index));
+        eprintln!("downloading {} ({} of {})", url, index + 1, urls.len());
+        let partial = path.with_extension("parquet.part");
+        let mut body = get(url).map_err(|err| request_error(split, err))?.into_body().into_reader();
+        let mut file = File::create(&partial).map_err(|e| InputError::Io(partial.clone(), e))?;
+        io::copy(&mut body, &mut file).map_err(|e| InputError::Io(partial.clone(), e))?;
+        fs::rename(&partial, &path).map_err(|e| InputError::Io(path.clone(), e))?;
+        files.push(path);
+    }
+    let marker = dir.join(COMPLETE_MARKER);
+    fs::write(&marker, files.len().to_string()).map_err(|e| InputError::Io(marker, e))?;
+    Ok(files)
+}
+
+fn get(url: &str) -> Result<ureq::http::Response<ureq::Body>, ureq::Error> {
+    let request = ureq::get(url);
+    match env::var("HF_TOKEN") {
+        Ok(token) if !token.is_empty() => request.header("Authorization", format!("Bearer {}", token)).call(),
+        _ => request.call(),
+    }
+}
+
+fn endpoint() -> String {
+    env::var("HF_ENDPOINT").map_or_else(|_| DEFAULT_ENDPOINT.to_string(), |url| url.trim_end_matches('/').to_string())
+}
+
+/// `$XDG_CACHE_HOME/rouge_l_rust/hf/REPO/CONFIG/SPLIT`, under `~/.cache`
+/// when `XDG_CACHE_HOME` is not set
+fn cache_dir(split: &HubSplit, config: &str) -> PathBuf {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(env::temp_dir);
+    base.join("rouge_l_rust")
+        .join("hf")
+        .join(split.repo.replace('/', "--"))
+        .join(config)
+        .join(&split.split)
+}
+
+/// The files of a completely downloaded split
+fn cached(dir: &Path) -> Option<Vec<PathBuf>> {
+    let count: usize = fs::read_to_string(dir.join(COMPLETE_MARKER)).ok()?.trim().parse().ok()?;
+    let files: Vec<PathBuf> = (0..count).map(|index| dir.join(format!("{:04}.parquet", index))).collect();
+    files.iter().all(|file| file.is_file()).then_some(files)
+}
+
+/// The id and reference text of every row, in file order
+fn read_references(files: &[PathBuf], split: &HubSplit) -> Result<Vec<(String, String)>, InputError> {
+    let mut rows = Vec::new();
+    for path in files {
+        let invalid = |message: String| InputError::Invalid(path.clone(), message);
+        let file = File::open(path).map_err(|e| InputError::Io(path.clone(), e))?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).map_err(|e| invalid(e.to_string()))?;
+        let schema = builder.schema();
+        if schema.field_with_name(&split.reference).is_err() {
+            let columns: Vec<&str> = schema.fields().iter().map(|field| field.name().as_str()).collect();
+            return Err(invalid(format!("no column `{}`; it has {}", split.reference, columns.join(", "))));
+        }
+        let has_id = schema.field_with_name(&split.id).is_ok();
+        let mut names = vec![split.reference.as_str()];
+        if has_id {
+            names.push(&split.id);
+        }
+        let mask = ProjectionMask::columns(builder.parquet_schema(), names);
+        let batches = builder.with_projection(mask).build().map_err(|e| invalid(e.to_string()))?;
+        for batch in batches {
+            let batch = batch.map_err(|e| invalid(e.to_string()))?;
+            let text_column = |name: &str| {
+                let column = batch.column_by_name(name)?;
+                Some(
+                    arrow_cast::cast(column, &DataType::Utf8)
+                        .map(|text| text.as_string::<i32>().clone())
+                        .map_err(|e| invalid(format!("column `{}`: {}", name, e))),
+                )
+            };
+            let references = text_column(&split.reference).expect("projected column")?;
+            let ids = text_column(&split.id).transpose()?;
+            for row in 0..references.len() {
+                let id = match &ids {
+                    Some(ids) if ids.is_valid(row) => ids.value(row).to_string(),
+                    _ => (rows.len() + 1).to_string(),
+                };
+                let reference = if references.is_valid(row) { references.value(row) } else { "" };
+                rows.push((id, reference.to_string()));
+            }
+        }
+    }
+    Ok(rows)
+}
+
+fn request_error(split: &HubSplit, err: ureq::Error) -> InputError {
+    let message = match err {
+        ureq::Error::StatusCode(401 | 403) => {
+            "is gated or private; set HF_TOKEN to an access token that may read it".into()
+        }
+        ureq::Error::StatusCode(404) => "not found on the Hub, or it has no Parquet conversion".into(),
+        err => err.to_string(),
+    };
+    hub_error(&split.repo, message)
+}
+
+fn hub_error(dataset: &str, message: impl Into<String>) -> InputError {
+    InputError::Hub(dataset.to_string(), message.into())
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+
+use rouge_l_rust::RougeLResult;
+
+use crate::batch::{ScoredPair, Summary};
+use crate::format::ScoreFormat;
+use crate::output;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        run INTEGER PRIMARY KEY,
+        tag TEXT UNIQUE,
+        recorded TEXT NOT NULL DEFAULT (datetime('now')),
+        pairs INTEGER NOT NULL,
+        f_measure REAL NOT NULL,
+        precision REAL NOT NULL,
+        recall REAL NOT NULL,
+        signature TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS run_scores (
+        run INTEGER NOT NULL REFERENCES runs (run),
+        id TEXT NOT NULL,
+        f_measure REAL NOT NULL,
+        precision REAL NOT NULL,
+        recall REAL NOT NULL,
+        PRIMARY KEY (run, id)
+    );";
+
+/// One recorded batch run with its averages
+pub struct Run {
+    pub run: i64,
+    pub tag: Option<String>,
+    /// UTC time the run was recorded, as `YYYY-MM-DD HH:MM:SS`
+    pub recorded: String,
+    pub summary: Summary,
+    pub signature: String,
+}
+
+/// F-measure of one id in two runs
+pub struct Change {
+    pub id: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+/// Error raised while reading or writing a run registry
+#[derive(Debug)]
+pub enum HistoryError {
+    Sqlite(PathBuf, rusqlite::Error),
+    /// No run of the registry has this tag
+    UnknownTag(PathBuf, String),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Sqlite(path, err) => write!(f, "run registry {}: {}", path.display(), err),
+            HistoryError::UnknownTag(path, tag) => {
+                write!(f, "run registry {} has no run tagged {}", path.display(), tag)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+/// Record the averages and per-pair scores of a run in the registry at
+/// `path`, creating it if needed, and return the number of the run
+///
+/// Recording a tag again replaces the earlier run of that tag. Scores are
+/// rounded as they are printed.
+pub fn record(
+    path: &Path,
+    tag: Option<&str>,
+    scores: &[ScoredPair],
+    score_format: &ScoreFormat,
+    signature: &str,
+) -> Result<i64, HistoryError> {
+    let fail = |err| HistoryError::Sqlite(path.to_path_buf(), err);
+    let round = |result: &RougeLResult| {
+        (
+            score_format.round(result.f_measure),
+            score_format.round(result.precision),
+            score_format.round(result.recall),
+        )
+    };
+    let mut connection = Connection::open(path).map_err(fail)?;
+    let transaction = connection.transaction().map_err(fail)?;
+    transaction.execute_batch(SCHEMA).map_err(fail)?;
+    if let Some(tag) = tag {
+        transaction
+            .execute("DELETE FROM run_scores WHERE run IN (SELECT run FROM runs WHERE tag = ?1)", [tag])
+            .map_err(fail)?;
+        transaction.execute("DELETE FROM runs WHERE tag = ?1", [tag]).map_err(fail)?;
+    }
+    let (f_measure, precision, recall) = round(&Summary::from_scores(scores).mean);
+    transaction
+        .execute(
+            "INSERT INTO runs (tag, pairs, f_measure, precision, recall, signature) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![tag, scores.len() as i64, f_measure, precision, recall, signature],
+        )
+        .map_err(fail)?;
+    let run = transaction.last_insert_rowid();
+    {
+        let mut insert = transaction
+            .prepare(
+                "INSERT OR REPLACE INTO run_scores (run, id, f_measure, precision, recall) \
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .map_err(fail)?;
+        for scored in scores {
+            let (f_measure, precision, recall) = round(&scored.result);
+            insert
+                .execute(params![run, scored.id, f_measure, precision, recall])
+                .map_err(fail)?;
+        }
+    }
+    transaction.commit().map_err(fail)?;
+    Ok(run)
+}
+
+/// Every run of the registry at `path`, oldest first
+pub fn runs(path: &Path) -> Result<Vec<Run>, HistoryError> {
+    let fail = |err| HistoryError::Sqlite(path.to_path_buf(), err);
+    let connection = open(path)?;
+    let mut statement = connection
+        .prepare("SELECT run, tag, recorded, pairs, f_measure, precision, recall, signature FROM runs ORDER BY run")
+        .map_err(fail)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok(Run {
+                run: row.get(0)?,
+                tag: row.get(1)?,
+                recorded: row.get(2)?,
+                summary: Summary {
+                    count: row.get::<_, i64>(3)? as usize,
+                    mean: RougeLResult {
+                        f_measure: row.get(4)?,
+                        precision: row.get(5)?,
+                        recall: row.get(6)?,
+                    },
+                    oracle: None,
+                },
+                signature: row.get(7)?,
+            })
+        })
+        .map_err(fail)?;
+    rows.collect::<Result<_, _>>().map_err(fail)
+}
+
+/// F-measures of the ids both tagged runs scored, largest change first
+///
+/// Also returns the number of ids only one of the runs scored.
+pub fn changes(path: &Path, from: &str, to: &str) -> Result<(Vec<Change>, usize), HistoryError> {
+    let connection = open(path)?;
+    let before = tagged_scores(&connection, path, from)?;
+    let mut after = tagged_scores(&connection, path, to)?;
+    let mut changes = Vec::new();
+    let mut unshared = 0;
+    for (id, before) in before {
+        match after.remove(&id) {
+            Some(after) => changes.push(Change { id, before, after }),
+            None => unshared += 1,
+        }
+    }
+    unshared += after.len();
+    changes.sort_by(|a, b| {
+        (b.after - b.before)
+            .abs()
+            .total_cmp(&(a.after - a.before).abs())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    Ok((changes, unshared))
+}
+
+fn open(path: &Path) -> Result<Connection, HistoryError> {
+    let fail = |err| HistoryError::Sqlite(path.to_path_buf(), err);
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(fail)?;
+    connection.execute_batch("SELECT 1 FROM runs LIMIT 1").map_err(fail)?;
+    Ok(connection)
+}
+
+/// F-measure of every id of the run tagged `tag`
+fn tagged_scores(connection: &Connection, path: &Path, tag: &str) -> Result<HashMap<String, f64>, HistoryError> {
+    let fail = |err| HistoryError::Sqlite(path.to_path_buf(), err);
+    let run: i64 = connection
+        .query_row("SELECT run FROM runs WHERE tag = ?1", [tag], |row| row.get(0))
+        .optional()
+        .map_err(fail)?
+        .ok_or_else(|| HistoryError::UnknownTag(path.to_path_buf(), tag.to_string()))?;
+    let mut statement = connection
+        .prepare("SELECT id, f_measure FROM run_scores WHERE run = ?1")
+        .map_err(fail)?;
+    let rows = statement
+        .query_map([run], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(fail)?;
+    rows.collect::<Result<_, _>>().map_err(fail)
+}
+
+/// Print the averages of every run with the change in F-measure from the
+/// run before, one row each
+pub fn write_trend(out: &mut dyn Write, runs: &[Run], score_format: &ScoreFormat) -> io::Result<()> {
+    let header = ["Run", "Tag", "Recorded", "Pairs", "F-Measure", "Precision", "Recall", "Change"];
+    let mut rows = vec![header.map(String::from)];
+    let mut previous: Option<f64> = None;
+    for run in runs {
+        let mean = &run.summary.mean;
+        rows.push([
+            run.run.to_string(),
+            run.tag.clone().unwrap_or_else(|| "-".to_string()),
+            run.recorded.clone(),
+            run.summary.count.to_string(),
+            score_format.format(mean.f_measure),
+            score_format.format(mean.precision),
+            score_format.format(mean.recall),
+            match previous {
+                Some(previous) => signed(mean.f_measure - previous, score_format),
+                None => "-".to_string(),
+            },
+        ]);
+        previous = Some(mean.f_measure);
+    }
+    output::write_columns(out, &rows, 3)
+}
+
+/// Print the `top` largest changes in F-measure between two runs
+pub fn write_changes(
+    out: &mut dyn Write,
+    changes: &[Change],
+    (from, to): (&str, &str),
+    top: usize,
+    score_format: &ScoreFormat,
+) -> io::Result<()> {
+    let mut rows = vec![["Id", from, to, "Change"].map(String::from)];
+    for change in changes.iter().take(top) {
+        rows.push([
+            change.id.clone(),
+            score_format.format(change.before),
+            score_format.format(change.after),
+            signed(change.after - change.before, score_format),
+        ]);
+    }
+    writeln!(
+        out,
+        "\nLargest F-measure changes from {} to {} over {} shared ids",
+        from,
+        to,
+        changes.len()
+    )?;
+    output::write_columns(out, &rows, 1)
+}
+
+fn signed(value: f64, score_format: &ScoreFormat) -> String {
+    match value < 0.0 {
+        true => score_format.format(value),
+        false => format!("+{}", score_format.format(value)),
+    }
+}
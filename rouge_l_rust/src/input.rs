@@ -0,0 +1,903 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[cfg(feature = "parquet")]
+use arrow_array::cast::AsArray;
+#[cfg(feature = "parquet")]
+use arrow_array::{Array, RecordBatch, StringArray};
+#[cfg(feature = "parquet")]
+use arrow_schema::DataType;
+use clap::ValueEnum;
+use memmap2::Mmap;
+#[cfg(feature = "parquet")]
+use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+#[cfg(feature = "parquet")]
+use parquet::arrow::ProjectionMask;
+use serde_json::{json, Value};
+
+use crate::compress::{self, Compression};
+
+/// Most ids listed when reporting ids found on one side of a join only
+const LISTED_IDS: usize = 5;
+
+/// A candidate and its references to be scored, identified by `id`
+#[derive(Debug, Clone)]
+pub struct Pair {
+    pub id: String,
+    pub candidate: String,
+    /// Never empty; with several, the candidate keeps its best score
+    pub references: Vec<String>,
+    /// Hypotheses ranked below `candidate` in an n-best list, which is then
+    /// the rank-1 hypothesis; empty for a single candidate
+    pub hypotheses: Vec<String>,
+}
+
+/// Error raised while reading evaluation inputs
+#[derive(Debug)]
+pub enum InputError {
+    Io(PathBuf, io::Error),
+    Invalid(PathBuf, String),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+        /// The malformed record itself, when the format has a text form of it
+        record: Option<String>,
+    },
+    /// A Hugging Face Hub dataset could not be listed or downloaded
+    #[cfg(feature = "hf")]
+    Hub(String, String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Io(path, err) => write!(f, "cannot read {}: {}", path.display(), err),
+            InputError::Invalid(path, message) => write!(f, "invalid {}: {}", path.display(), message),
+            InputError::Parse { path, line, message, .. } => write!(f, "{}:{}: {}", path.display(), line, message),
+            #[cfg(feature = "hf")]
+            InputError::Hub(dataset, message) => write!(f, "dataset {}: {}", dataset, message),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// Pairs read from a candidates directory and a references directory
+#[derive(Debug, Default)]
+pub struct DirPairs {
+    pub pairs: Vec<Pair>,
+    /// Candidate file names with no reference file of the same name
+    pub unmatched: Vec<String>,
+}
+
+/// A directory of text files or, with the `archive` feature, a tar or zip
+/// archive of them given as `ARCHIVE` or `ARCHIVE::PATTERN`
+#[derive(Debug, Clone)]
+pub enum TextFiles {
+    Dir(PathBuf),
+    /// Members selected by a pattern such as `*.txt`, or all of them
+    #[cfg(feature = "archive")]
+    Archive { path: PathBuf, pattern: Option<String> },
+}
+
+impl FromStr for TextFiles {
+    type Err = Infallible;
+
+    /// A path containing `::` or naming a file is an archive
+    fn from_str(source: &str) -> Result<TextFiles, Infallible> {
+        #[cfg(feature = "archive")]
+        {
+            if let Some((path, pattern)) = source.split_once("::") {
+                return Ok(TextFiles::Archive {
+                    path: PathBuf::from(path),
+                    pattern: Some(pattern.to_string()),
+                });
+            }
+            if Path::new(source).is_file() {
+                return Ok(TextFiles::Archive {
+                    path: PathBuf::from(source),
+                    pattern: None,
+                });
+            }
+        }
+        Ok(TextFiles::Dir(PathBuf::from(source)))
+    }
+}
+
+/// A file of a [`TextFiles`], read on demand from a directory
+enum Member {
+    File(PathBuf),
+    #[cfg(feature = "archive")]
+    Loaded(String),
+}
+
+impl Member {
+    fn read(self) -> Result<String, InputError> {
+        match self {
+            Member::File(path) => read_text(&path),
+            #[cfg(feature = "archive")]
+            Member::Loaded(text) => Ok(text),
+        }
+    }
+}
+
+impl TextFiles {
+    /// The directory or archive file
+    pub fn path(&self) -> &Path {
+        match self {
+            TextFiles::Dir(path) => path,
+            #[cfg(feature = "archive")]
+            TextFiles::Archive { path, .. } => path,
+        }
+    }
+
+    /// Every file, keyed and ordered by file name; directories are listed
+    /// without reading their files, archives are read whole
+    fn members(&self) -> Result<BTreeMap<String, Member>, InputError> {
+        match self {
+            TextFiles::Dir(dir) => {
+                let mut members = BTreeMap::new();
+                let entries = fs::read_dir(dir).map_err(|e| InputError::Io(dir.to_path_buf(), e))?;
+                for entry in entries {
+                    let entry = entry.map_err(|e| InputError::Io(dir.to_path_buf(), e))?;
+                    if entry.path().is_file() {
+                        members.insert(entry.file_name().to_string_lossy().into_owned(), Member::File(entry.path()));
+                    }
+                }
+                Ok(members)
+            }
+            #[cfg(feature = "archive")]
+            TextFiles::Archive { path, pattern } => Ok(crate::archive::read_files(path, pattern.as_deref())?
+                .into_iter()
+                .map(|(name, text)| (name, Member::Loaded(text)))
+                .collect()),
+        }
+    }
+}
+
+/// Pair every file in `candidates` with the file of the same name in
+/// `references`, ordered by file name
+pub fn read_dir_pairs(candidates: &TextFiles, references: &TextFiles) -> Result<DirPairs, InputError> {
+    let mut reference_members = references.members()?;
+    let mut result = DirPairs::default();
+    for (name, candidate) in candidates.members()? {
+        let Some(reference) = reference_members.remove(&name) else {
+            result.unmatched.push(name);
+            continue;
+        };
+        result.pairs.push(Pair {
+            candidate: candidate.read()?,
+            references: vec![reference.read()?],
+            hypotheses: Vec::new(),
+            id: name,
+        });
+    }
+    Ok(result)
+}
+
+/// Pairs read from a candidates file and a references file with one document per line
+#[derive(Debug, Default)]
+pub struct LinePairs {
+    pub pairs: Vec<Pair>,
+    /// Lines of the longer file past the end of the other, left unscored
+    pub unpaired: usize,
+}
+
+/// Pair line `n` of `candidates` with line `n` of `references`, identified by
+/// the 1-based line number
+///
+/// Empty lines are empty documents and keep their place. Files with a
+/// different number of lines are an error unless `lenient`, which scores the
+/// lines both files have.
+pub fn read_line_pairs(candidates: &Path, references: &Path, lenient: bool) -> Result<LinePairs, InputError> {
+    let candidate_text = read_text(candidates)?;
+    let reference_text = read_text(references)?;
+    let candidate_lines: Vec<&str> = candidate_text.lines().collect();
+    let reference_lines: Vec<&str> = reference_text.lines().collect();
+    if candidate_lines.len() != reference_lines.len() && !lenient {
+        return Err(InputError::Invalid(
+            references.to_path_buf(),
+            format!(
+                "has {} lines, but {} has {}",
+                reference_lines.len(),
+                candidates.display(),
+                candidate_lines.len()
+            ),
+        ));
+    }
+
+    Ok(LinePairs {
+        pairs: candidate_lines
+            .iter()
+            .zip(&reference_lines)
+            .enumerate()
+            .map(|(index, (candidate, reference))| Pair {
+                id: (index + 1).to_string(),
+                candidate: candidate.to_string(),
+                references: vec![reference.to_string()],
+                hypotheses: Vec::new(),
+            })
+            .collect(),
+        unpaired: candidate_lines.len().abs_diff(reference_lines.len()),
+    })
+}
+
+/// The lines of a text file, one document each; empty lines are empty
+/// documents
+pub fn read_lines(path: &Path) -> Result<Vec<String>, InputError> {
+    Ok(read_text(path)?.lines().map(str::to_string).collect())
+}
+
+/// Pairs joined on their id from a candidates file and a references file
+#[derive(Debug, Default)]
+pub struct JoinedPairs {
+    pub pairs: Vec<Pair>,
+    /// Candidate ids the references file lacks, in file order
+    pub unmatched_candidates: Vec<String>,
+    /// Reference ids the candidates file lacks, in file order
+    pub unmatched_references: Vec<String>,
+}
+
+/// Join every record of `candidates` with the record of `references` that
+/// has the same id, in candidate order
+///
+/// Each file is JSON Lines, or CSV with a header row when its name ends in
+/// `.csv` (before any `.gz` or `.zst`). Records are read from the id field
+/// and the candidate or reference field of `fields`; a JSONL candidate field
+/// may hold an n-best list and a reference field several references. Ids
+/// must be unique within each file, and records whose id only one file has
+/// are left unscored.
+pub fn read_joined_pairs(candidates: &Path, references: &Path, fields: &RecordFields) -> Result<JoinedPairs, InputError> {
+    let candidate_rows = read_keyed(candidates, &fields.id, &fields.candidate)?;
+    let reference_rows = read_keyed(references, &fields.id, &fields.reference)?;
+    let reference_ids: Vec<String> = reference_rows.iter().map(|(id, _)| id.clone()).collect();
+    let mut reference_texts: HashMap<String, Vec<String>> = reference_rows.into_iter().collect();
+
+    let mut result = JoinedPairs::default();
+    for (id, mut texts) in candidate_rows {
+        let Some(references) = reference_texts.remove(&id) else {
+            result.unmatched_candidates.push(id);
+            continue;
+        };
+        // An n-best list holds the best-ranked hypothesis first
+        let candidate = texts.remove(0);
+        result.pairs.push(Pair {
+            id,
+            candidate,
+            references,
+            hypotheses: texts,
+        });
+    }
+    result.unmatched_references = reference_ids
+        .into_iter()
+        .filter(|id| reference_texts.contains_key(id))
+        .collect();
+    Ok(result)
+}
+
+/// The id and texts of every record of a JSONL or CSV file, refusing duplicate ids
+pub fn read_keyed(path: &Path, id_field: &str, text_field: &str) -> Result<Vec<(String, Vec<String>)>, InputError> {
+    if is_csv(path) {
+        let rows = read_csv_rows(path, id_field, text_field)?;
+        return Ok(rows.into_iter().map(|(id, text)| (id, vec![text])).collect());
+    }
+
+    let reader = compress::open(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+    let mut rows = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+        let parse_error = |message: String| InputError::Parse {
+            path: path.to_path_buf(),
+            line: index + 1,
+            message,
+            record: Some(line.clone()),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Value = serde_json::from_str(&line).map_err(|e| parse_error(e.to_string()))?;
+        let id = record_id(&record, id_field).ok_or_else(|| parse_error(format!("missing field `{}`", id_field)))?;
+        if !seen.insert(id.clone()) {
+            return Err(parse_error(format!("duplicate id {}", id)));
+        }
+        rows.push((id, texts(&record, text_field).map_err(parse_error)?));
+    }
+    Ok(rows)
+}
+
+/// Whether the name of `path` ends in `.csv`, before any `.gz` or `.zst`
+pub fn is_csv(path: &Path) -> bool {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let name = name
+        .strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zst"))
+        .unwrap_or(name);
+    name.ends_with(".csv")
+}
+
+/// The pair of every row of a CSV file with a header naming the candidate
+/// and reference columns, read in a single pass
+///
+/// Rows are identified by their 1-based row number when the header has no
+/// id column or their id cell is empty.
+pub fn read_csv_pairs(path: &Path, fields: &RecordFields) -> Result<Vec<Pair>, InputError> {
+    let invalid = |message: String| InputError::Invalid(path.to_path_buf(), message);
+    let file = compress::open(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+    let mut reader = csv::Reader::from_reader(file);
+    let headers = reader.headers().map_err(|e| invalid(e.to_string()))?.clone();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let missing = |name: &str| invalid(format!("no column `{}`", name));
+    let candidate = column(&fields.candidate).ok_or_else(|| missing(&fields.candidate))?;
+    let reference = column(&fields.reference).ok_or_else(|| missing(&fields.reference))?;
+    let id = column(&fields.id);
+
+    let mut pairs = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| invalid(e.to_string()))?;
+        let field = |index: usize| record.get(index).unwrap_or_default().to_string();
+        pairs.push(Pair {
+            id: id.map(field).filter(|id| !id.is_empty()).unwrap_or_else(|| (index + 1).to_string()),
+            candidate: field(candidate),
+            references: vec![field(reference)],
+            hypotheses: Vec::new(),
+        });
+    }
+    Ok(pairs)
+}
+
+/// The `(id, text)` of every row of a CSV file with a header naming both
+/// columns, refusing duplicate ids
+pub fn read_csv_rows(path: &Path, id_column: &str, text_column: &str) -> Result<Vec<(String, String)>, InputError> {
+    let invalid = |message: String| InputError::Invalid(path.to_path_buf(), message);
+    let file = compress::open(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+    let mut reader = csv::Reader::from_reader(file);
+    let headers = reader.headers().map_err(|e| invalid(e.to_string()))?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or_else(|| invalid(format!("no column `{}`", name)))
+    };
+    let (id, text) = (column(id_column)?, column(text_column)?);
+
+    let mut rows = Vec::new();
+    let mut seen = HashSet::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| invalid(e.to_string()))?;
+        // The header is line 1
+        let line = record.position().map_or(rows.len() + 2, |position| position.line() as usize);
+        let field = |index: usize| record.get(index).unwrap_or_default().to_string();
+        let row_id = field(id);
+        if !seen.insert(row_id.clone()) {
+            return Err(InputError::Parse {
+                path: path.to_path_buf(),
+                line,
+                message: format!("duplicate id {}", row_id),
+                record: None,
+            });
+        }
+        rows.push((row_id, field(text)));
+    }
+    Ok(rows)
+}
+
+/// Whether `text` matches a shell-style pattern where `*` stands for any run
+/// of characters and `?` for a single one, neither crossing a `/`
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it currently covers up to
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == text[t] || (c == '?' && text[t] != '/') => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((after_star, covered)) if text[covered] != '/' => {
+                    p = after_star;
+                    t = covered + 1;
+                    backtrack = Some((after_star, covered + 1));
+                }
+                _ => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A short list of `ids` for a message, elided past the first few
+pub fn list_ids(ids: &[String]) -> String {
+    let mut listed = ids[..ids.len().min(LISTED_IDS)].join(", ");
+    if ids.len() > LISTED_IDS {
+        listed.push_str(", ...");
+    }
+    listed
+}
+
+/// Layout of the file given with `--input`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// JSON Lines, one record per line
+    Jsonl,
+    /// Apache Parquet, one row per pair
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// SQLite database, one row of the `--query` result per pair
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl InputFormat {
+    /// Format implied by the file extension, JSON Lines unless it says otherwise
+    pub fn detect(path: &Path) -> InputFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "parquet")]
+            Some("parquet") => InputFormat::Parquet,
+            _ => InputFormat::Jsonl,
+        }
+    }
+}
+
+/// Lazily read pairs, in file order
+pub type Records = Box<dyn Iterator<Item = Result<Pair, InputError>> + Send>;
+
+/// Open `path` for reading in the given format
+///
+/// Files are read lazily, while a SQLite `query` is run to completion up
+/// front; `query` is required for SQLite inputs and refused for the others.
+pub fn open_records(
+    path: &Path,
+    format: InputFormat,
+    fields: RecordFields,
+    query: Option<&str>,
+) -> Result<Records, InputError> {
+    let invalid = |message: &str| Err(InputError::Invalid(path.to_path_buf(), message.to_string()));
+    Ok(match (format, query) {
+        #[cfg(feature = "sqlite")]
+        (InputFormat::Sqlite, Some(query)) => Box::new(crate::sqlite::read_pairs(path, query, &fields)?.into_iter().map(Ok)),
+        #[cfg(feature = "sqlite")]
+        (InputFormat::Sqlite, None) => return invalid("SQLite inputs need a --query"),
+        (_, Some(_)) => return invalid("--query only applies to SQLite inputs"),
+        (InputFormat::Jsonl, None) => Box::new(JsonlReader::open(path, fields)?),
+        #[cfg(feature = "parquet")]
+        (InputFormat::Parquet, None) => Box::new(ParquetReader::open(path, fields)?),
+    })
+}
+
+/// Names of the JSONL fields, Parquet columns or SQLite query columns holding
+/// each part of a record
+#[derive(Debug, Clone)]
+pub struct RecordFields {
+    pub candidate: String,
+    pub reference: String,
+    pub id: String,
+}
+
+/// The field names of a JSONL `--input` with the default options
+impl Default for RecordFields {
+    fn default() -> Self {
+        RecordFields {
+            candidate: "candidate".into(),
+            reference: "reference".into(),
+            id: "id".into(),
+        }
+    }
+}
+
+/// Read every pair from a JSON Lines, Parquet or SQLite input
+///
+/// JSONL blank lines are skipped, and records without the id field are
+/// identified by their 1-based line or row number. Malformed records are
+/// set aside in `errors` when given, and end the read otherwise.
+pub fn read_records(
+    path: &Path,
+    format: InputFormat,
+    fields: RecordFields,
+    query: Option<&str>,
+    mut errors: Option<&mut ErrorLog>,
+) -> Result<Vec<Pair>, InputError> {
+    let mut pairs = Vec::new();
+    for pair in open_records(path, format, fields, query)? {
+        match (pair, errors.as_deref_mut()) {
+            (Ok(pair), _) => pairs.push(pair),
+            (Err(err), Some(errors)) => errors.skip(err)?,
+            (Err(err), None) => return Err(err),
+        }
+    }
+    Ok(pairs)
+}
+
+/// What happens to a record that cannot be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OnError {
+    /// Stop at the first malformed record
+    #[default]
+    Fail,
+    /// Write malformed records to the error file and score the others
+    Skip,
+}
+
+/// Malformed records set aside under `--on-error skip`, written as one JSON
+/// object per line with the file, line, error message and the record itself
+pub struct ErrorLog {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    skipped: usize,
+}
+
+impl ErrorLog {
+    /// Create or truncate the error file, so it never lists a previous run's
+    /// records, or with `append` add to it
+    pub fn create(path: &Path, append: bool) -> Result<ErrorLog, InputError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+        Ok(ErrorLog {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            skipped: 0,
+        })
+    }
+
+    /// Log `error` if it concerns a single malformed record, or hand it back
+    /// when the input as a whole cannot be read
+    pub fn skip(&mut self, error: InputError) -> Result<(), InputError> {
+        let InputError::Parse {
+            path,
+            line,
+            message,
+            record,
+        } = &error
+        else {
+            return Err(error);
+        };
+        let entry = json!({
+            "path": path.display().to_string(),
+            "line": line,
+            "error": message,
+            "record": record,
+        });
+        writeln!(self.writer, "{}", entry).map_err(|e| InputError::Io(self.path.clone(), e))?;
+        self.skipped += 1;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flush the error file and return how many records were skipped
+    pub fn finish(mut self) -> Result<usize, InputError> {
+        self.writer.flush().map_err(|e| InputError::Io(self.path.clone(), e))?;
+        Ok(self.skipped)
+    }
+}
+
+/// Pairs parsed lazily, one line at a time, from a JSON Lines file
+///
+/// Plain files are memory-mapped and their lines parsed straight from the
+/// map, so multi-gigabyte inputs are never read into a buffer. gzip and zstd
+/// files are decompressed as they are read, one line at a time, and pipes,
+/// FIFOs and other inputs that cannot be mapped are read the same way.
+pub struct JsonlReader {
+    path: PathBuf,
+    fields: RecordFields,
+    source: Source,
+    line: usize,
+}
+
+/// Where a [`JsonlReader`] takes its lines from
+enum Source {
+    Mapped {
+        /// `None` for an empty file, which cannot be mapped on every platform
+        map: Option<Mmap>,
+        offset: usize,
+    },
+    /// A decompressed file or an input that is not a regular file
+    Buffered {
+        reader: Box<dyn BufRead + Send>,
+        /// The current line
+        buffer: Vec<u8>,
+    },
+}
+
+impl JsonlReader {
+    pub fn open(path: &Path, fields: RecordFields) -> Result<JsonlReader, InputError> {
+        let io_error = |e| InputError::Io(path.to_path_buf(), e);
+        let file = File::open(path).map_err(io_error)?;
+        let mut reader = BufReader::new(file);
+        let source = match Compression::detect(path, &mut reader).map_err(io_error)? {
+            Some(compression) => Source::Buffered {
+                reader: Box::new(BufReader::new(compression.decoder(reader).map_err(io_error)?)),
+                buffer: Vec::new(),
+            },
+            None => {
+                let metadata = reader.get_ref().metadata().map_err(io_error)?;
+                match (metadata.is_file(), metadata.len()) {
+                    // Pipes and FIFOs report a length of 0 whatever they hold
+                    (false, _) => Source::Buffered {
+                        reader: Box::new(reader),
+                        buffer: Vec::new(),
+                    },
+                    (true, 0) => Source::Mapped { map: None, offset: 0 },
+                    // SAFETY: the map is only read; like any reader we assume the file is
+                    // not truncated while it is being scored
+                    (true, _) => Source::Mapped {
+                        map: Some(unsafe { Mmap::map(reader.get_ref()) }.map_err(io_error)?),
+                        offset: 0,
+                    },
+                }
+            }
+        };
+        Ok(JsonlReader {
+            path: path.to_path_buf(),
+            fields,
+            source,
+            line: 0,
+        })
+    }
+
+    /// Byte range of the next line within the map or the line buffer, or
+    /// `None` at the end of the file
+    fn next_line(&mut self) -> Option<io::Result<Range<usize>>> {
+        match &mut self.source {
+            Source::Mapped { map, offset } => {
+                let rest = &map.as_ref()?[*offset..];
+                if rest.is_empty() {
+                    return None;
+                }
+                let end = rest.iter().position(|&byte| byte == b'\n').unwrap_or(rest.len());
+                let line = *offset..*offset + end;
+                *offset = (line.end + 1).min(*offset + rest.len());
+                Some(Ok(line))
+            }
+            Source::Buffered { reader, buffer } => {
+                buffer.clear();
+                match reader.read_until(b'\n', buffer) {
+                    Ok(0) => None,
+                    Ok(len) => Some(Ok(0..len)),
+                    Err(err) => Some(Err(err)),
+                }
+            }
+        }
+    }
+
+    fn parse(&self, line: &[u8]) -> Result<Pair, InputError> {
+        let parse_error = |message: String| InputError::Parse {
+            path: self.path.clone(),
+            line: self.line,
+            message,
+            record: Some(String::from_utf8_lossy(line.trim_ascii_end()).into_owned()),
+        };
+        let record: Value = serde_json::from_slice(line).map_err(|e| parse_error(e.to_string()))?;
+        record_pair(&record, &self.fields, self.line).map_err(parse_error)
+    }
+}
+
+/// The pair held by a JSON record, identified by `number` when it has no id
+pub fn record_pair(record: &Value, fields: &RecordFields, number: usize) -> Result<Pair, String> {
+    let references = texts(record, &fields.reference)?;
+    // An n-best list holds the best-ranked hypothesis first
+    let mut hypotheses = texts(record, &fields.candidate)?;
+    let candidate = hypotheses.remove(0);
+    Ok(Pair {
+        id: record_id(record, &fields.id).unwrap_or_else(|| number.to_string()),
+        candidate,
+        references,
+        hypotheses,
+    })
+}
+
+/// The texts of a JSONL field holding a string or a non-empty list of strings
+fn texts(record: &Value, field: &str) -> Result<Vec<String>, String> {
+    match lookup(record, field) {
+        Some(Value::String(text)) => Ok(vec![text.clone()]),
+        Some(Value::Array(texts)) if !texts.is_empty() => texts
+            .iter()
+            .map(|text| text.as_str().map(str::to_string))
+            .collect::<Option<Vec<String>>>()
+            .ok_or_else(|| format!("field `{}` is not a list of strings", field)),
+        Some(Value::Array(_)) => Err(format!("field `{}` is an empty list", field)),
+        Some(_) => Err(format!("field `{}` is not a string", field)),
+        None => Err(format!("missing field `{}`", field)),
+    }
+}
+
+/// The id of a JSONL record, `None` when the field is missing or null
+fn record_id(record: &Value, field: &str) -> Option<String> {
+    match lookup(record, field)? {
+        Value::String(id) => Some(id.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// The value of a JSONL field, which names a top-level key or, starting with
+/// `/`, is a JSON Pointer into nested objects and arrays such as `/outputs/0/text`
+fn lookup<'a>(record: &'a Value, field: &str) -> Option<&'a Value> {
+    if field.starts_with('/') {
+        record.pointer(field)
+    } else {
+        record.get(field)
+    }
+}
+
+impl Iterator for JsonlReader {
+    type Item = Result<Pair, InputError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.next_line()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(InputError::Io(self.path.clone(), err))),
+            };
+            self.line += 1;
+
+            let record = match &self.source {
+                Source::Mapped { map, .. } => &map.as_ref()?[line],
+                Source::Buffered { buffer, .. } => &buffer[line],
+            };
+            if !record.iter().all(u8::is_ascii_whitespace) {
+                return Some(self.parse(record));
+            }
+        }
+    }
+}
+
+/// Pairs read batch by batch from a Parquet file through Arrow
+///
+/// Only the candidate, reference and id columns are decoded; any column type
+/// Arrow can cast to a string is accepted, and rows without an id column are
+/// identified by their 1-based row number.
+#[cfg(feature = "parquet")]
+pub struct ParquetReader {
+    path: PathBuf,
+    fields: RecordFields,
+    batches: ParquetRecordBatchReader,
+    columns: Option<Columns>,
+    /// Next row within the current batch, or rows passed since the last
+    /// one while none is loaded
+    row: usize,
+    /// Rows in the batches before the current one
+    rows_before: usize,
+}
+
+/// The text columns of one record batch
+#[cfg(feature = "parquet")]
+struct Columns {
+    candidate: StringArray,
+    reference: StringArray,
+    id: Option<StringArray>,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetReader {
+    pub fn open(path: &Path, fields: RecordFields) -> Result<ParquetReader, InputError> {
+        let invalid = |message: String| InputError::Invalid(path.to_path_buf(), message);
+        let file = File::open(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).map_err(|e| invalid(e.to_string()))?;
+
+        let schema = builder.schema();
+        for name in [&fields.candidate, &fields.reference] {
+            if schema.field_with_name(name).is_err() {
+                return Err(invalid(format!("no column `{}`", name)));
+            }
+        }
+        let mut names = vec![fields.candidate.as_str(), fields.reference.as_str()];
+        if schema.field_with_name(&fields.id).is_ok() {
+            names.push(&fields.id);
+        }
+        let mask = ProjectionMask::columns(builder.parquet_schema(), names);
+        let batches = builder
+            .with_projection(mask)
+            .build()
+            .map_err(|e| invalid(e.to_string()))?;
+
+        Ok(ParquetReader {
+            path: path.to_path_buf(),
+            fields,
+            batches,
+            columns: None,
+            row: 0,
+            rows_before: 0,
+        })
+    }
+
+    fn columns(&self, batch: &RecordBatch) -> Result<Columns, InputError> {
+        let text_column = |name: &str| {
+            let column = batch.column_by_name(name)?;
+            Some(arrow_cast::cast(column, &DataType::Utf8).map(|text| text.as_string::<i32>().clone()))
+        };
+        let cast_error = |name: &str, err| InputError::Invalid(self.path.clone(), format!("column `{}`: {}", name, err));
+        let required = |name: &str| match text_column(name) {
+            Some(column) => column.map_err(|e| cast_error(name, e)),
+            None => Err(InputError::Invalid(self.path.clone(), format!("no column `{}`", name))),
+        };
+        Ok(Columns {
+            candidate: required(&self.fields.candidate)?,
+            reference: required(&self.fields.reference)?,
+            id: text_column(&self.fields.id)
+                .transpose()
+                .map_err(|e| cast_error(&self.fields.id, e))?,
+        })
+    }
+
+    fn pair(&self, columns: &Columns, row: usize) -> Result<Pair, InputError> {
+        let number = self.rows_before + row + 1;
+        let text = |column: &StringArray, name: &str| match column.is_valid(row) {
+            true => Ok(column.value(row).to_string()),
+            false => Err(InputError::Parse {
+                path: self.path.clone(),
+                line: number,
+                message: format!("column `{}` is null", name),
+                record: None,
+            }),
+        };
+        Ok(Pair {
+            id: match &columns.id {
+                Some(ids) if ids.is_valid(row) => ids.value(row).to_string(),
+                _ => number.to_string(),
+            },
+            candidate: text(&columns.candidate, &self.fields.candidate)?,
+            references: vec![text(&columns.reference, &self.fields.reference)?],
+            hypotheses: Vec::new(),
+        })
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl Iterator for ParquetReader {
+    type Item = Result<Pair, InputError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(columns) = &self.columns {
+                if self.row < columns.candidate.len() {
+                    let pair = self.pair(columns, self.row);
+                    self.row += 1;
+                    return Some(pair);
+                }
+            }
+            // Drop the finished batch before reading on, so an error leaves no
+            // stale columns behind; `row` keeps counting the rows passed since
+            // `rows_before` until the next batch loads
+            self.columns = None;
+            let batch = match self.batches.next()? {
+                Ok(batch) => batch,
+                Err(err) => return Some(Err(InputError::Invalid(self.path.clone(), err.to_string()))),
+            };
+            let columns = match self.columns(&batch) {
+                Ok(columns) => columns,
+                Err(err) => {
+                    self.row += batch.num_rows();
+                    return Some(Err(err));
+                }
+            };
+            self.rows_before += self.row;
+            self.columns = Some(columns);
+            self.row = 0;
+        }
+    }
+}
+
+fn read_text(path: &Path) -> Result<String, InputError> {
+    compress::read_to_string(path).map_err(|e| InputError::Io(path.to_path_buf(), e))
+}
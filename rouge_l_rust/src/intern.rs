@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crate::summary::{self, Variant, SENTENCE_BREAK};
+use crate::tokenize::Tokenizer;
+
+/// Maps each distinct token to a `u32` ID so the LCS compares integers
+/// instead of strings
+#[derive(Debug, Default)]
+pub struct Interner {
+    ids: HashMap<String, u32>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// ID of `token`, allocating a new one the first time it is seen
+    pub fn intern(&mut self, token: &str) -> u32 {
+        if let Some(&id) = self.ids.get(token) {
+            return id;
+        }
+        let id = u32::try_from(self.ids.len())
+            .ok()
+            .filter(|&id| id != SENTENCE_BREAK)
+            .expect("more than u32::MAX - 1 distinct tokens");
+        self.ids.insert(token.to_string(), id);
+        id
+    }
+
+    /// Tokenize `text` straight into IDs; only unseen tokens are allocated
+    pub fn tokenize(&mut self, tokenizer: Tokenizer, text: &str) -> Vec<u32> {
+        let mut ids = Vec::new();
+        self.tokenize_into(tokenizer, text, &mut ids);
+        ids
+    }
+
+    /// Like [`Interner::tokenize`], replacing the contents of `ids`
+    pub fn tokenize_into(&mut self, tokenizer: Tokenizer, text: &str, ids: &mut Vec<u32>) {
+        ids.clear();
+        tokenizer.for_each_token(text, |token| ids.push(self.intern(token)));
+    }
+
+    /// Tokenize each of the [`summary::split_sentences`] of `text`, with a
+    /// [`SENTENCE_BREAK`] between sentences
+    pub fn tokenize_sentences(&mut self, tokenizer: Tokenizer, text: &str) -> Vec<u32> {
+        let mut ids = Vec::new();
+        for (i, sentence) in summary::split_sentences(text).into_iter().enumerate() {
+            if i > 0 {
+                ids.push(SENTENCE_BREAK);
+            }
+            tokenizer.for_each_token(sentence, |token| ids.push(self.intern(token)));
+        }
+        ids
+    }
+}
+
+/// Interner shared by a whole batch run
+///
+/// Common words get their ID once for the corpus rather than once per pair.
+#[derive(Debug)]
+pub struct Vocab {
+    tokenizer: Tokenizer,
+    variant: Variant,
+    interner: Interner,
+}
+
+impl Vocab {
+    pub fn new(tokenizer: Tokenizer) -> Self {
+        Vocab::with_variant(tokenizer, Variant::default())
+    }
+
+    /// Vocabulary tokenizing texts as `variant` scores them
+    pub fn with_variant(tokenizer: Tokenizer, variant: Variant) -> Self {
+        Vocab {
+            tokenizer,
+            variant,
+            interner: Interner::new(),
+        }
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Token IDs of a text, split into sentences when the variant needs them
+    #[tracing::instrument(level = "trace", skip_all, fields(bytes = text.len()))]
+    pub fn tokenize(&mut self, text: &str) -> Vec<u32> {
+        if self.variant.splits_sentences() {
+            self.interner.tokenize_sentences(self.tokenizer, text)
+        } else {
+            self.interner.tokenize(self.tokenizer, text)
+        }
+    }
+}
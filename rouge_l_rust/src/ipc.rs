@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use arrow_array::builder::{ArrayBuilder, BooleanBuilder, Float64Builder, StringBuilder, UInt64Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+use crate::batch::ScoredPair;
+use crate::format::ScoreFormat;
+
+/// Results buffered before they are written out as one record batch
+const BATCH_ROWS: usize = 8192;
+
+/// Writes per-pair results as an Arrow IPC stream, ready to be loaded
+/// zero-copy by Polars (`pl.read_ipc_stream`) or pandas through pyarrow
+///
+/// Each record batch has `id`, `f_measure`, `precision`, `recall`,
+/// `candidate_tokens` and `truncated` columns, with scores rounded as they
+/// are printed; the signature is stored in the schema metadata.
+pub struct IpcWriter<'a> {
+    writer: StreamWriter<&'a mut dyn Write>,
+    schema: SchemaRef,
+    ids: StringBuilder,
+    f_measures: Float64Builder,
+    precisions: Float64Builder,
+    recalls: Float64Builder,
+    candidate_tokens: UInt64Builder,
+    truncated: BooleanBuilder,
+}
+
+impl<'a> IpcWriter<'a> {
+    pub fn new(out: &'a mut dyn Write, signature: &str) -> io::Result<IpcWriter<'a>> {
+        let fields = vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("f_measure", DataType::Float64, false),
+            Field::new("precision", DataType::Float64, false),
+            Field::new("recall", DataType::Float64, false),
+            Field::new("candidate_tokens", DataType::UInt64, false),
+            Field::new("truncated", DataType::Boolean, false),
+        ];
+        let metadata = HashMap::from([("signature".to_string(), signature.to_string())]);
+        let schema = Arc::new(Schema::new_with_metadata(fields, metadata));
+        Ok(IpcWriter {
+            writer: StreamWriter::try_new(out, &schema).map_err(arrow_error)?,
+            schema,
+            ids: StringBuilder::new(),
+            f_measures: Float64Builder::new(),
+            precisions: Float64Builder::new(),
+            recalls: Float64Builder::new(),
+            candidate_tokens: UInt64Builder::new(),
+            truncated: BooleanBuilder::new(),
+        })
+    }
+
+    pub fn write(&mut self, scored: &ScoredPair, score_format: &ScoreFormat) -> io::Result<()> {
+        self.ids.append_value(&scored.id);
+        self.f_measures.append_value(score_format.round(scored.result.f_measure));
+        self.precisions.append_value(score_format.round(scored.result.precision));
+        self.recalls.append_value(score_format.round(scored.result.recall));
+        self.candidate_tokens.append_value(scored.candidate_tokens as u64);
+        self.truncated.append_value(scored.truncated);
+        if self.ids.len() == BATCH_ROWS {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered results and the end-of-stream marker
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.ids.is_empty() {
+            self.flush()?;
+        }
+        self.writer.finish().map_err(arrow_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.ids.finish()),
+            Arc::new(self.f_measures.finish()),
+            Arc::new(self.precisions.finish()),
+            Arc::new(self.recalls.finish()),
+            Arc::new(self.candidate_tokens.finish()),
+            Arc::new(self.truncated.finish()),
+        ];
+        let batch = RecordBatch::try_new(self.schema.clone(), columns).map_err(arrow_error)?;
+        self.writer.write(&batch).map_err(arrow_error)
+    }
+}
+
+fn arrow_error(err: ArrowError) -> io::Error {
+    match err {
+        ArrowError::IoError(_, source) => source,
+        other => io::Error::other(other),
+    }
+}
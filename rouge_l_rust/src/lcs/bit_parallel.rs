@@ -0,0 +1,41 @@
+//! Bit-parallel LCS length (Allison–Dix / Hyyrö)
+//!
+//! Tokens of the shorter sequence become bit positions, so one 64-bit word
+//! update advances 64 DP cells at a time. The per-token match masks live in
+//! one flat vector indexed by the dense token IDs.
+
+use super::interned::Interned;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// LCS length in O(⌈min(m, n)/64⌉ × max(m, n)) time, keeping the match
+/// masks and the state vector in `buffer` between calls
+pub(super) fn length(interned: &Interned, buffer: &mut Vec<u64>) -> usize {
+    if interned.short.is_empty() {
+        return 0;
+    }
+    let words = interned.short.len().div_ceil(WORD_BITS);
+    buffer.clear();
+    buffer.resize(interned.symbols() * words, 0);
+    buffer.resize((interned.symbols() + 1) * words, u64::MAX);
+    let (masks, v) = buffer.split_at_mut(interned.symbols() * words);
+
+    // masks[id * words..][..words] has bit i set where short[i] has that id
+    for (i, &id) in interned.short.iter().enumerate() {
+        masks[id * words + i / WORD_BITS] |= 1 << (i % WORD_BITS);
+    }
+
+    // Zero bits of `v` mark the positions of `short` that are part of the LCS
+    // so far; bits past the end stay set throughout
+    for &id in interned.long.iter().flatten() {
+        let mask = &masks[id * words..(id + 1) * words];
+        let mut carry = false;
+        for (v, &m) in v.iter_mut().zip(mask) {
+            let (sum, overflow1) = v.overflowing_add(*v & m);
+            let (sum, overflow2) = sum.overflowing_add(carry as u64);
+            carry = overflow1 || overflow2;
+            *v = sum | (*v & !m);
+        }
+    }
+    v.iter().map(|word| word.count_zeros() as usize).sum()
+}
@@ -0,0 +1,76 @@
+//! Classic `(m+1)×(n+1)` dynamic programming table
+
+/// The DP table stored row-major in one contiguous buffer
+///
+/// Cells are `u32`: they hold LCS lengths, which never exceed `min(m, n)`,
+/// and any table with a side longer than `u32::MAX` could not be allocated.
+struct Table {
+    cells: Vec<u32>,
+    width: usize,
+}
+
+impl Table {
+    /// LCS length of `seq1[..i]` and `seq2[..j]`
+    fn get(&self, i: usize, j: usize) -> usize {
+        self.cells[i * self.width + j] as usize
+    }
+}
+
+/// Bytes of the table built for sequences of these lengths
+pub fn table_bytes(len1: usize, len2: usize) -> usize {
+    (len1 + 1)
+        .saturating_mul(len2 + 1)
+        .saturating_mul(std::mem::size_of::<u32>())
+}
+
+/// Fill the full DP table, where cell `(i, j)` is the LCS length of
+/// `seq1[..i]` and `seq2[..j]`
+fn table<T: PartialEq>(seq1: &[T], seq2: &[T]) -> Table {
+    let m = seq1.len();
+    let n = seq2.len();
+    let width = n + 1;
+    
+    let mut cells = vec![0u32; (m + 1) * width];
+    
+    // Each row only reads the one above it, so walk them as adjacent slices
+    for (i, token) in seq1.iter().enumerate() {
+        let (done, rest) = cells.split_at_mut((i + 1) * width);
+        let prev = &done[i * width..];
+        let curr = &mut rest[..width];
+        for j in 1..=n {
+            curr[j] = if *token == seq2[j - 1] {
+                prev[j - 1] + 1
+            } else {
+                prev[j].max(curr[j - 1])
+            };
+        }
+    }
+    
+    Table { cells, width }
+}
+
+/// LCS length read off the full table
+pub fn length<T: PartialEq>(seq1: &[T], seq2: &[T]) -> usize {
+    table(seq1, seq2).get(seq1.len(), seq2.len())
+}
+
+/// Recover one LCS by backtracking through the full table
+pub fn alignment<T: PartialEq>(seq1: &[T], seq2: &[T]) -> super::Alignment {
+    let dp = table(seq1, seq2);
+    
+    let (mut i, mut j) = (seq1.len(), seq2.len());
+    let mut alignment = Vec::with_capacity(dp.get(i, j));
+    while i > 0 && j > 0 {
+        if seq1[i - 1] == seq2[j - 1] {
+            alignment.push((i - 1, j - 1));
+            i -= 1;
+            j -= 1;
+        } else if dp.get(i - 1, j) >= dp.get(i, j - 1) {
+            i -= 1;
+        } else {
+            j -= 1;
+        }
+    }
+    alignment.reverse();
+    alignment
+}
@@ -0,0 +1,64 @@
+//! Hirschberg's divide-and-conquer LCS in linear space
+//!
+//! The full DP table for two 50k-token documents needs 2.5 billion cells.
+//! Hirschberg's algorithm recovers the same alignment while only ever holding
+//! two rows over the shorter sequence, at roughly twice the DP's running time.
+
+use super::two_row::forward_row;
+use super::Alignment;
+
+/// Recover one LCS in O(min(m, n)) memory
+pub fn alignment<T: PartialEq>(seq1: &[T], seq2: &[T]) -> Alignment {
+    let mut alignment = Vec::new();
+    if seq1.len() >= seq2.len() {
+        split(seq1, seq2, 0, 0, &mut alignment);
+    } else {
+        split(seq2, seq1, 0, 0, &mut alignment);
+        for pair in &mut alignment {
+            *pair = (pair.1, pair.0);
+        }
+    }
+    alignment
+}
+
+/// Append the alignment of `a` against `b` (offset by the position of both
+/// slices in the original sequences), splitting `a` in half each time
+fn split<T: PartialEq>(a: &[T], b: &[T], a_offset: usize, b_offset: usize, out: &mut Alignment) {
+    if a.is_empty() || b.is_empty() {
+        return;
+    }
+    if a.len() == 1 {
+        if let Some(j) = b.iter().position(|token| *token == a[0]) {
+            out.push((a_offset, b_offset + j));
+        }
+        return;
+    }
+
+    let mid = a.len() / 2;
+    let prefix = forward_row(&a[..mid], b);
+    let suffix = backward_row(&a[mid..], b);
+    let cut = (0..=b.len())
+        .max_by_key(|&j| (prefix[j] + suffix[j], std::cmp::Reverse(j)))
+        .unwrap_or(0);
+
+    split(&a[..mid], &b[..cut], a_offset, b_offset, out);
+    split(&a[mid..], &b[cut..], a_offset + mid, b_offset + cut, out);
+}
+
+/// `row[j]` is the LCS length of `a` and `b[j..]`
+fn backward_row<T: PartialEq>(a: &[T], b: &[T]) -> Vec<usize> {
+    let n = b.len();
+    let mut prev = vec![0; n + 1];
+    let mut curr = vec![0; n + 1];
+    for token in a.iter().rev() {
+        for j in (0..n).rev() {
+            curr[j] = if *token == b[j] {
+                prev[j + 1] + 1
+            } else {
+                prev[j].max(curr[j + 1])
+            };
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev
+}
@@ -0,0 +1,59 @@
+//! Hunt–Szymanski LCS length for inputs with few matching token pairs
+//!
+//! Only the `r` matching `(i, j)` positions are visited, each with one binary
+//! search, giving O(r log n) time after interning. This beats the
+//! bit-parallel pass when most tokens of one sequence never occur in the
+//! other, e.g. when comparing unrelated documents.
+
+use super::interned::Interned;
+
+/// Vectors reused across calls to [`length`]
+#[derive(Debug, Default)]
+pub(super) struct Buffers {
+    offsets: Vec<usize>,
+    fill: Vec<usize>,
+    positions: Vec<usize>,
+    thresholds: Vec<usize>,
+}
+
+/// LCS length visiting only matching positions
+pub(super) fn length(interned: &Interned, buffers: &mut Buffers) -> usize {
+    let Buffers {
+        offsets,
+        fill,
+        positions,
+        thresholds,
+    } = buffers;
+
+    // positions[offsets[id]..offsets[id + 1]] lists where `id` occurs in the
+    // shorter sequence, in decreasing order
+    offsets.clear();
+    offsets.push(0);
+    for &count in &interned.counts {
+        offsets.push(offsets[offsets.len() - 1] + count);
+    }
+    fill.clear();
+    fill.extend_from_slice(&offsets[1..]);
+    positions.clear();
+    positions.resize(interned.short.len(), 0);
+    for (j, &id) in interned.short.iter().enumerate() {
+        fill[id] -= 1;
+        positions[fill[id]] = j;
+    }
+
+    // thresholds[k] is the smallest end position in the shorter sequence of a
+    // common subsequence of length k + 1 seen so far
+    thresholds.clear();
+    for &id in interned.long.iter().flatten() {
+        // Decreasing order keeps one token from extending its own subsequence
+        for &j in &positions[offsets[id]..offsets[id + 1]] {
+            let k = thresholds.partition_point(|&t| t < j);
+            if k == thresholds.len() {
+                thresholds.push(j);
+            } else {
+                thresholds[k] = j;
+            }
+        }
+    }
+    thresholds.len()
+}
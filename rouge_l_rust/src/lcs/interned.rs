@@ -0,0 +1,50 @@
+//! Dense token IDs shared by the length algorithms
+//!
+//! Only tokens of the shorter sequence get an ID; tokens of the longer
+//! sequence that never occur in it can never match and map to `None`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A sequence pair with every token replaced by its dense ID
+#[derive(Debug, Default)]
+pub struct Interned {
+    /// IDs of the shorter sequence, one per token
+    pub short: Vec<usize>,
+    /// IDs of the longer sequence, `None` for tokens absent from `short`
+    pub long: Vec<Option<usize>>,
+    /// Number of occurrences of each ID in `short`
+    pub counts: Vec<usize>,
+}
+
+impl Interned {
+    /// Intern a new pair, reusing the vectors of the previous one
+    pub fn fill<T: Eq + Hash>(&mut self, seq1: &[T], seq2: &[T]) {
+        let (long, short) = if seq1.len() >= seq2.len() { (seq1, seq2) } else { (seq2, seq1) };
+        self.short.clear();
+        self.long.clear();
+        self.counts.clear();
+
+        let mut ids: HashMap<&T, usize> = HashMap::with_capacity(short.len());
+        for token in short {
+            let next_id = ids.len();
+            let id = *ids.entry(token).or_insert(next_id);
+            if id == next_id {
+                self.counts.push(0);
+            }
+            self.counts[id] += 1;
+            self.short.push(id);
+        }
+        self.long.extend(long.iter().map(|token| ids.get(token).copied()));
+    }
+
+    /// Number of distinct tokens in the shorter sequence
+    pub fn symbols(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Number of matching `(i, j)` position pairs between the two sequences
+    pub fn match_count(&self) -> usize {
+        self.long.iter().flatten().map(|&id| self.counts[id]).sum()
+    }
+}
@@ -0,0 +1,269 @@
+//! Longest common subsequence algorithms over token sequences
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use interned::Interned;
+
+pub mod bit_parallel;
+pub mod dp;
+pub mod hirschberg;
+pub mod hunt_szymanski;
+mod interned;
+pub mod myers;
+#[cfg(feature = "simd")]
+mod simd;
+pub mod two_row;
+#[cfg(feature = "parallel")]
+pub mod wavefront;
+
+/// Matched `(index in seq1, index in seq2)` positions of one LCS, in
+/// increasing order
+pub type Alignment = Vec<(usize, usize)>;
+
+/// Largest DP table (in cells) that alignment recovery materializes; bigger
+/// inputs are aligned with Hirschberg's linear-space algorithm instead
+const FULL_TABLE_CELL_LIMIT: usize = 1 << 24;
+
+/// Smallest DP table (in cells) for which the bit-parallel algorithm's setup
+/// cost is repaid; smaller inputs use the two-row DP
+const BIT_PARALLEL_MIN_CELLS: usize = 1 << 8;
+
+/// Smallest edit budget given to Myers' algorithm before falling back
+const MYERS_MIN_EDITS: usize = 4;
+
+/// Smallest DP table (in cells) worth splitting across threads
+#[cfg(feature = "parallel")]
+const WAVEFRONT_MIN_CELLS: usize = 1 << 30;
+
+/// A bit-parallel word covers 64 cells, so the wavefront DP only catches up
+/// with more threads than that
+#[cfg(feature = "parallel")]
+const WAVEFRONT_MIN_THREADS: usize = 64;
+
+/// Algorithm used to compute LCS lengths
+///
+/// Every strategy returns the same length; only the speed differs, so the
+/// fixed ones exist to benchmark and cross-check the automatic choice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LcsStrategy {
+    /// Trim the common prefix and suffix, then pick per pair from the
+    /// sequence lengths and match density
+    #[default]
+    Auto,
+    /// Classic full `(m+1)×(n+1)` table
+    Dp,
+    /// Two rolling DP rows
+    TwoRow,
+    /// 64 DP cells per machine word
+    BitParallel,
+    /// Visit only matching positions
+    HuntSzymanski,
+    /// Greedy O(ND) diff with no edit budget
+    Myers,
+    /// Tiled DP filled one anti-diagonal at a time across threads
+    #[cfg(feature = "parallel")]
+    Wavefront,
+    /// Two-row DP with AVX2 row updates; never picked by `auto`, since
+    /// bit-parallel covers 64 cells per word to its 8
+    #[cfg(feature = "simd")]
+    Simd,
+}
+
+/// Buffers reused across LCS computations
+///
+/// Once they have grown to the largest pair seen, repeated calls through
+/// [`length_in`] or [`LcsStrategy::length_in`] stop hitting the allocator
+/// for DP rows, match masks and interned IDs. The full-table, wavefront and
+/// SIMD strategies still allocate per call.
+#[derive(Debug, Default)]
+pub struct Scratch {
+    rows: Vec<usize>,
+    furthest: Vec<isize>,
+    words: Vec<u64>,
+    interned: Interned,
+    hunt: hunt_szymanski::Buffers,
+}
+
+impl Scratch {
+    /// Scratch preallocated for sequences of up to `tokens` tokens
+    pub fn with_capacity(tokens: usize) -> Self {
+        let mut scratch = Scratch::default();
+        scratch.rows.reserve(2 * (tokens + 1));
+        scratch.interned.short.reserve(tokens);
+        scratch.interned.long.reserve(tokens);
+        scratch.interned.counts.reserve(tokens);
+        scratch
+    }
+}
+
+impl LcsStrategy {
+    /// Name used on the command line and in reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            LcsStrategy::Auto => "auto",
+            LcsStrategy::Dp => "dp",
+            LcsStrategy::TwoRow => "two-row",
+            LcsStrategy::BitParallel => "bit-parallel",
+            LcsStrategy::HuntSzymanski => "hunt-szymanski",
+            LcsStrategy::Myers => "myers",
+            #[cfg(feature = "parallel")]
+            LcsStrategy::Wavefront => "wavefront",
+            #[cfg(feature = "simd")]
+            LcsStrategy::Simd => "simd",
+        }
+    }
+
+    /// Bytes of DP table this strategy allocates for sequences of these
+    /// lengths; only the full-table strategy grows with m × n, the others
+    /// stay linear and report 0
+    pub fn table_bytes(&self, len1: usize, len2: usize) -> usize {
+        match self {
+            LcsStrategy::Dp => dp::table_bytes(len1, len2),
+            _ => 0,
+        }
+    }
+
+    /// Length of the longest common subsequence using this strategy
+    pub fn length<T: Eq + Hash + Sync>(&self, seq1: &[T], seq2: &[T]) -> usize {
+        self.length_in(&mut Scratch::default(), seq1, seq2)
+    }
+
+    /// [`LcsStrategy::length`] reusing the buffers in `scratch`
+    pub fn length_in<T: Eq + Hash + Sync>(&self, scratch: &mut Scratch, seq1: &[T], seq2: &[T]) -> usize {
+        match self {
+            LcsStrategy::Auto => length_in(scratch, seq1, seq2),
+            LcsStrategy::Dp => dp::length(seq1, seq2),
+            LcsStrategy::TwoRow => two_row::length_in(&mut scratch.rows, seq1, seq2),
+            LcsStrategy::BitParallel => {
+                scratch.interned.fill(seq1, seq2);
+                bit_parallel::length(&scratch.interned, &mut scratch.words)
+            }
+            LcsStrategy::HuntSzymanski => {
+                scratch.interned.fill(seq1, seq2);
+                hunt_szymanski::length(&scratch.interned, &mut scratch.hunt)
+            }
+            LcsStrategy::Myers => myers::length_within_in(&mut scratch.furthest, seq1, seq2, seq1.len() + seq2.len())
+                .expect("sequences are never more edits apart than their total length"),
+            #[cfg(feature = "parallel")]
+            LcsStrategy::Wavefront => wavefront::length(seq1, seq2),
+            #[cfg(feature = "simd")]
+            LcsStrategy::Simd => {
+                scratch.interned.fill(seq1, seq2);
+                simd::length(&scratch.interned)
+            }
+        }
+    }
+}
+
+/// Length of the longest common subsequence of two sequences, choosing the
+/// algorithm from the input (see [`LcsStrategy::Auto`])
+///
+/// Scoring only needs the length, so this never builds the full table; the
+/// table is reserved for [`alignment`], and the two-row DP always beats it.
+pub fn length<T: Eq + Hash + Sync>(seq1: &[T], seq2: &[T]) -> usize {
+    length_in(&mut Scratch::default(), seq1, seq2)
+}
+
+/// [`length`] reusing the buffers in `scratch`
+pub fn length_in<T: Eq + Hash + Sync>(scratch: &mut Scratch, seq1: &[T], seq2: &[T]) -> usize {
+    let (prefix, seq1, seq2, suffix) = trim(seq1, seq2);
+    prefix + suffix + middle_length(scratch, seq1, seq2)
+}
+
+/// Split off the common prefix and suffix, returning their lengths around
+/// the differing middles
+///
+/// Shared leading and trailing tokens always belong to some LCS, so only the
+/// middles need an algorithm; near-identical pairs collapse to almost nothing.
+fn trim<'a, T: PartialEq>(seq1: &'a [T], seq2: &'a [T]) -> (usize, &'a [T], &'a [T], usize) {
+    let prefix = seq1.iter().zip(seq2).take_while(|(a, b)| a == b).count();
+    let (seq1, seq2) = (&seq1[prefix..], &seq2[prefix..]);
+    let suffix = seq1.iter().rev().zip(seq2.iter().rev()).take_while(|(a, b)| a == b).count();
+    (prefix, &seq1[..seq1.len() - suffix], &seq2[..seq2.len() - suffix], suffix)
+}
+
+/// LCS length of sequences that differ in their first and last tokens
+fn middle_length<T: Eq + Hash + Sync>(scratch: &mut Scratch, seq1: &[T], seq2: &[T]) -> usize {
+    if seq1.len().saturating_mul(seq2.len()) < BIT_PARALLEL_MIN_CELLS {
+        return two_row::length_in(&mut scratch.rows, seq1, seq2);
+    }
+
+    // Near-identical pairs finish within a few edits; dissimilar ones give up
+    // after a small fraction of the bit-parallel cost
+    let budget = myers_budget(seq1.len(), seq2.len());
+    if let Some(length) = myers::length_within_in(&mut scratch.furthest, seq1, seq2, budget) {
+        return length;
+    }
+
+    // Interning is needed by both algorithms and yields the match count for free
+    let interned = &mut scratch.interned;
+    interned.fill(seq1, seq2);
+    if is_sparse(interned.match_count(), interned.long.len(), interned.short.len()) {
+        return hunt_szymanski::length(interned, &mut scratch.hunt);
+    }
+
+    #[cfg(feature = "parallel")]
+    if seq1.len().saturating_mul(seq2.len()) >= WAVEFRONT_MIN_CELLS
+        && rayon::current_num_threads() > WAVEFRONT_MIN_THREADS
+    {
+        return wavefront::length(seq1, seq2);
+    }
+    bit_parallel::length(interned, &mut scratch.words)
+}
+
+/// Size of the multiset intersection of the two sequences, an upper bound on
+/// the LCS length that costs one counting pass
+pub fn multiset_bound<T: Eq + Hash>(seq1: &[T], seq2: &[T]) -> usize {
+    let mut counts: HashMap<&T, usize> = HashMap::with_capacity(seq1.len());
+    for token in seq1 {
+        *counts.entry(token).or_default() += 1;
+    }
+    seq2.iter()
+        .filter(|token| match counts.get_mut(token) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        })
+        .count()
+}
+
+/// Edit budget whose failed Myers attempt (about budget² steps) stays near
+/// 1/64 of the bit-parallel pass over `long × ⌈short / 64⌉` words
+fn myers_budget(len1: usize, len2: usize) -> usize {
+    let (long, short) = (len1.max(len2), len1.min(len2));
+    let words = long.saturating_mul(short.div_ceil(64));
+    (words.isqrt() / 8).max(MYERS_MIN_EDITS)
+}
+
+/// Whether visiting `matches` positions with a binary search each is cheaper
+/// than the bit-parallel pass over `long × ⌈short / 64⌉` words
+fn is_sparse(matches: usize, long: usize, short: usize) -> bool {
+    let search_cost = usize::BITS - short.leading_zeros();
+    matches.saturating_mul(search_cost as usize) < long.saturating_mul(short.div_ceil(64))
+}
+
+/// Recover one LCS, falling back to linear space for large inputs
+pub fn alignment<T: PartialEq>(seq1: &[T], seq2: &[T]) -> Alignment {
+    let (prefix, middle1, middle2, suffix) = trim(seq1, seq2);
+    let cells = (middle1.len() + 1).saturating_mul(middle2.len() + 1);
+    let middle = if cells > FULL_TABLE_CELL_LIMIT {
+        hirschberg::alignment(middle1, middle2)
+    } else {
+        dp::alignment(middle1, middle2)
+    };
+
+    let (end1, end2) = (seq1.len() - suffix, seq2.len() - suffix);
+    let mut alignment = Vec::with_capacity(prefix + middle.len() + suffix);
+    alignment.extend((0..prefix).map(|i| (i, i)));
+    alignment.extend(middle.into_iter().map(|(i, j)| (i + prefix, j + prefix)));
+    alignment.extend((0..suffix).map(|k| (end1 + k, end2 + k)));
+    alignment
+}
+
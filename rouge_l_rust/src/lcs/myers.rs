@@ -0,0 +1,63 @@
+//! Myers' O(ND) greedy algorithm for near-identical sequences
+//!
+//! `D` is the number of insertions and deletions needed to turn one sequence
+//! into the other, and the LCS length is `(m + n - D) / 2`. Long runs of equal
+//! tokens are consumed as "snakes" without touching a DP cell, so inputs that
+//! differ in only a handful of places are scored in close to linear time.
+
+/// LCS length if the sequences are at most `max_edits` insertions and
+/// deletions apart, `None` otherwise
+///
+/// Giving up costs O((m + n) × `max_edits`) at worst, so callers bound
+/// `max_edits` to keep a failed attempt cheap next to the fallback.
+pub fn length_within<T: PartialEq>(seq1: &[T], seq2: &[T], max_edits: usize) -> Option<usize> {
+    length_within_in(&mut Vec::new(), seq1, seq2, max_edits)
+}
+
+/// [`length_within`] keeping the diagonal vector in `furthest` between calls
+pub(super) fn length_within_in<T: PartialEq>(
+    furthest: &mut Vec<isize>,
+    seq1: &[T],
+    seq2: &[T],
+    max_edits: usize,
+) -> Option<usize> {
+    let (n, m) = (seq1.len() as isize, seq2.len() as isize);
+    if seq1.len().abs_diff(seq2.len()) > max_edits {
+        return None;
+    }
+
+    // furthest[k + offset] is the furthest index into `seq1` reached on
+    // diagonal k = x - y, or -1 if the diagonal has not been reached
+    let max = max_edits as isize;
+    let offset = max + 1;
+    furthest.clear();
+    furthest.resize(2 * max_edits + 3, -1);
+    furthest[(offset + 1) as usize] = 0;
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            // Step down (skip a token of seq2) or right (skip one of seq1),
+            // whichever stays in bounds and reaches further
+            let down = furthest[i + 1];
+            let down = (down >= 0 && down - k <= m).then_some(down);
+            let right = furthest[i - 1] + 1;
+            let right = (furthest[i - 1] >= 0 && right <= n).then_some(right);
+            let Some(mut x) = down.max(right) else {
+                furthest[i] = -1;
+                continue;
+            };
+
+            let mut y = x - k;
+            while x < n && y < m && seq1[x as usize] == seq2[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[i] = x;
+            if x >= n && y >= m {
+                return Some(((n + m - d) / 2) as usize);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,85 @@
+//! Two-row DP over interned IDs with SIMD row updates
+//!
+//! Each row is computed in two steps: `t[j] = prev[j - 1] + 1` where the
+//! tokens match and `prev[j]` elsewhere, which is independent per cell, then
+//! `curr = prefix-max(t)`. A match never falls below `curr[j - 1]`, so this
+//! equals the usual recurrence. Both steps run on eight 32-bit lanes with
+//! AVX2 when the CPU supports it, with a scalar fallback otherwise.
+
+use super::interned::Interned;
+
+/// LCS length of two interned sequences
+pub(super) fn length(interned: &Interned) -> usize {
+    let short: Vec<u32> = interned.short.iter().map(|&id| id as u32).collect();
+    let mut prev = vec![0u32; short.len() + 1];
+    let mut curr = vec![0u32; short.len() + 1];
+
+    // A token absent from `short` matches nothing and leaves the row as is
+    for &id in interned.long.iter().flatten() {
+        update_row(&short, id as u32, &prev, &mut curr);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[short.len()] as usize
+}
+
+fn update_row(short: &[u32], token: u32, prev: &[u32], curr: &mut [u32]) {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: AVX2 support was just checked
+        unsafe { avx2::update_row(short, token, prev, curr) };
+        return;
+    }
+    update_row_scalar(short, token, prev, curr, 1, 0);
+}
+
+/// Fill `curr[start..]` given that `curr[start - 1] == carry`
+fn update_row_scalar(short: &[u32], token: u32, prev: &[u32], curr: &mut [u32], start: usize, carry: u32) {
+    let mut carry = carry;
+    for j in start..=short.len() {
+        let t = if short[j - 1] == token { prev[j - 1] + 1 } else { prev[j] };
+        carry = carry.max(t);
+        curr[j] = carry;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    const LANES: usize = 8;
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn update_row(short: &[u32], token: u32, prev: &[u32], curr: &mut [u32]) {
+        let needle = _mm256_set1_epi32(token as i32);
+        let one = _mm256_set1_epi32(1);
+        // Lane permutations shifting by 1, 2 and 4 lanes; lane 0 repeats,
+        // which is harmless for a prefix max
+        let shift1 = _mm256_setr_epi32(0, 0, 1, 2, 3, 4, 5, 6);
+        let shift2 = _mm256_setr_epi32(0, 0, 0, 1, 2, 3, 4, 5);
+        let shift4 = _mm256_setr_epi32(0, 0, 0, 0, 0, 1, 2, 3);
+
+        let mut carry = 0u32;
+        let mut j = 1;
+        while j + LANES <= short.len() + 1 {
+            // SAFETY: j - 1 + LANES <= short.len() and j + LANES <= prev.len()
+            let (ids, diagonal, above) = unsafe {
+                (
+                    _mm256_loadu_si256(short.as_ptr().add(j - 1).cast()),
+                    _mm256_loadu_si256(prev.as_ptr().add(j - 1).cast()),
+                    _mm256_loadu_si256(prev.as_ptr().add(j).cast()),
+                )
+            };
+            let matches = _mm256_cmpeq_epi32(ids, needle);
+            let mut t = _mm256_blendv_epi8(above, _mm256_add_epi32(diagonal, one), matches);
+            t = _mm256_max_epu32(t, _mm256_permutevar8x32_epi32(t, shift1));
+            t = _mm256_max_epu32(t, _mm256_permutevar8x32_epi32(t, shift2));
+            t = _mm256_max_epu32(t, _mm256_permutevar8x32_epi32(t, shift4));
+            t = _mm256_max_epu32(t, _mm256_set1_epi32(carry as i32));
+            // SAFETY: j + LANES <= curr.len()
+            unsafe { _mm256_storeu_si256(curr.as_mut_ptr().add(j).cast(), t) };
+            carry = _mm256_extract_epi32::<7>(t) as u32;
+            j += LANES;
+        }
+        super::update_row_scalar(short, token, prev, curr, j, carry);
+    }
+}
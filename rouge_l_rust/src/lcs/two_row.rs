@@ -0,0 +1,42 @@
+//! LCS length with two rolling DP rows
+//!
+//! Row `i` of the DP only depends on row `i - 1`, so when the alignment
+//! itself is not needed the length can be computed in O(min(m, n)) memory
+//! instead of materializing the whole table.
+
+/// LCS length in O(min(m, n)) memory
+pub fn length<T: PartialEq>(seq1: &[T], seq2: &[T]) -> usize {
+    length_in(&mut Vec::new(), seq1, seq2)
+}
+
+/// [`length`] keeping both rows in `rows` between calls
+pub(super) fn length_in<T: PartialEq>(rows: &mut Vec<usize>, seq1: &[T], seq2: &[T]) -> usize {
+    let (long, short) = if seq1.len() >= seq2.len() { (seq1, seq2) } else { (seq2, seq1) };
+    rows.clear();
+    rows.resize(2 * (short.len() + 1), 0);
+    let (prev, curr) = rows.split_at_mut(short.len() + 1);
+    fill_rows(long, short, prev, curr)[short.len()]
+}
+
+/// Last DP row: `row[j]` is the LCS length of `a` and `b[..j]`
+pub(super) fn forward_row<T: PartialEq>(a: &[T], b: &[T]) -> Vec<usize> {
+    let mut prev = vec![0; b.len() + 1];
+    let mut curr = vec![0; b.len() + 1];
+    fill_rows(a, b, &mut prev, &mut curr).to_vec()
+}
+
+/// Run the DP over `a` from two zeroed rows of `b.len() + 1` cells, returning
+/// whichever of them holds the last row
+fn fill_rows<'a, T: PartialEq>(a: &[T], b: &[T], mut prev: &'a mut [usize], mut curr: &'a mut [usize]) -> &'a [usize] {
+    for token in a {
+        for j in 1..=b.len() {
+            curr[j] = if *token == b[j - 1] {
+                prev[j - 1] + 1
+            } else {
+                prev[j].max(curr[j - 1])
+            };
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev
+}
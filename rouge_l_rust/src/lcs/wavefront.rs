@@ -0,0 +1,75 @@
+//! Anti-diagonal (wavefront) parallel DP for a single huge pair
+//!
+//! The table is cut into square tiles. A tile only depends on the tile above
+//! it and the tile to its left, so all tiles on one anti-diagonal are filled
+//! in parallel on the rayon thread pool. Only one boundary row per tile
+//! column and one boundary column per tile row are kept, so memory stays
+//! O(m + n).
+
+use rayon::prelude::*;
+
+/// Side of a square tile, in DP cells
+const TILE: usize = 1024;
+
+/// LCS length with the tiles of each anti-diagonal filled in parallel
+pub fn length<T: PartialEq + Sync>(seq1: &[T], seq2: &[T]) -> usize {
+    if seq1.is_empty() || seq2.is_empty() {
+        return 0;
+    }
+    let tile_rows = seq1.len().div_ceil(TILE);
+    let tile_cols = seq2.len().div_ceil(TILE);
+
+    // tops[bj] is the bottom row of the last tile filled in tile column bj
+    // and lefts[bi] the right column of the last tile filled in tile row bi;
+    // both start with the shared corner cell
+    let mut tops: Vec<Vec<usize>> = (0..tile_cols).map(|bj| vec![0; span(seq2, bj) + 1]).collect();
+    let mut lefts: Vec<Vec<usize>> = (0..tile_rows).map(|bi| vec![0; span(seq1, bi) + 1]).collect();
+
+    for diagonal in 0..tile_rows + tile_cols - 1 {
+        let first = diagonal.saturating_sub(tile_cols - 1);
+        let last = diagonal.min(tile_rows - 1);
+        // Tile (bi, diagonal - bi) owns lefts[bi] and tops[diagonal - bi]
+        lefts[first..=last]
+            .par_iter_mut()
+            .zip(tops[diagonal - last..=diagonal - first].par_iter_mut().rev())
+            .enumerate()
+            .for_each(|(offset, (left, top))| {
+                let bi = first + offset;
+                let bj = diagonal - bi;
+                fill_tile(
+                    &seq1[bi * TILE..bi * TILE + span(seq1, bi)],
+                    &seq2[bj * TILE..bj * TILE + span(seq2, bj)],
+                    top,
+                    left,
+                );
+            });
+    }
+    tops[tile_cols - 1][span(seq2, tile_cols - 1)]
+}
+
+/// Number of tokens of `seq` in tile `block`
+fn span<T>(seq: &[T], block: usize) -> usize {
+    (seq.len() - block * TILE).min(TILE)
+}
+
+/// Fill one tile from its top row and left column, replacing them with its
+/// bottom row and right column
+fn fill_tile<T: PartialEq>(a: &[T], b: &[T], top: &mut Vec<usize>, left: &mut [usize]) {
+    let top_right = top[b.len()];
+    let mut prev = std::mem::take(top);
+    let mut curr = vec![0; b.len() + 1];
+    for (i, token) in a.iter().enumerate() {
+        curr[0] = left[i + 1];
+        for j in 1..=b.len() {
+            curr[j] = if *token == b[j - 1] {
+                prev[j - 1] + 1
+            } else {
+                prev[j].max(curr[j - 1])
+            };
+        }
+        left[i + 1] = curr[b.len()];
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    left[0] = top_right;
+    *top = prev;
+}
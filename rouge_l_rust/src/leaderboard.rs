@@ -0,0 +1,131 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::batch::{self, PairError, Summary};
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+use crate::input::{self, InputError, RecordFields};
+use crate::output;
+
+/// Averages of one system over the references
+pub struct Entry {
+    pub path: PathBuf,
+    pub summary: Summary,
+    /// Reference ids the system has no candidate for, which are not scored
+    pub missing: usize,
+}
+
+/// Why a system could not be put on the leaderboard
+#[derive(Debug)]
+pub enum LeaderboardError {
+    Input(InputError),
+    Pair(PathBuf, PairError),
+    /// A --systems pattern matched no file
+    NoMatch(String),
+}
+
+impl fmt::Display for LeaderboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeaderboardError::Input(err) => write!(f, "{}", err),
+            LeaderboardError::Pair(path, err) => write!(f, "{}: {}", path.display(), err),
+            LeaderboardError::NoMatch(pattern) => write!(f, "no system file matches {}", pattern),
+        }
+    }
+}
+
+impl std::error::Error for LeaderboardError {}
+
+impl From<InputError> for LeaderboardError {
+    fn from(err: InputError) -> Self {
+        LeaderboardError::Input(err)
+    }
+}
+
+/// The files of `patterns`, sorted within each pattern
+///
+/// A pattern whose file name holds `*` or `?` matches the files of its
+/// directory, e.g. `runs/*.jsonl`, so it can be quoted against the shell;
+/// any other pattern is a path.
+pub fn expand(patterns: &[String]) -> Result<Vec<PathBuf>, LeaderboardError> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        if !name.contains(['*', '?']) {
+            paths.push(path.to_path_buf());
+            continue;
+        }
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let entries = fs::read_dir(dir).map_err(|e| InputError::Io(dir.to_path_buf(), e))?;
+        let mut matched = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| InputError::Io(dir.to_path_buf(), e))?;
+            let is_file = entry.file_type().is_ok_and(|kind| kind.is_file());
+            if is_file && input::glob_match(&name, &entry.file_name().to_string_lossy()) {
+                matched.push(entry.path());
+            }
+        }
+        if matched.is_empty() {
+            return Err(LeaderboardError::NoMatch(pattern.clone()));
+        }
+        matched.sort();
+        paths.extend(matched);
+    }
+    Ok(paths)
+}
+
+/// Score every system file against the references joined on the id field,
+/// ranked by F-measure, best first
+///
+/// Systems with the same F-measure keep the order they were given in.
+/// Candidates without a reference are left out with a warning.
+pub fn rank(
+    systems: &[PathBuf],
+    references: &Path,
+    fields: &RecordFields,
+    settings: &Settings,
+) -> Result<Vec<Entry>, LeaderboardError> {
+    let mut entries = Vec::with_capacity(systems.len());
+    for path in systems {
+        let joined = input::read_joined_pairs(path, references, fields)?;
+        let extra = &joined.unmatched_candidates;
+        if !extra.is_empty() {
+            eprintln!("warning: {}: {} ids have no reference: {}", path.display(), extra.len(), input::list_ids(extra));
+        }
+        let (scores, stats) = batch::score_pairs(&joined.pairs, settings, None, None)
+            .map_err(|e| LeaderboardError::Pair(path.clone(), e))?;
+        if stats.truncated > 0 {
+            eprintln!("truncation: {}: cut {} pairs to the token limit", path.display(), stats.truncated);
+        }
+        entries.push(Entry {
+            path: path.clone(),
+            summary: Summary::from_scores(&scores),
+            missing: joined.unmatched_references.len(),
+        });
+    }
+    entries.sort_by(|a, b| b.summary.mean.f_measure.total_cmp(&a.summary.mean.f_measure));
+    Ok(entries)
+}
+
+/// Print the ranked systems with their averages, one row each
+pub fn write_table(out: &mut dyn Write, entries: &[Entry], score_format: &ScoreFormat) -> io::Result<()> {
+    let mut rows = vec![["Rank", "System", "Pairs", "Missing", "F-Measure", "Precision", "Recall"].map(String::from)];
+    for (rank, entry) in entries.iter().enumerate() {
+        rows.push([
+            (rank + 1).to_string(),
+            entry.path.display().to_string(),
+            entry.summary.count.to_string(),
+            entry.missing.to_string(),
+            score_format.format(entry.summary.mean.f_measure),
+            score_format.format(entry.summary.mean.precision),
+            score_format.format(entry.summary.mean.recall),
+        ]);
+    }
+    output::write_columns(out, &rows, 2)
+}
@@ -0,0 +1,154 @@
+//! ROUGE-L scoring: tokenization, LCS algorithms and the precision, recall
+//! and F-measure built on them
+
+pub mod copy;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod intern;
+pub mod lcs;
+pub mod mbr;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod reference;
+pub mod scorer;
+pub mod summary;
+pub mod tokenize;
+pub mod truncate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use std::hash::Hash;
+
+use intern::Interner;
+use lcs::{LcsStrategy, Scratch};
+use tokenize::Tokenizer;
+
+pub use scorer::{BuildError, RougeL, RougeLConfig};
+
+/// ROUGE-L result structure
+#[derive(Debug, Clone)]
+pub struct RougeLResult {
+    pub f_measure: f64,
+    pub precision: f64,
+    pub recall: f64,
+}
+
+impl RougeLResult {
+    pub fn new(f_measure: f64, precision: f64, recall: f64) -> Self {
+        RougeLResult {
+            f_measure,
+            precision,
+            recall,
+        }
+    }
+}
+
+/// Calculate ROUGE-L score (F-measure, Precision, Recall)
+pub fn calculate_rouge_l(
+    candidate: &str,
+    reference: &str,
+    tokenizer: Tokenizer,
+    strategy: LcsStrategy,
+) -> RougeLResult {
+    let mut interner = Interner::new();
+    let candidate_words = interner.tokenize(tokenizer, candidate);
+    let reference_words = interner.tokenize(tokenizer, reference);
+    
+    rouge_l_from_tokens(&candidate_words, &reference_words, strategy)
+}
+
+/// Calculate ROUGE-L score from already tokenized (or interned) candidate and reference
+pub fn rouge_l_from_tokens<T: Eq + Hash + Sync>(
+    candidate_words: &[T],
+    reference_words: &[T],
+    strategy: LcsStrategy,
+) -> RougeLResult {
+    rouge_l_from_tokens_in(&mut Scratch::default(), candidate_words, reference_words, strategy)
+}
+
+/// [`rouge_l_from_tokens`] reusing the LCS buffers in `scratch`
+pub fn rouge_l_from_tokens_in<T: Eq + Hash + Sync>(
+    scratch: &mut Scratch,
+    candidate_words: &[T],
+    reference_words: &[T],
+    strategy: LcsStrategy,
+) -> RougeLResult {
+    if candidate_words.is_empty() || reference_words.is_empty() {
+        return RougeLResult::new(0.0, 0.0, 0.0);
+    }
+    
+    let lcs = strategy.length_in(scratch, candidate_words, reference_words);
+    scores_from_lcs(lcs, candidate_words.len(), reference_words.len())
+}
+
+/// Score a candidate against several references and keep the result with
+/// the best F-measure, the earliest on ties, as SQuAD does over its gold
+/// answers; no references score zero
+pub fn rouge_l_best_of_in<T: Eq + Hash + Sync, R: AsRef<[T]>>(
+    scratch: &mut Scratch,
+    candidate_words: &[T],
+    references: &[R],
+    strategy: LcsStrategy,
+) -> RougeLResult {
+    best_result(
+        references
+            .iter()
+            .map(|reference| rouge_l_from_tokens_in(scratch, candidate_words, reference.as_ref(), strategy)),
+    )
+}
+
+/// The result with the best F-measure, the earliest on ties
+fn best_result(results: impl Iterator<Item = RougeLResult>) -> RougeLResult {
+    results
+        .reduce(|best, result| if result.f_measure > best.f_measure { result } else { best })
+        .unwrap_or_else(|| RougeLResult::new(0.0, 0.0, 0.0))
+}
+
+/// Precision, recall and F-measure for an LCS of length `lcs`
+fn scores_from_lcs(lcs: usize, candidate_len: usize, reference_len: usize) -> RougeLResult {
+    let precision = lcs as f64 / candidate_len as f64;
+    let recall = lcs as f64 / reference_len as f64;
+    
+    let f_measure = if precision + recall > 0.0 {
+        2.0 * precision * recall / (precision + recall)
+    } else {
+        0.0
+    };
+    
+    RougeLResult::new(f_measure, precision, recall)
+}
+
+/// Score the pair only if its F-measure reaches `min_f`
+///
+/// The F-measure equals `2 × LCS / (|candidate| + |reference|)`, and the LCS
+/// never exceeds the token multiset intersection, so pairs whose bound falls
+/// short are rejected without running the LCS at all.
+pub fn score_at_least(
+    candidate: &str,
+    reference: &str,
+    min_f: f64,
+    tokenizer: Tokenizer,
+    strategy: LcsStrategy,
+) -> Option<RougeLResult> {
+    let mut interner = Interner::new();
+    let candidate_words = interner.tokenize(tokenizer, candidate);
+    let reference_words = interner.tokenize(tokenizer, reference);
+
+    if f_measure_bound(&candidate_words, &reference_words) < min_f {
+        return None;
+    }
+    let result = rouge_l_from_tokens(&candidate_words, &reference_words, strategy);
+    (result.f_measure >= min_f).then_some(result)
+}
+
+/// Upper bound on the F-measure from the token multiset intersection, in
+/// O(m + n) instead of the LCS cost
+pub fn f_measure_bound<T: Eq + Hash>(candidate_words: &[T], reference_words: &[T]) -> f64 {
+    if candidate_words.is_empty() || reference_words.is_empty() {
+        return 0.0;
+    }
+    let bound = lcs::multiset_bound(candidate_words, reference_words);
+    scores_from_lcs(bound, candidate_words.len(), reference_words.len()).f_measure
+}
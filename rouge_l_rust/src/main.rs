@@ -1,198 +1,948 @@
-use std::time::Instant;
-
-/// Calculate the Longest Common Subsequence (LCS) between two sequences
-fn longest_common_subsequence(seq1: &[String], seq2: &[String]) -> usize {
-    let m = seq1.len();
-    let n = seq2.len();
-    
-    let mut dp = vec![vec![0; n + 1]; m + 1];
-    
-    for i in 1..=m {
-        for j in 1..=n {
-            if seq1[i - 1] == seq2[j - 1] {
-                dp[i][j] = dp[i - 1][j - 1] + 1;
+mod alignment;
+mod analyze;
+#[cfg(feature = "archive")]
+mod archive;
+mod batch;
+mod bench;
+mod breakdown;
+mod brevity;
+mod cache;
+mod checkpoint;
+mod cli;
+mod compare;
+mod compress;
+mod config;
+mod copy_rate;
+mod correlate;
+mod daemon;
+mod dedup;
+mod delta;
+mod demo;
+mod display;
+mod filter;
+mod format;
+#[cfg(feature = "server")]
+mod gate;
+mod grade;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "hf")]
+mod hf;
+#[cfg(feature = "sqlite")]
+mod history;
+mod input;
+#[cfg(feature = "arrow")]
+mod ipc;
+mod leaderboard;
+mod matrix;
+#[cfg(feature = "mlflow")]
+mod mlflow;
+mod nearest;
+mod oracle;
+mod output;
+mod rouge155;
+#[cfg(feature = "server")]
+mod server;
+mod side_by_side;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod squad;
+mod stats;
+mod stream;
+mod telemetry;
+mod tensorboard;
+#[cfg(feature = "tui")]
+mod tui;
+mod verify;
+#[cfg(feature = "wandb")]
+mod wandb;
+mod watch;
+
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::{CommandFactory, Parser};
+
+use rouge_l_rust::mbr;
+use rouge_l_rust::summary::Variant;
+
+use breakdown::Breakdown;
+use checkpoint::Checkpoint;
+use cli::{BatchArgs, Cli, Command, InputArgs, OutputArgs, SelectArgs};
+use config::{Config, Settings};
+use output::OutputFormat;
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = run(cli) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let _telemetry = telemetry::init(&cli)?;
+    let config = Config::discover(cli.config.as_deref())?;
+    let settings = Settings::resolve(&cli, &config);
+    for option in cli.rouge155_args.iter().flat_map(|args| &args.unsupported) {
+        eprintln!("warning: ROUGE-1.5.5 option {} is not supported and is ignored", option);
+    }
+    #[cfg(feature = "parallel")]
+    if let Some(threads) = cli.threads.or(config.threads) {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()?;
+    }
+
+    match cli.command {
+        None => {
+            let suite = match &cli.examples_file {
+                Some(path) => demo::ExampleSuite::load(path)?,
+                None => demo::ExampleSuite::builtin(),
+            };
+            demo::run(&suite, &settings);
+        }
+        Some(Command::Score(args)) => {
+            if args.side_by_side && settings.variant == Variant::RougeLsum {
+                return Err("--side-by-side needs --variant rouge-l; ROUGE-Lsum has no single LCS per pair".into());
+            }
+            let pair = input::Pair {
+                id: "1".to_string(),
+                candidate: args.candidate,
+                references: args.references,
+                hypotheses: Vec::new(),
+            };
+            let (scores, stats) = batch::score_pairs(std::slice::from_ref(&pair), &settings, None, None)?;
+            report_truncation(&stats, &settings);
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            if args.side_by_side {
+                let matched = alignment::best_match(&pair, &settings);
+                side_by_side::render(&mut out, &matched, pair.references.len(), side_by_side::width(args.width))?;
+                writeln!(out)?;
+            }
+            let result = &scores[0].result;
+            writeln!(out, "F-Measure: {}", settings.score_format.format(result.f_measure))?;
+            writeln!(out, "Precision: {}", settings.score_format.format(result.precision))?;
+            writeln!(out, "Recall:    {}", settings.score_format.format(result.recall))?;
+            writeln!(out, "Signature: {}", settings.signature())?;
+        }
+        Some(Command::Batch(mut args)) => {
+            if args.select.filters.is_empty() {
+                args.select.filters.clone_from(&config.threshold.filter);
+            }
+            #[cfg(feature = "tui")]
+            if args.tui {
+                let pairs = load_pairs(&args.input)?;
+                let scores = score_and_select(&pairs, &args.select, &settings)?;
+                tui::run(&pairs, &scores, &settings)?;
+                return Ok(());
+            }
+            if args.stream {
+                let summary = run_stream(&args, &config, &settings)?;
+                #[cfg(feature = "mlflow")]
+                log_to_mlflow(&args, &summary, None, &settings)?;
+                write_tensorboard(&args, &summary, &settings)?;
+                check_thresholds(&args, &config, &summary)?;
+                return Ok(());
+            }
+            let (_pairs, scores) = run_batch(&args.input, &args.select, &args.output, &config, &settings)?;
+            let summary = batch::Summary::from_scores(&scores);
+            #[cfg(feature = "mlflow")]
+            log_to_mlflow(&args, &summary, Some(&scores), &settings)?;
+            write_tensorboard(&args, &summary, &settings)?;
+            #[cfg(feature = "sqlite")]
+            if let Some(database) = &args.track {
+                let signature = settings.signature();
+                let run = history::record(database, args.tag.as_deref(), &scores, &settings.score_format, &signature)?;
+                eprintln!("track: recorded run {} of {} pairs in {}", run, scores.len(), database.display());
+            }
+            #[cfg(feature = "wandb")]
+            if let Some(project) = &args.wandb_project {
+                let run = wandb::WandbRun {
+                    project: project.clone(),
+                    entity: args.wandb_entity.clone(),
+                    run_id: args.wandb_run_id.clone(),
+                    name: args.wandb_name.clone(),
+                };
+                wandb::log(&run, &_pairs, &scores, &settings, args.wandb_worst, &args.wandb_python)?;
+            }
+            check_thresholds(&args, &config, &summary)?;
+        }
+        Some(Command::Watch(mut args)) => {
+            if args.select.filters.is_empty() {
+                args.select.filters.clone_from(&config.threshold.filter);
+            }
+            // Writing into the watched database would trigger another rescore
+            #[cfg(feature = "sqlite")]
+            if args.output.write_scores.is_some() {
+                return Err("--write-scores cannot be used with watch".into());
+            }
+            watch::watch_paths(&args.input.paths(), |changed| {
+                if !changed.is_empty() {
+                    println!("\n--- Rescoring after change to {} ---\n", changed.join(", "));
+                }
+                run_batch(&args.input, &args.select, &args.output, &config, &settings).map(drop)
+            })?;
+        }
+        Some(Command::Bench(args)) => {
+            let pairs = load_pairs(&args.input)?;
+            let report = bench::run(&pairs, &settings, args.warmup, args.iterations)?;
+            bench::print_report(&report, &settings.signature());
+        }
+        Some(Command::Grade(args)) => {
+            let pairs = grade::read_pairs(&args.solution, &args.submission, &args.id_column, &args.target_column)?;
+            let (scores, stats) = batch::score_pairs(&pairs, &settings, None, None)?;
+            report_truncation(&stats, &settings);
+            grade::write_report(&mut io::stdout().lock(), &scores, &settings)?;
+        }
+        Some(Command::Verify(args)) => {
+            let pairs = load_pairs(&args.input)?;
+            if settings.token_limit.is_some() {
+                eprintln!("warning: --max-tokens applies here only; the Python implementation scores whole texts");
+            }
+            let report = verify::compare(&pairs, &settings, &args.python, args.against, args.tolerance)?;
+            verify::print_report(&report, args.against, args.tolerance, args.show, &settings.signature());
+            if !report.discrepancies.is_empty() {
+                return Err(format!(
+                    "{} of {} pairs differ from {} by more than {:e}",
+                    report.discrepancies.len(),
+                    report.pairs,
+                    args.against.name(),
+                    args.tolerance
+                )
+                .into());
+            }
+        }
+        Some(Command::Analyze(args)) => {
+            if settings.variant == Variant::RougeLsum {
+                return Err("analyze needs --variant rouge-l; ROUGE-Lsum has no single LCS per pair".into());
+            }
+            let pairs = load_pairs(&args.input)?;
+            let errors = analyze::token_errors(&pairs, &settings);
+            let mut out = io::stdout().lock();
+            analyze::write_tables(&mut out, &errors, pairs.len(), args.top, &settings.score_format)?;
+        }
+        Some(Command::Compare(args)) => {
+            if !(args.confidence > 0.0 && args.confidence < 100.0) {
+                return Err(format!("--confidence must be between 0 and 100, got {}", args.confidence).into());
+            }
+            let fields = input::RecordFields {
+                candidate: args.candidate_field.clone(),
+                reference: args.reference_field.clone(),
+                id: args.id_field.clone(),
+            };
+            let read = |path: &Path| {
+                input::read_records(path, input::InputFormat::detect(path), fields.clone(), None, None)
+            };
+            let joined = compare::join(read(&args.baseline)?, read(&args.system)?);
+            let unmatched = [
+                ("baseline", "system", &joined.baseline_only),
+                ("system", "baseline", &joined.system_only),
+            ];
+            for (side, other, ids) in unmatched {
+                if !ids.is_empty() {
+                    eprintln!("warning: {} {} ids have no {} pair: {}", ids.len(), side, other, input::list_ids(ids));
+                }
+            }
+            if joined.baseline.is_empty() {
+                return Err("the baseline and the system have no ids in common".into());
+            }
+            let (baseline, stats) = batch::score_pairs(&joined.baseline, &settings, None, None)?;
+            report_truncation(&stats, &settings);
+            let (system, stats) = batch::score_pairs(&joined.system, &settings, None, None)?;
+            report_truncation(&stats, &settings);
+            let iters = args.resamples as usize;
+            let differences = compare::compare(&baseline, &system, args.test, iters, args.seed, args.confidence);
+            let mut out = io::stdout().lock();
+            let (pairs, confidence) = (baseline.len(), args.confidence);
+            compare::write_report(&mut out, &differences, args.test, pairs, confidence, &settings.score_format)?;
+            writeln!(out, "Signature: {}", settings.signature())?;
+        }
+        Some(Command::CopyRate(args)) => {
+            let fields = input::RecordFields {
+                candidate: args.candidate_field.clone(),
+                reference: args.source_field.clone(),
+                id: args.id_field.clone(),
+            };
+            // Candidate and source come from the same record, read once so stdin and pipes work
+            let pairs = if input::is_csv(&args.input) {
+                input::read_csv_pairs(&args.input, &fields)?
             } else {
-                dp[i][j] = dp[i - 1][j].max(dp[i][j - 1]);
+                input::read_records(&args.input, input::InputFormat::detect(&args.input), fields, None, None)?
+            };
+            let mut out = BufWriter::new(io::stdout().lock());
+            let corpus = copy_rate::write_rates(&mut out, &pairs, args.min_length as usize, &settings)?;
+            out.flush()?;
+            copy_rate::write_summary(&mut io::stderr(), &corpus, &settings.score_format)?;
+        }
+        Some(Command::Correlate(args)) => {
+            let pairs = load_pairs(&args.input)?;
+            let (scores, stats) = batch::score_pairs(&pairs, &settings, None, None)?;
+            report_truncation(&stats, &settings);
+            let ratings = correlate::read_ratings(&args.human, &args.human_id_column, &args.rating_column)?;
+            let rated = correlate::join(&scores, ratings);
+            let unmatched = [("scored", "rating", &rated.unrated), ("rated", "scored pair", &rated.unscored)];
+            for (side, other, ids) in unmatched {
+                if !ids.is_empty() {
+                    eprintln!("warning: {} {} ids have no {}: {}", ids.len(), side, other, input::list_ids(ids));
+                }
+            }
+            if rated.ratings.len() < 2 {
+                return Err(format!("{} pairs have a human rating; correlations need at least 2", rated.ratings.len()).into());
+            }
+            let mut out = io::stdout().lock();
+            correlate::write_report(&mut out, &rated, &settings.score_format)?;
+            writeln!(out, "Signature: {}", settings.signature())?;
+        }
+        Some(Command::Dedup(args)) => {
+            if !(args.threshold > 0.0 && args.threshold <= 1.0) {
+                return Err(format!("--threshold must be above 0 and at most 1, got {}", args.threshold).into());
+            }
+            let records = input::read_keyed(&args.input, &args.id_field, &args.text_field)?;
+            let (ids, texts): (Vec<String>, Vec<String>) = records
+                .into_iter()
+                .map(|(id, texts)| (id, texts.join("\n")))
+                .unzip();
+            let (duplicates, stats) = dedup::near_duplicates(&texts, args.threshold, &settings);
+            eprintln!(
+                "dedup: {} pairs of {} records reach F-Measure {}; the length and bag-of-words bounds ruled out \
+                 {} and {} of {} comparisons",
+                duplicates.len(),
+                texts.len(),
+                args.threshold,
+                stats.by_length,
+                stats.by_overlap,
+                stats.comparisons
+            );
+            let mut out = BufWriter::new(io::stdout().lock());
+            if args.clusters {
+                let clusters = dedup::clusters(texts.len(), &duplicates);
+                let count = clusters.iter().max().map_or(0, |&last| last + 1);
+                eprintln!("dedup: {} records form {} clusters", texts.len(), count);
+                dedup::write_clusters(&mut out, &clusters, &ids)?;
+            } else {
+                dedup::write_pairs(&mut out, &duplicates, &ids, &settings.score_format)?;
+            }
+            out.flush()?;
+        }
+        Some(Command::DiffResults(args)) => {
+            if args.tolerance.is_nan() || args.tolerance < 0.0 {
+                return Err(format!("--tolerance must be at least 0, got {}", args.tolerance).into());
+            }
+            let old = delta::read_results(&args.old)?;
+            let new = delta::read_results(&args.new)?;
+            if let (Some(before), Some(after)) = (&old.signature, &new.signature) {
+                if before != after {
+                    eprintln!("warning: the results were scored with different settings: {} vs {}", before, after);
+                }
+            }
+            let delta = delta::diff(old.results, new.results, args.tolerance);
+            for (side, ids) in [("old", &delta.old_only), ("new", &delta.new_only)] {
+                if !ids.is_empty() {
+                    eprintln!("warning: {} ids are only in the {} results: {}", ids.len(), side, input::list_ids(ids));
+                }
+            }
+            let fields = input::RecordFields {
+                candidate: args.candidate_field.clone(),
+                reference: args.reference_field.clone(),
+                id: args.id_field.clone(),
+            };
+            let texts = |path: &Option<PathBuf>| -> Result<_, input::InputError> {
+                let Some(path) = path else { return Ok(None) };
+                let pairs = input::read_joined_pairs(path, path, &fields)?.pairs;
+                Ok(Some(pairs.into_iter().map(|pair| (pair.id.clone(), pair)).collect::<delta::Texts>()))
+            };
+            let (old_texts, new_texts) = (texts(&args.old_input)?, texts(&args.new_input)?);
+            let mut out = io::stdout().lock();
+            delta::write_report(&mut out, &delta, args.top, &settings.score_format)?;
+            if old_texts.is_some() || new_texts.is_some() {
+                let texts = (old_texts.as_ref(), new_texts.as_ref());
+                delta::write_drill_down(&mut out, &delta, args.top, texts, &settings.score_format)?;
+            }
+        }
+        Some(Command::Leaderboard(args)) => {
+            let systems = leaderboard::expand(&args.systems)?;
+            let fields = input::RecordFields {
+                candidate: args.candidate_field.clone(),
+                reference: args.reference_field.clone(),
+                id: args.id_field.clone(),
+            };
+            let entries = leaderboard::rank(&systems, &args.references, &fields, &settings)?;
+            let mut out = io::stdout().lock();
+            leaderboard::write_table(&mut out, &entries, &settings.score_format)?;
+            writeln!(out, "Signature: {}", settings.signature())?;
+        }
+        Some(Command::Matrix(args)) => {
+            let rows = input::read_lines(&args.rows)?;
+            let columns = input::read_lines(&args.cols)?;
+            let (mut file, mut stdout);
+            let writer = match &args.output {
+                #[cfg(feature = "parquet")]
+                Some(path) if matrix::is_parquet(path) => {
+                    matrix::MatrixWriter::parquet(path, columns.len(), &settings.signature())
+                        .map_err(|e| format!("cannot write {}: {}", path.display(), e))?
+                }
+                #[cfg(not(feature = "parquet"))]
+                Some(path) if matrix::is_parquet(path) => {
+                    return Err("writing a Parquet matrix needs the `parquet` feature".into());
+                }
+                Some(path) => {
+                    let created = File::create(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))?;
+                    file = BufWriter::new(created);
+                    matrix::MatrixWriter::csv(&mut file, columns.len())?
+                }
+                None => {
+                    stdout = io::stdout().lock();
+                    matrix::MatrixWriter::csv(&mut stdout, columns.len())?
+                }
+            };
+            let chunk_bytes = args.chunk_memory as usize * 1024 * 1024;
+            let chunks = matrix::write_matrix(writer, &rows, &columns, &settings, chunk_bytes)?;
+            eprintln!("matrix: scored {} rows x {} columns in {} chunks", rows.len(), columns.len(), chunks);
+        }
+        Some(Command::Mbr(args)) => {
+            let records = input::read_keyed(&args.input, &args.id_field, &args.candidate_field)?;
+            let mut out = BufWriter::new(io::stdout().lock());
+            for (id, samples) in records {
+                let Some(selection) = mbr::select(&samples, settings.tokenizer, settings.variant, settings.lcs_strategy)
+                else {
+                    continue;
+                };
+                let json_score = |value| output::json_score(value, &settings.score_format);
+                let line = serde_json::json!({
+                    "id": id,
+                    "index": selection.index,
+                    "candidate": samples[selection.index],
+                    "expected_f_measure": json_score(selection.utility()),
+                    "expected": selection.expected.iter().map(|&value| json_score(value)).collect::<Vec<_>>(),
+                });
+                serde_json::to_writer(&mut out, &line)?;
+                writeln!(out)?;
             }
+            out.flush()?;
+        }
+        Some(Command::Nearest(args)) => {
+            let read = |path: &Path, field: &str| -> Result<(Vec<String>, Vec<String>), input::InputError> {
+                let records = input::read_keyed(path, &args.id_field, field)?;
+                Ok(records.into_iter().map(|(id, texts)| (id, texts.join("\n"))).unzip())
+            };
+            let (candidate_ids, candidates) = read(&args.candidates, &args.candidate_field)?;
+            let (reference_ids, references) = read(&args.references, &args.reference_field)?;
+            let (neighbours, stats) = nearest::nearest(&candidates, &references, args.top as usize, &settings);
+            eprintln!(
+                "nearest: scored {} of {} candidate-reference pairs, {} sharing a token",
+                stats.scored, stats.pairs, stats.overlapping
+            );
+            let mut out = BufWriter::new(io::stdout().lock());
+            nearest::write_neighbours(&mut out, &candidate_ids, &neighbours, &reference_ids, &settings.score_format)?;
+            out.flush()?;
+        }
+        Some(Command::Oracle(args)) => {
+            let fields = input::RecordFields {
+                candidate: args.source_field.clone(),
+                reference: args.reference_field.clone(),
+                id: args.id_field.clone(),
+            };
+            let references = args.references.as_deref().unwrap_or(&args.input);
+            let joined = input::read_joined_pairs(&args.input, references, &fields)?;
+            let unmatched = [
+                ("source", "reference", &joined.unmatched_candidates),
+                ("reference", "source", &joined.unmatched_references),
+            ];
+            for (side, other, ids) in unmatched {
+                if !ids.is_empty() {
+                    eprintln!("warning: {} {} ids have no {}: {}", ids.len(), side, other, input::list_ids(ids));
+                }
+            }
+            let max_sentences = args.max_sentences.map(|max| max as usize);
+            let mut out = BufWriter::new(io::stdout().lock());
+            for pair in &joined.pairs {
+                let oracle = oracle::greedy(&pair.candidate, &pair.references, args.metric, &settings, max_sentences);
+                oracle::write_record(&mut out, pair, &oracle, &settings.score_format)?;
+            }
+            out.flush()?;
+        }
+        #[cfg(feature = "sqlite")]
+        Some(Command::History(args)) => {
+            let runs = history::runs(&args.database)?;
+            let tags = args.from.as_deref().zip(args.to.as_deref());
+            let changes = match tags {
+                Some((from, to)) => Some(history::changes(&args.database, from, to)?),
+                None => None,
+            };
+            let mut out = io::stdout().lock();
+            history::write_trend(&mut out, &runs, &settings.score_format)?;
+            if let (Some((from, to)), Some((changes, unshared))) = (tags, changes) {
+                let signatures: Vec<&str> = runs
+                    .iter()
+                    .filter(|run| run.tag.as_deref().is_some_and(|tag| tag == from || tag == to))
+                    .map(|run| run.signature.as_str())
+                    .collect();
+                if signatures.windows(2).any(|pair| pair[0] != pair[1]) {
+                    let signatures = signatures.join(" vs ");
+                    eprintln!("warning: {} and {} were scored with different settings: {}", from, to, signatures);
+                }
+                if unshared > 0 {
+                    eprintln!("warning: {} ids were scored in only one of {} and {}", unshared, from, to);
+                }
+                history::write_changes(&mut out, &changes, (from, to), args.top, &settings.score_format)?;
+            }
+        }
+        #[cfg(feature = "server")]
+        Some(Command::Serve(args)) => server::run(&args, settings)?,
+        Some(Command::Daemon) => daemon::run(&settings)?,
+        Some(Command::Completions { shell }) => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+        }
+        Some(Command::Man { out_dir: None }) => {
+            clap_mangen::Man::new(Cli::command()).render(&mut io::stdout())?;
+        }
+        Some(Command::Man { out_dir: Some(dir) }) => {
+            clap_mangen::generate_to(Cli::command(), dir)?;
         }
     }
-    
-    dp[m][n]
+    Ok(())
 }
 
-/// Tokenize text into words (simple whitespace splitting)
-fn tokenize(text: &str) -> Vec<String> {
-    text.trim()
-        .to_lowercase()
-        .split_whitespace()
-        .map(|s| s.to_string())
-        .collect()
-}
+/// Read the pairs selected on the command line, warning about candidate
+/// files without a reference, lines left unpaired by --lenient, ids found
+/// in only one of the joined files and unanswered SQuAD questions
+#[tracing::instrument(name = "read", level = "info", skip_all)]
+fn load_pairs(args: &InputArgs) -> Result<Vec<input::Pair>, input::InputError> {
+    if let Some((path, format)) = args.input_source() {
+        let mut errors = error_log(args, false)?;
+        let pairs = input::read_records(path, format, record_fields(args), args.query(), errors.as_mut())?;
+        report_skipped(errors)?;
+        return Ok(pairs);
+    }
+    if let (Some(candidates), Some(references)) = (&args.candidate_lines, &args.reference_lines) {
+        let line_pairs = input::read_line_pairs(candidates, references, args.lenient)?;
+        if line_pairs.unpaired > 0 {
+            eprintln!(
+                "warning: line counts differ; scoring the first {} lines and skipping {}",
+                line_pairs.pairs.len(),
+                line_pairs.unpaired
+            );
+        }
+        return Ok(line_pairs.pairs);
+    }
+    if let (Some(candidates), Some(references)) = (&args.candidate_file, &args.reference_file) {
+        let joined = input::read_joined_pairs(candidates, references, &record_fields(args))?;
+        let unmatched = [
+            ("candidate", "reference", &joined.unmatched_candidates),
+            ("reference", "candidate", &joined.unmatched_references),
+        ];
+        for (side, other, ids) in unmatched {
+            if !ids.is_empty() {
+                eprintln!("warning: {} {} ids have no {}: {}", ids.len(), side, other, input::list_ids(ids));
+            }
+        }
+        return Ok(joined.pairs);
+    }
+    if let Some(config) = &args.rouge155_config {
+        return rouge155::read_config(config);
+    }
+    #[cfg(feature = "hf")]
+    if let (Some(dataset), Some(predictions)) = (&args.hf_dataset, &args.hf_predictions) {
+        let split = hf::HubSplit::new(
+            dataset,
+            args.hf_config.as_deref(),
+            args.hf_split.as_deref(),
+            args.hf_reference_column.as_deref(),
+            args.hf_id_column.as_deref(),
+        )?;
+        let hub = hf::read_pairs(&split, predictions, &record_fields(args))?;
+        if !hub.unpredicted.is_empty() {
+            eprintln!(
+                "warning: {} of {} rows have no prediction and score 0: {}",
+                hub.unpredicted.len(),
+                hub.pairs.len(),
+                input::list_ids(&hub.unpredicted)
+            );
+        }
+        if !hub.unmatched.is_empty() {
+            eprintln!(
+                "warning: {} prediction ids are not in the split: {}",
+                hub.unmatched.len(),
+                input::list_ids(&hub.unmatched)
+            );
+        }
+        return Ok(hub.pairs);
+    }
+    if let (Some(gold), Some(predictions)) = (&args.squad_gold, &args.squad_predictions) {
+        let squad = squad::read_pairs(gold, predictions)?;
+        if !squad.unanswered.is_empty() {
+            eprintln!(
+                "warning: {} of {} questions have no prediction and score 0",
+                squad.unanswered.len(),
+                squad.pairs.len()
+            );
+        }
+        return Ok(squad.pairs);
+    }
 
-/// ROUGE-L result structure
-#[derive(Debug, Clone)]
-pub struct RougeLResult {
-    pub f_measure: f64,
-    pub precision: f64,
-    pub recall: f64,
+    let (Some(candidates), Some(references)) = (&args.candidates, &args.references) else {
+        unreachable!(
+            "clap requires --input, a pair of directories, line files or id-keyed files, both SQuAD files, a ROUGE-1.5.5 config or a Hub dataset"
+        );
+    };
+    let dir_pairs = input::read_dir_pairs(candidates, references)?;
+    for name in &dir_pairs.unmatched {
+        eprintln!("warning: no reference for candidate {}", name);
+    }
+    Ok(dir_pairs.pairs)
 }
 
-impl RougeLResult {
-    pub fn new(f_measure: f64, precision: f64, recall: f64) -> Self {
-        RougeLResult {
-            f_measure,
-            precision,
-            recall,
+/// The error file malformed records go to under --on-error skip, appended
+/// to when resuming a run
+fn error_log(args: &InputArgs, resume: bool) -> Result<Option<input::ErrorLog>, input::InputError> {
+    match args.on_error.unwrap_or_default() {
+        input::OnError::Fail => Ok(None),
+        input::OnError::Skip => {
+            let path = args.error_file.as_deref().unwrap_or(Path::new("errors.jsonl"));
+            input::ErrorLog::create(path, resume).map(Some)
         }
     }
 }
 
-/// Calculate ROUGE-L score (F-measure, Precision, Recall)
-pub fn calculate_rouge_l(candidate: &str, reference: &str) -> RougeLResult {
-    let candidate_words = tokenize(candidate);
-    let reference_words = tokenize(reference);
-    
-    if candidate_words.is_empty() || reference_words.is_empty() {
-        return RougeLResult::new(0.0, 0.0, 0.0);
-    }
-    
-    let lcs = longest_common_subsequence(&candidate_words, &reference_words);
-    
-    let precision = lcs as f64 / candidate_words.len() as f64;
-    let recall = lcs as f64 / reference_words.len() as f64;
-    
-    let f_measure = if precision + recall > 0.0 {
-        2.0 * precision * recall / (precision + recall)
-    } else {
-        0.0
+/// Report on stderr how many malformed records were skipped, and where they went
+fn report_skipped(errors: Option<input::ErrorLog>) -> Result<(), input::InputError> {
+    let Some(errors) = errors else {
+        return Ok(());
     };
-    
-    RougeLResult::new(f_measure, precision, recall)
+    let path = errors.path().display().to_string();
+    let skipped = errors.finish()?;
+    if skipped > 0 {
+        eprintln!("warning: skipped {} malformed records; see {}", skipped, path);
+    }
+    Ok(())
 }
 
-fn main() {
-    // Test examples - progressing from basic to advanced
-    let examples: Vec<(&str, &str)> = vec![
-        // Level 1: Basic Text (Simple sentences)
-        (
-            "The quick brown fox jumps over the lazy dog",
-            "A quick brown fox jumps over a lazy dog"
-        ),
-        (
-            "Machine learning is a subset of artificial intelligence",
-            "Machine learning forms part of artificial intelligence systems"
-        ),
-        
-        // Level 2: Structured Text (Lists, formatting)
-        (
-            "Key features include: security authentication and data encryption",
-            "Main features are: authentication security and encryption of data"
-        ),
-        (
-            "User name: John Doe, Email: john@example.com, Status: Active",
-            "Name: John Doe, Email address: john@example.com, Status: Active user"
-        ),
-        
-        // Level 3: JSON-like structured data
-        (
-            r#"{"user": {"name": "Alice", "age": 30, "city": "New York"}}"#,
-            r#"{"user": {"name": "Alice", "age": 30, "location": "New York"}}"#
-        ),
-        (
-            r#"{"employees": [{"id": 1, "name": "Bob"}, {"id": 2, "name": "Charlie"}]}"#,
-            r#"{"staff": [{"id": 1, "name": "Bob"}, {"id": 2, "name": "Charlie"}]}"#
-        ),
-        (
-            r#"{"status": "success", "data": {"count": 42, "items": ["a", "b"]}}"#,
-            r#"{"result": "success", "payload": {"total": 42, "list": ["a", "b"]}}"#
-        ),
-        
-        // Level 4: HTML content
-        (
-            "<div><h1>Title</h1><p>Content here</p></div>",
-            "<section><h1>Title</h1><p>Content here</p></section>"
-        ),
-        (
-            r#"<a href="/page">Link</a> <img src="photo.jpg" alt="Image">"#,
-            r#"<a href="/page">Link</a> <img src="photo.jpg" alt="Photo">"#
-        ),
-        (
-            "<ul><li>Item 1</li><li>Item 2</li><li>Item 3</li></ul>",
-            "<ol><li>Item 1</li><li>Item 2</li><li>Item 3</li></ol>"
-        ),
-        
-        // Level 5: Mixed complex content (JSON + text)
-        (
-            r#"The API returned {"status": 200, "message": "OK"} with user data"#,
-            r#"API response was {"status": 200, "message": "OK"} containing user information"#
-        ),
-        (
-            r#"Error: {"code": 404, "error": "Not Found"} occurred at 2024-01-15"#,
-            r#"Error occurred: {"code": 404, "error": "Not Found"} on date 2024-01-15"#
-        ),
-        
-        // Level 6: Real-world complex scenarios
-        (
-            "Natural language processing enables computers to understand human language through advanced algorithms",
-            "NLP allows machines to comprehend natural human communication using sophisticated algorithmic approaches"
-        ),
-        (
-            "The cat sat on the mat while the dog played in the yard",
-            "The dog played in the yard while the cat sat on the mat"
-        ),
-        (
-            "POST /api/users HTTP/1.1\nHost: api.example.com\nContent-Type: application/json\n{\"name\": \"Test\"}",
-            "POST /api/users HTTP/1.1\nHost: api.example.com\nContent-Type: application/json\n{\"username\": \"Test\"}"
-        ),
-        (
-            "<html><body><script>console.log('Hello');</script><div>Content</div></body></html>",
-            "<html><body><div>Content</div><script>console.log('Hello');</script></body></html>"
-        )
-    ];
-    
-    let level_names = vec![
-        "Basic Text",
-        "Structured Text",
-        "JSON Data",
-        "HTML Content",
-        "Mixed Content",
-        "Real-world Scenarios"
-    ];
-    
-    println!("=== ROUGE-L Rust Implementation ===\n");
-    println!("Testing {} examples (Basic to Advanced)\n", examples.len());
-    
-    let level_starts = vec![0, 2, 4, 7, 10, 12];
-    
-    for (i, (candidate, reference)) in examples.iter().enumerate() {
-        // Determine level
-        if let Some(&start_idx) = level_starts.iter().find(|&&idx| i == idx) {
-            let current_level = level_starts.iter().position(|&x| x == start_idx).unwrap() + 1;
-            println!("--- Level {}: {} ---", current_level, level_names[current_level - 1]);
-        }
-        
-        let start = Instant::now();
-        let result = calculate_rouge_l(candidate, reference);
-        let duration = start.elapsed();
-        
-        let candidate_display = if candidate.len() > 80 {
-            format!("{}...", &candidate[..77])
+/// Field or column names selected on the command line
+fn record_fields(args: &InputArgs) -> input::RecordFields {
+    input::RecordFields {
+        candidate: args.candidate_field.clone(),
+        reference: args.reference_field.clone(),
+        id: args.id_field.clone(),
+    }
+}
+
+/// The --bucket-width, which must leave at least one bucket
+fn bucket_width(output: &OutputArgs) -> Result<f64, String> {
+    match output.bucket_width {
+        width if width > 0.0 && width <= 1.0 => Ok(width),
+        width => Err(format!("--bucket-width must be in (0, 1], got {}", width)),
+    }
+}
+
+/// Score the pairs, then apply the requested filters and sort order
+///
+/// A lower bound on `f` in the filters lets pairs that cannot reach it skip
+/// the LCS entirely, and `--cache-dir` reuses scores from earlier runs; both
+/// are reported on stderr.
+fn score_and_select(
+    pairs: &[input::Pair],
+    select: &SelectArgs,
+    settings: &Settings,
+) -> Result<Vec<batch::ScoredPair>, Box<dyn Error>> {
+    let min_f = filter::min_f_measure(&select.filters);
+    let mut cache = match &select.cache_dir {
+        Some(dir) => Some(cache::ScoreCache::open(dir, &settings.signature())?),
+        None => None,
+    };
+    let (mut scores, stats) = batch::score_pairs(
+        pairs,
+        settings,
+        min_f,
+        cache.as_mut(),
+    )?;
+    if let Some(min_f) = min_f {
+        eprintln!(
+            "prefilter: skipped {} of {} LCS computations (bag-of-words bound below f >= {})",
+            stats.skipped, stats.pairs, min_f
+        );
+    }
+    report_truncation(&stats, settings);
+    if let (Some(cache), Some(dir)) = (&mut cache, &select.cache_dir) {
+        cache.save()?;
+        eprintln!("cache: reused {} of {} scores from {}", stats.cached, stats.pairs, dir.display());
+    }
+    filter::select(&mut scores, &select.filters, select.sort_by, select.descending);
+    Ok(scores)
+}
+
+/// Score the selected inputs and print the results, returning the pairs with
+/// their scores
+fn run_batch(
+    input: &InputArgs,
+    select: &SelectArgs,
+    output: &OutputArgs,
+    config: &Config,
+    settings: &Settings,
+) -> Result<(Vec<input::Pair>, Vec<batch::ScoredPair>), Box<dyn Error>> {
+    let format = output.format.or(config.output.format).unwrap_or_default();
+    if settings.variant == Variant::RougeLsum {
+        let lcs_outputs = [
+            (output.export_alignments.is_some(), "--export-alignments"),
+            (output.export_edits.is_some(), "--export-edits"),
+            (output.report_details, "--report-details"),
+        ];
+        if let Some((_, flag)) = lcs_outputs.into_iter().find(|&(set, _)| set) {
+            return Err(format!("{} needs --variant rouge-l; ROUGE-Lsum has no single LCS per pair", flag).into());
+        }
+    }
+    if settings.variant != Variant::RougeLsum && output.export_sentences.is_some() {
+        return Err("--export-sentences needs --variant rouge-lsum; ROUGE-L does not split sentences".into());
+    }
+    let bucket_width = bucket_width(output)?;
+    let pairs = load_pairs(input)?;
+    let scores = score_and_select(&pairs, select, settings)?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut files = open_outputs(output, false)?;
+    let mut outputs: Vec<(&mut dyn Write, OutputFormat)> = vec![(&mut out, format)];
+    outputs.extend(files.iter_mut().map(|(file, format)| (file as &mut dyn Write, *format)));
+    let report = output.report.as_deref().filter(|_| output.report_details);
+    let details = report.map(|report| output::relative_url(&output::details_dir(report)));
+    let signature = settings.signature();
+    let id_width = scores.iter().map(|s| display::width(&s.id)).max().unwrap_or(0);
+    let last = outputs.len() - 1;
+    let mut writers = output::ResultWriters::new(
+        outputs,
+        &settings.score_format,
+        &signature,
+        id_width,
+        settings.token_limit.is_some(),
+        settings.bootstrap,
+    )?;
+    writers.bucket_width(bucket_width);
+    // The --report is the last output
+    if let Some(details) = &details {
+        writers.link_details(last, details);
+    }
+    for scored in &scores {
+        writers.write(scored)?;
+    }
+    writers.finish(&batch::Summary::from_scores(&scores))?;
+    drop(out);
+    for (file, _) in &mut files {
+        file.flush()?;
+    }
+    if let Some(report) = report {
+        output::write_detail_pages(report, &pairs, &scores, settings)
+            .map_err(|e| format!("cannot write the pages of {}: {}", report.display(), e))?;
+    }
+    if let Some(path) = &output.export_alignments {
+        let file = File::create(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))?;
+        let mut out = BufWriter::new(file);
+        alignment::write_alignments(&mut out, &pairs, &scores, settings)?;
+        out.flush()?;
+    }
+    if let Some(path) = &output.export_edits {
+        let file = File::create(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))?;
+        let mut out = BufWriter::new(file);
+        alignment::write_edit_scripts(&mut out, &pairs, &scores, settings)?;
+        out.flush()?;
+    }
+    if let Some(path) = &output.export_sentences {
+        let file = File::create(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))?;
+        let mut out = BufWriter::new(file);
+        alignment::write_sentence_contributions(&mut out, &pairs, &scores, settings)?;
+        out.flush()?;
+    }
+    #[cfg(feature = "sqlite")]
+    if let (Some(table), Some((database, _))) = (&output.write_scores, input.input_source()) {
+        let written = sqlite::write_scores(database, table, &scores, &settings.score_format, &settings.signature())?;
+        eprintln!("sqlite: wrote {} scores to table {} in {}", written, table, database.display());
+    }
+    if output.histogram {
+        // Keep machine-readable output parseable by drawing the chart on stderr
+        if format == OutputFormat::Text {
+            output::write_histogram(&mut stdout.lock(), &scores, bucket_width)?;
         } else {
-            candidate.to_string()
-        };
-        
-        let reference_display = if reference.len() > 80 {
-            format!("{}...", &reference[..77])
+            output::write_histogram(&mut io::stderr(), &scores, bucket_width)?;
+        }
+    }
+    if output.stats {
+        if format == OutputFormat::Text {
+            stats::write_table(&mut stdout.lock(), &scores, &settings.score_format)?;
+        } else {
+            stats::write_table(&mut io::stderr(), &scores, &settings.score_format)?;
+        }
+    }
+    if let Some(Breakdown::Length) = output.breakdown {
+        let groups = breakdown::by_reference_length(&pairs, &scores, settings, &output.length_buckets);
+        let heading = "Reference length";
+        if format == OutputFormat::Text {
+            breakdown::write_table(&mut stdout.lock(), heading, &groups, &settings.score_format)?;
         } else {
-            reference.to_string()
-        };
-        
-        println!("Example {}:", i + 1);
-        println!("  Candidate: {}", candidate_display);
-        println!("  Reference: {}", reference_display);
-        println!("  Result:    F-Measure: {:.4}, Precision: {:.4}, Recall: {:.4}", 
-                 result.f_measure, result.precision, result.recall);
-        println!("  Time:      {:?}\n", duration);
+            breakdown::write_table(&mut io::stderr(), heading, &groups, &settings.score_format)?;
+        }
+    }
+    if output.brevity {
+        let brevity = brevity::analyze(&pairs, &scores, settings);
+        if format == OutputFormat::Text {
+            brevity::write_report(&mut stdout.lock(), &brevity, &settings.score_format)?;
+        } else {
+            brevity::write_report(&mut io::stderr(), &brevity, &settings.score_format)?;
+        }
+    }
+    Ok((pairs, scores))
+}
+
+/// Score an input file through the streaming pipeline, printing each result as
+/// soon as it is scored
+fn run_stream(args: &BatchArgs, config: &Config, settings: &Settings) -> Result<batch::Summary, Box<dyn Error>> {
+    let format = args.output.format.or(config.output.format).unwrap_or_default();
+    let bucket_width = bucket_width(&args.output)?;
+    let Some((path, input_format)) = args.input.input_source() else {
+        unreachable!("clap requires --input with --stream");
+    };
+    let signature = settings.signature();
+    let checkpoint = match &args.checkpoint {
+        // A resumed run appends to the output of the interrupted one
+        Some(_)
+            if !std::iter::once(format)
+                .chain(args.output.targets().iter().map(|target| target.format))
+                .all(|format| matches!(format, OutputFormat::Text | OutputFormat::Ndjson)) =>
+        {
+            return Err("--checkpoint needs text or ndjson output, which a resumed run can append to".into())
+        }
+        Some(state) if args.resume => Some((state.as_path(), Checkpoint::resume(state, path, &signature)?)),
+        Some(state) => Some((state.as_path(), Checkpoint::new(path, &signature))),
+        None => None,
+    };
+    let reader = input::open_records(path, input_format, record_fields(&args.input), args.input.query())?;
+    let mut errors = error_log(&args.input, args.resume)?;
+    let min_f = filter::min_f_measure(&args.select.filters);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    // Results are printed before later ids are known, so text output leaves
+    // the id column unaligned
+    let mut files = open_outputs(&args.output, args.resume)?;
+    let mut outputs: Vec<(&mut dyn Write, OutputFormat)> = vec![(&mut out, format)];
+    outputs.extend(files.iter_mut().map(|(file, format)| (file as &mut dyn Write, *format)));
+    let mut writer = output::ResultWriters::new(outputs, &settings.score_format, &signature, 0, settings.token_limit.is_some(), settings.bootstrap)?;
+    writer.bucket_width(bucket_width);
+    let (summary, stats) = stream::run(
+        reader,
+        errors.as_mut(),
+        &args.select.filters,
+        settings,
+        args.in_flight as usize,
+        checkpoint,
+        &mut writer,
+    )?;
+    writer.finish(&summary)?;
+    for (file, _) in &mut files {
+        file.flush()?;
+    }
+    report_skipped(errors)?;
+    if let Some(min_f) = min_f {
+        eprintln!(
+            "prefilter: skipped {} of {} LCS computations (bag-of-words bound below f >= {})",
+            stats.skipped, stats.pairs, min_f
+        );
+    }
+    report_truncation(&stats, settings);
+    Ok(summary)
+}
+
+/// Fail a batch run whose mean F-measure, precision or recall is below
+/// --min-f-measure, --min-precision or --min-recall, or the `[threshold]`
+/// of the config
+fn check_thresholds(args: &BatchArgs, config: &Config, summary: &batch::Summary) -> Result<(), Box<dyn Error>> {
+    let threshold = &config.threshold;
+    let bounds = [
+        ("F-measure", args.min_f_measure.or(threshold.f_measure), summary.mean.f_measure),
+        ("precision", args.min_precision.or(threshold.precision), summary.mean.precision),
+        ("recall", args.min_recall.or(threshold.recall), summary.mean.recall),
+    ];
+    for (score, bound, mean) in bounds {
+        if let Some(bound) = bound.filter(|&bound| mean.is_nan() || mean < bound) {
+            let count = summary.count;
+            return Err(format!("mean {} {:.4} over {} pairs is below the threshold {}", score, mean, count, bound).into());
+        }
     }
+    Ok(())
 }
 
+/// Record the averages of a batch run at --step in a new event file of --tb-logdir
+fn write_tensorboard(args: &BatchArgs, summary: &batch::Summary, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    if let Some(logdir) = &args.tb_logdir {
+        let path = tensorboard::write_scalars(logdir, args.step, summary, settings)
+            .map_err(|e| format!("cannot write TensorBoard events to {}: {}", logdir.display(), e))?;
+        eprintln!("tensorboard: wrote step {} to {}", args.step, path.display());
+    }
+    Ok(())
+}
+
+/// Log the averages of a batch run to the --mlflow-uri run, and the per-pair
+/// results too with --mlflow-artifacts
+#[cfg(feature = "mlflow")]
+fn log_to_mlflow(
+    args: &BatchArgs,
+    summary: &batch::Summary,
+    scores: Option<&[batch::ScoredPair]>,
+    settings: &Settings,
+) -> Result<(), Box<dyn Error>> {
+    let (Some(uri), Some(run_id)) = (&args.mlflow_uri, &args.mlflow_run_id) else {
+        return Ok(());
+    };
+    let run = mlflow::Run::new(uri, run_id);
+    run.log_summary(summary, settings)?;
+    eprintln!("mlflow: logged the averages of {} pairs to run {}", summary.count, run.id);
+    if let (true, Some(scores)) = (args.mlflow_artifacts, scores) {
+        let mut contents = Vec::new();
+        output::write_results(
+            vec![(&mut contents, OutputFormat::Ndjson)],
+            scores,
+            &settings.score_format,
+            &settings.signature(),
+            settings.token_limit.is_some(),
+            settings.bootstrap,
+        )?;
+        run.log_artifact(mlflow::SCORES_ARTIFACT, contents)?;
+        eprintln!("mlflow: uploaded {}", mlflow::SCORES_ARTIFACT);
+    }
+    Ok(())
+}
+
+/// An --output or --report file and the format written to it
+type OutputFile = (BufWriter<File>, OutputFormat);
+
+/// Create the --output and --report files, or append to them when resuming a run
+fn open_outputs(output: &OutputArgs, append: bool) -> Result<Vec<OutputFile>, Box<dyn Error>> {
+    output
+        .targets()
+        .into_iter()
+        .map(|target| {
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&target.path)
+                .map_err(|e| format!("cannot create {}: {}", target.path.display(), e))?;
+            Ok((BufWriter::new(file), target.format))
+        })
+        .collect()
+}
+
+/// Report on stderr how many pairs the token limit cut down
+fn report_truncation(stats: &batch::ScoreStats, settings: &Settings) {
+    if let Some(limit) = settings.token_limit.filter(|_| stats.truncated > 0) {
+        eprintln!(
+            "truncation: cut {} of {} pairs down to {} tokens per text",
+            stats.truncated,
+            stats.pairs - stats.skipped,
+            limit.max_tokens
+        );
+    }
+}
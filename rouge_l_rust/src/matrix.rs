@@ -0,0 +1,135 @@
+#[cfg(feature = "parquet")]
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+#[cfg(feature = "parquet")]
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+#[cfg(feature = "parquet")]
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use rouge_l_rust::intern::Vocab;
+use rouge_l_rust::lcs::Scratch;
+
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+
+/// Where the rows of the matrix go as they are scored
+///
+/// Every row starts with its 1-based line number in the `row` column,
+/// followed by one F-measure column per column text, named after its line
+/// number; scores are rounded as they are printed.
+pub enum MatrixWriter<'a> {
+    Csv(Box<csv::Writer<&'a mut dyn Write>>),
+    #[cfg(feature = "parquet")]
+    Parquet(Box<ArrowWriter<File>>, SchemaRef),
+}
+
+impl<'a> MatrixWriter<'a> {
+    pub fn csv(out: &'a mut dyn Write, columns: usize) -> io::Result<MatrixWriter<'a>> {
+        let mut writer = csv::Writer::from_writer(out);
+        writer.write_record(std::iter::once("row".to_string()).chain((1..=columns).map(|column| column.to_string())))?;
+        Ok(MatrixWriter::Csv(Box::new(writer)))
+    }
+
+    /// Write a Parquet file at `path`, each chunk of rows flushed as a row
+    /// group; the signature is stored in the schema metadata
+    #[cfg(feature = "parquet")]
+    pub fn parquet(path: &Path, columns: usize, signature: &str) -> io::Result<MatrixWriter<'a>> {
+        let fields = std::iter::once(Field::new("row", DataType::UInt64, false))
+            .chain((1..=columns).map(|column| Field::new(column.to_string(), DataType::Float64, false)));
+        let metadata = [("signature".to_string(), signature.to_string())].into();
+        let schema = Arc::new(Schema::new_with_metadata(fields.collect::<Vec<_>>(), metadata));
+        let file = File::create(path)?;
+        let writer = ArrowWriter::try_new(file, schema.clone(), None).map_err(io::Error::other)?;
+        Ok(MatrixWriter::Parquet(Box::new(writer), schema))
+    }
+
+    /// Write `rows`, the first of which is row `first` counting from 0
+    fn write(&mut self, first: usize, rows: &[Vec<f64>], score_format: &ScoreFormat) -> io::Result<()> {
+        match self {
+            MatrixWriter::Csv(writer) => {
+                for (number, row) in (first + 1..).zip(rows) {
+                    let scores = row.iter().map(|&score| score_format.format(score));
+                    writer.write_record(std::iter::once(number.to_string()).chain(scores))?;
+                }
+                writer.flush()
+            }
+            #[cfg(feature = "parquet")]
+            MatrixWriter::Parquet(writer, schema) => {
+                let numbers = UInt64Array::from_iter_values((first as u64 + 1..).take(rows.len()));
+                let mut columns: Vec<ArrayRef> = vec![Arc::new(numbers)];
+                for column in 0..schema.fields().len() - 1 {
+                    let scores = rows.iter().map(|row| score_format.round(row[column]));
+                    columns.push(Arc::new(Float64Array::from_iter_values(scores)));
+                }
+                let batch = RecordBatch::try_new(schema.clone(), columns).map_err(io::Error::other)?;
+                writer.write(&batch).map_err(io::Error::other)?;
+                writer.flush().map_err(io::Error::other)
+            }
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            MatrixWriter::Csv(mut writer) => writer.flush(),
+            #[cfg(feature = "parquet")]
+            MatrixWriter::Parquet(writer, _) => writer.close().map(drop).map_err(io::Error::other),
+        }
+    }
+}
+
+/// Score every row text as the candidate against every column text as the
+/// reference and write the F-measures row by row, returning the number of
+/// chunks written
+///
+/// Only `chunk_bytes` worth of scores are held at once: the rows are scored
+/// a chunk at a time, each chunk in parallel with the `parallel` feature,
+/// and written out before the next one starts.
+pub fn write_matrix(
+    mut writer: MatrixWriter,
+    rows: &[String],
+    columns: &[String],
+    settings: &Settings,
+    chunk_bytes: usize,
+) -> io::Result<usize> {
+    let mut vocab = Vocab::with_variant(settings.tokenizer, settings.variant);
+    let column_tokens: Vec<Vec<u32>> = columns.iter().map(|text| vocab.tokenize(text)).collect();
+    let row_tokens: Vec<Vec<u32>> = rows.iter().map(|text| vocab.tokenize(text)).collect();
+    let chunk_rows = (chunk_bytes / (columns.len().max(1) * size_of::<f64>())).max(1);
+
+    let score_row = |scratch: &mut Scratch, tokens: &Vec<u32>| -> Vec<f64> {
+        column_tokens
+            .iter()
+            .map(|column| {
+                let reference = std::slice::from_ref(column);
+                settings.variant.best_of_in(scratch, tokens, reference, settings.lcs_strategy).f_measure
+            })
+            .collect()
+    };
+    let mut chunks = 0;
+    for (chunk, tokens) in row_tokens.chunks(chunk_rows).enumerate() {
+        #[cfg(feature = "parallel")]
+        let scores: Vec<Vec<f64>> = tokens.par_iter().map_init(Scratch::default, score_row).collect();
+        #[cfg(not(feature = "parallel"))]
+        let scores: Vec<Vec<f64>> = {
+            let mut scratch = Scratch::default();
+            tokens.iter().map(|tokens| score_row(&mut scratch, tokens)).collect()
+        };
+        writer.write(chunk * chunk_rows, &scores, &settings.score_format)?;
+        chunks += 1;
+    }
+    writer.finish()?;
+    Ok(chunks)
+}
+
+/// Whether the matrix written to `path` is Parquet rather than CSV
+pub fn is_parquet(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "parquet")
+}
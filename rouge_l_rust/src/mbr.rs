@@ -0,0 +1,74 @@
+//! Minimum Bayes risk selection among sampled candidates, with ROUGE-L as
+//! the utility
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::intern::Vocab;
+use crate::lcs::{LcsStrategy, Scratch};
+use crate::summary::Variant;
+use crate::tokenize::Tokenizer;
+
+/// Candidate chosen among the samples of one input
+#[derive(Debug, Clone)]
+pub struct Selection {
+    /// Position of the chosen sample
+    pub index: usize,
+    /// Expected utility of every sample: its mean F-measure against each
+    /// other sample taken as the reference
+    pub expected: Vec<f64>,
+}
+
+impl Selection {
+    /// Expected utility of the chosen sample
+    pub fn utility(&self) -> f64 {
+        self.expected[self.index]
+    }
+}
+
+/// F-measure of every sample scored against every other one as reference,
+/// by rows of candidates; the diagonal is left at 0
+///
+/// With the `parallel` feature the rows are scored on the rayon thread pool.
+pub fn utility_matrix<R: AsRef<[u32]> + Sync>(samples: &[R], variant: Variant, strategy: LcsStrategy) -> Vec<Vec<f64>> {
+    let row = |scratch: &mut Scratch, i: usize| {
+        (0..samples.len())
+            .map(|j| match i == j {
+                true => 0.0,
+                false => {
+                    let reference = std::slice::from_ref(&samples[j]);
+                    variant.best_of_in(scratch, samples[i].as_ref(), reference, strategy).f_measure
+                }
+            })
+            .collect()
+    };
+    #[cfg(feature = "parallel")]
+    let matrix = (0..samples.len()).into_par_iter().map_init(Scratch::default, row).collect();
+    #[cfg(not(feature = "parallel"))]
+    let matrix = {
+        let mut scratch = Scratch::default();
+        (0..samples.len()).map(|i| row(&mut scratch, i)).collect()
+    };
+    matrix
+}
+
+/// Choose the sample with the highest expected ROUGE-L against the others,
+/// the earliest on ties; `None` without samples
+///
+/// A single sample is chosen with an expected utility of 0.
+pub fn select<S: AsRef<str>>(
+    samples: &[S],
+    tokenizer: Tokenizer,
+    variant: Variant,
+    strategy: LcsStrategy,
+) -> Option<Selection> {
+    let mut vocab = Vocab::with_variant(tokenizer, variant);
+    let tokens: Vec<Vec<u32>> = samples.iter().map(|sample| vocab.tokenize(sample.as_ref())).collect();
+    let others = samples.len().saturating_sub(1).max(1) as f64;
+    let expected: Vec<f64> = utility_matrix(&tokens, variant, strategy)
+        .iter()
+        .map(|row| row.iter().sum::<f64>() / others)
+        .collect();
+    let index = (0..expected.len()).reduce(|best, i| if expected[i] > expected[best] { i } else { best })?;
+    Some(Selection { index, expected })
+}
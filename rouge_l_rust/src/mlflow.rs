@@ -0,0 +1,153 @@
+use std::env;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use crate::batch::Summary;
+use crate::config::Settings;
+
+/// Artifact the per-pair results are uploaded as
+pub const SCORES_ARTIFACT: &str = "rouge_l/scores.jsonl";
+
+/// Scheme of artifact stores served through the tracking server itself
+const PROXIED_ARTIFACTS: &str = "mlflow-artifacts:";
+
+/// An existing run of an MLflow tracking server
+#[derive(Debug, Clone)]
+pub struct Run {
+    /// Base URL of the tracking server, without a trailing slash
+    pub uri: String,
+    pub id: String,
+}
+
+/// Error returned by the tracking server or raised reaching it
+#[derive(Debug)]
+pub enum MlflowError {
+    /// A request failed, with the server's own message when it gave one
+    Request(String, String),
+    /// The run's artifacts live in a store the tracking server does not proxy
+    ArtifactStore(String),
+}
+
+impl fmt::Display for MlflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MlflowError::Request(url, message) => write!(f, "mlflow: {}: {}", url, message),
+            MlflowError::ArtifactStore(uri) => write!(
+                f,
+                "mlflow: run artifacts are stored at {}, which the tracking server does not serve; \
+                 start it with --serve-artifacts to upload them",
+                uri
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MlflowError {}
+
+impl Run {
+    pub fn new(uri: &str, id: &str) -> Self {
+        Run {
+            uri: uri.trim_end_matches('/').to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    /// Log the corpus averages as `rouge_l_f_measure`, `rouge_l_precision`,
+    /// `rouge_l_recall` and `rouge_l_pairs` (`rouge_lsum_*` for Lsum), and
+    /// the signature as the `rouge_l.signature` tag
+    ///
+    /// A tag rather than a param, since params cannot change once logged
+    /// and a run may be scored again with other settings.
+    pub fn log_summary(&self, summary: &Summary, settings: &Settings) -> Result<(), MlflowError> {
+        let prefix = settings.variant.name().replace('-', "_");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let metrics: Vec<Value> = [
+            ("f_measure", summary.mean.f_measure),
+            ("precision", summary.mean.precision),
+            ("recall", summary.mean.recall),
+            ("pairs", summary.count as f64),
+        ]
+        .into_iter()
+        .map(|(name, value)| {
+            json!({ "key": format!("{}_{}", prefix, name), "value": value, "timestamp": timestamp, "step": 0 })
+        })
+        .collect();
+        let body = json!({
+            "run_id": self.id,
+            "metrics": metrics,
+            "tags": [{ "key": "rouge_l.signature", "value": settings.signature() }],
+        });
+        self.request("/api/2.0/mlflow/runs/log-batch", Call::Post(body))?;
+        Ok(())
+    }
+
+    /// Upload `contents` as the artifact at `path` of the run
+    ///
+    /// Only runs whose artifact store the tracking server proxies (its
+    /// `mlflow-artifacts:` scheme) can be written to over HTTP.
+    pub fn log_artifact(&self, path: &str, contents: Vec<u8>) -> Result<(), MlflowError> {
+        let info = self.request(&format!("/api/2.0/mlflow/runs/get?run_id={}", self.id), Call::Get)?;
+        let artifact_uri = info["run"]["info"]["artifact_uri"].as_str().unwrap_or_default().to_string();
+        let Some(location) = artifact_uri.strip_prefix(PROXIED_ARTIFACTS) else {
+            return Err(MlflowError::ArtifactStore(artifact_uri));
+        };
+        // mlflow-artifacts://HOST/PATH names a host, mlflow-artifacts:/PATH does not
+        let location = match location.strip_prefix("//") {
+            Some(hosted) => hosted.split_once('/').map_or("", |(_, path)| path),
+            None => location,
+        };
+        let endpoint = format!("/api/2.0/mlflow-artifacts/artifacts/{}/{}", location.trim_matches('/'), path);
+        self.request(&endpoint, Call::Put(contents))?;
+        Ok(())
+    }
+
+    /// Send one request, authenticated with `MLFLOW_TRACKING_TOKEN` when it
+    /// is set, and parse the JSON response
+    fn request(&self, endpoint: &str, call: Call) -> Result<Value, MlflowError> {
+        let url = format!("{}{}", self.uri, endpoint);
+        let failed = |message: String| MlflowError::Request(url.clone(), message);
+        let authorization = env::var("MLFLOW_TRACKING_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .map(|token| format!("Bearer {}", token));
+        let response = match call {
+            Call::Get => prepare(ureq::get(&url), &authorization).call(),
+            Call::Post(body) => prepare(ureq::post(&url), &authorization)
+                .content_type("application/json")
+                .send(body.to_string()),
+            Call::Put(contents) => prepare(ureq::put(&url), &authorization)
+                .content_type("application/octet-stream")
+                .send(contents),
+        }
+        .map_err(|err| failed(err.to_string()))?;
+        let status = response.status();
+        let text = response.into_body().read_to_string().map_err(|err| failed(err.to_string()))?;
+        let value: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = value["message"].as_str().map_or_else(|| status.to_string(), String::from);
+            return Err(failed(message));
+        }
+        Ok(value)
+    }
+}
+
+/// A request to the tracking server and its body
+enum Call {
+    Get,
+    Post(Value),
+    Put(Vec<u8>),
+}
+
+/// Set the `Authorization` header and keep error statuses as responses, so
+/// the message MLflow puts in their body can be reported
+fn prepare<B>(request: ureq::RequestBuilder<B>, authorization: &Option<String>) -> ureq::RequestBuilder<B> {
+    let request = request.config().http_status_as_error(false).build();
+    match authorization {
+        Some(value) => request.header("Authorization", value),
+        None => request,
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde_json::json;
+
+use rouge_l_rust::intern::Vocab;
+use rouge_l_rust::lcs::Scratch;
+use rouge_l_rust::summary;
+
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+use crate::output;
+
+/// F-measures closer than this are ties; the same ratio computed from
+/// different token counts can differ in the last bits
+const TIE: f64 = 1e-12;
+
+/// A reference of the pool close to a candidate
+pub struct Neighbour {
+    /// Position of the reference in the pool
+    pub reference: usize,
+    pub f_measure: f64,
+}
+
+/// How many candidate-reference pairs needed the exact LCS
+#[derive(Debug, Default)]
+pub struct NearestStats {
+    /// Candidates times references
+    pub pairs: usize,
+    /// Pairs sharing at least one token, the only ones considered
+    pub overlapping: usize,
+    /// Pairs scored with the LCS
+    pub scored: usize,
+}
+
+/// The `k` references of the pool with the highest F-measure for every
+/// candidate, best first and the earliest on ties
+///
+/// An inverted index from tokens to the references holding them gives the
+/// bag-of-words overlap of the candidate with every reference sharing a
+/// token, which bounds the F-measure. References are then scored in order
+/// of that bound until it falls below the k-th best exact score, so most of
+/// the pool never reaches the LCS; references sharing no token are never
+/// listed. With the `parallel` feature the candidates are searched on the
+/// rayon thread pool.
+pub fn nearest(
+    candidates: &[String],
+    references: &[String],
+    k: usize,
+    settings: &Settings,
+) -> (Vec<Vec<Neighbour>>, NearestStats) {
+    let mut vocab = Vocab::with_variant(settings.tokenizer, settings.variant);
+    let reference_tokens: Vec<Vec<u32>> = references.iter().map(|text| vocab.tokenize(text)).collect();
+    let candidate_tokens: Vec<Vec<u32>> = candidates.iter().map(|text| vocab.tokenize(text)).collect();
+    let reference_lengths: Vec<usize> = reference_tokens.iter().map(|tokens| summary::token_count(tokens)).collect();
+
+    // Token ID -> (reference, occurrences in it)
+    let mut index: Vec<Vec<(usize, usize)>> = Vec::new();
+    for (reference, tokens) in reference_tokens.iter().enumerate() {
+        for (token, count) in counts(tokens) {
+            let token = token as usize;
+            if index.len() <= token {
+                index.resize_with(token + 1, Vec::new);
+            }
+            index[token].push((reference, count));
+        }
+    }
+
+    let search = |scratch: &mut Scratch, tokens: &Vec<u32>| {
+        let mut stats = NearestStats::default();
+        let mut overlap: HashMap<usize, usize> = HashMap::new();
+        for (token, count) in counts(tokens) {
+            for &(reference, occurrences) in index.get(token as usize).into_iter().flatten() {
+                *overlap.entry(reference).or_insert(0) += count.min(occurrences);
+            }
+        }
+        let length = summary::token_count(tokens);
+        let mut bounds: Vec<(f64, usize)> = overlap
+            .into_iter()
+            .map(|(reference, overlap)| {
+                (2.0 * overlap as f64 / (length + reference_lengths[reference]) as f64, reference)
+            })
+            .collect();
+        bounds.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        stats.overlapping = bounds.len();
+
+        let mut best: Vec<Neighbour> = Vec::with_capacity(k + 1);
+        for (bound, reference) in bounds {
+            if best.len() == k && bound < best[k - 1].f_measure - TIE {
+                break;
+            }
+            stats.scored += 1;
+            let result = settings.variant.best_of_in(
+                scratch,
+                tokens,
+                std::slice::from_ref(&reference_tokens[reference]),
+                settings.lcs_strategy,
+            );
+            let at = best.partition_point(|neighbour| {
+                neighbour.f_measure > result.f_measure + TIE
+                    || (neighbour.f_measure >= result.f_measure - TIE && neighbour.reference < reference)
+            });
+            best.insert(
+                at,
+                Neighbour {
+                    reference,
+                    f_measure: result.f_measure,
+                },
+            );
+            best.truncate(k);
+        }
+        (best, stats)
+    };
+    #[cfg(feature = "parallel")]
+    let found: Vec<(Vec<Neighbour>, NearestStats)> =
+        candidate_tokens.par_iter().map_init(Scratch::default, search).collect();
+    #[cfg(not(feature = "parallel"))]
+    let found: Vec<(Vec<Neighbour>, NearestStats)> = {
+        let mut scratch = Scratch::default();
+        candidate_tokens.iter().map(|tokens| search(&mut scratch, tokens)).collect()
+    };
+
+    let mut stats = NearestStats {
+        pairs: candidates.len() * references.len(),
+        ..NearestStats::default()
+    };
+    let mut neighbours = Vec::with_capacity(found.len());
+    for (best, found_stats) in found {
+        neighbours.push(best);
+        stats.overlapping += found_stats.overlapping;
+        stats.scored += found_stats.scored;
+    }
+    (neighbours, stats)
+}
+
+/// Occurrences of every token, not counting sentence breaks
+fn counts(tokens: &[u32]) -> HashMap<u32, usize> {
+    let mut counts = HashMap::new();
+    for &token in summary::words(tokens).iter() {
+        *counts.entry(token).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Write the neighbours of every candidate as a JSON line with its id and
+/// the ids and F-measures of its nearest references, best first
+pub fn write_neighbours(
+    out: &mut dyn Write,
+    candidate_ids: &[String],
+    neighbours: &[Vec<Neighbour>],
+    reference_ids: &[String],
+    score_format: &ScoreFormat,
+) -> io::Result<()> {
+    for (id, neighbours) in candidate_ids.iter().zip(neighbours) {
+        let nearest: Vec<_> = neighbours
+            .iter()
+            .map(|neighbour| {
+                json!({
+                    "id": reference_ids[neighbour.reference],
+                    "f_measure": output::json_score(neighbour.f_measure, score_format),
+                })
+            })
+            .collect();
+        serde_json::to_writer(&mut *out, &json!({ "id": id, "nearest": nearest }))?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
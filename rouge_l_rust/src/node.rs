@@ -0,0 +1,109 @@
+//! Node.js addon built with napi-rs, so JavaScript evaluation dashboards can
+//! score text in-process instead of shelling out to Python
+//!
+//! Built with the `node` feature; the shared library is loaded by Node as a
+//! `.node` file. `scoreRougeL` blocks the calling thread, while
+//! `scoreRougeLAsync` scores on the libuv thread pool and returns a promise.
+
+use clap::ValueEnum;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::lcs::LcsStrategy;
+use crate::reference::ReferenceSet;
+use crate::summary::Variant;
+use crate::tokenize::Tokenizer;
+use crate::RougeLResult;
+
+/// Tokenizer and variant, named as on the command line; both default as
+/// there
+#[napi(object)]
+pub struct ScoreOptions {
+    /// "whitespace" (default) or "rouge-score"
+    #[napi(ts_type = "\"whitespace\" | \"rouge-score\"")]
+    pub tokenizer: Option<String>,
+    /// "rouge-l" (default) or "rouge-lsum"
+    #[napi(ts_type = "\"rouge-l\" | \"rouge-lsum\"")]
+    pub variant: Option<String>,
+}
+
+#[napi(object)]
+pub struct RougeLScore {
+    pub f_measure: f64,
+    pub precision: f64,
+    pub recall: f64,
+}
+
+impl From<RougeLResult> for RougeLScore {
+    fn from(result: RougeLResult) -> Self {
+        RougeLScore {
+            f_measure: result.f_measure,
+            precision: result.precision,
+            recall: result.recall,
+        }
+    }
+}
+
+/// Options checked on the calling thread, so bad ones throw synchronously
+fn parse_options(options: Option<ScoreOptions>) -> Result<(Tokenizer, Variant)> {
+    fn parse<T: ValueEnum + Default>(value: Option<String>, name: &str) -> Result<T> {
+        match value {
+            Some(value) => T::from_str(&value, false)
+                .map_err(|_| Error::new(Status::InvalidArg, format!("unknown {} `{}`", name, value))),
+            None => Ok(T::default()),
+        }
+    }
+    let options = options.unwrap_or(ScoreOptions {
+        tokenizer: None,
+        variant: None,
+    });
+    Ok((parse(options.tokenizer, "tokenizer")?, parse(options.variant, "variant")?))
+}
+
+fn score(candidate: &str, reference: &str, tokenizer: Tokenizer, variant: Variant) -> RougeLResult {
+    ReferenceSet::with_variant(tokenizer, variant).score(candidate, reference, LcsStrategy::Auto)
+}
+
+/// Score a candidate against a reference on the calling thread
+#[napi(js_name = "scoreRougeL")]
+pub fn score_rouge_l(candidate: String, reference: String, options: Option<ScoreOptions>) -> Result<RougeLScore> {
+    let (tokenizer, variant) = parse_options(options)?;
+    Ok(score(&candidate, &reference, tokenizer, variant).into())
+}
+
+pub struct ScoreTask {
+    candidate: String,
+    reference: String,
+    tokenizer: Tokenizer,
+    variant: Variant,
+}
+
+impl Task for ScoreTask {
+    type Output = RougeLResult;
+    type JsValue = RougeLScore;
+
+    fn compute(&mut self) -> Result<RougeLResult> {
+        Ok(score(&self.candidate, &self.reference, self.tokenizer, self.variant))
+    }
+
+    fn resolve(&mut self, _env: Env, result: RougeLResult) -> Result<RougeLScore> {
+        Ok(result.into())
+    }
+}
+
+/// Score a candidate against a reference off the main thread, resolving to
+/// the same scores as `scoreRougeL`
+#[napi(js_name = "scoreRougeLAsync")]
+pub fn score_rouge_l_async(
+    candidate: String,
+    reference: String,
+    options: Option<ScoreOptions>,
+) -> Result<AsyncTask<ScoreTask>> {
+    let (tokenizer, variant) = parse_options(options)?;
+    Ok(AsyncTask::new(ScoreTask {
+        candidate,
+        reference,
+        tokenizer,
+        variant,
+    }))
+}
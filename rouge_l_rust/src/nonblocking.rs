@@ -0,0 +1,112 @@
+//! Async scoring for tokio services, so axum or tonic handlers can await a
+//! score without wrapping every call in `spawn_blocking`
+//!
+//! Built with the `async` feature. The LCS runs on tokio's blocking pool,
+//! never on the async workers. Dropping a returned future, or cancelling the
+//! [`CancelToken`] given to [`score_many_async`], skips the pairs that have
+//! not been scored yet; the pair being scored at that moment still runs to
+//! the end, as the DP cannot be interrupted midway.
+
+use std::fmt;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::lcs::LcsStrategy;
+use crate::reference::ReferenceSet;
+use crate::summary::Variant;
+use crate::tokenize::Tokenizer;
+use crate::RougeLResult;
+
+/// Shared flag that stops [`score_many_async`] before its next pair
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    /// Ask every call holding a clone of this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The call was cancelled, or the runtime shut down, before it finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("scoring was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Cancels its token when dropped, i.e. when the future awaiting the
+/// blocking task is dropped before the task is done
+struct CancelOnDrop(CancelToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Score a candidate against a reference on the blocking pool, with the
+/// same scores as [`ReferenceSet::score`]
+///
+/// Panics raised while scoring are resumed in the caller.
+pub async fn score_async(
+    candidate: String,
+    reference: String,
+    tokenizer: Tokenizer,
+    variant: Variant,
+    strategy: LcsStrategy,
+) -> Result<RougeLResult, Cancelled> {
+    let pairs = vec![(candidate, reference)];
+    let mut results = score_many_async(pairs, tokenizer, variant, strategy, CancelToken::new()).await?;
+    Ok(results.remove(0))
+}
+
+/// Score `(candidate, reference)` pairs in order on one blocking thread,
+/// tokenizing each distinct reference once, and return the results in
+/// input order
+///
+/// Returns [`Cancelled`] once `cancel` is cancelled; pairs are checked one at
+/// a time, so a long batch stops soon after the caller gives up on it.
+pub async fn score_many_async(
+    pairs: Vec<(String, String)>,
+    tokenizer: Tokenizer,
+    variant: Variant,
+    strategy: LcsStrategy,
+    cancel: CancelToken,
+) -> Result<Vec<RougeLResult>, Cancelled> {
+    let dropped = CancelOnDrop(CancelToken::new());
+    let abandoned = dropped.0.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        let mut references = ReferenceSet::with_variant(tokenizer, variant);
+        pairs
+            .iter()
+            .map(|(candidate, reference)| {
+                if cancel.is_cancelled() || abandoned.is_cancelled() {
+                    return Err(Cancelled);
+                }
+                Ok(references.score(candidate, reference, strategy))
+            })
+            .collect()
+    });
+    match task.await {
+        Ok(results) => results,
+        Err(err) => match err.try_into_panic() {
+            Ok(payload) => panic::resume_unwind(payload),
+            Err(_) => Err(Cancelled),
+        },
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use serde_json::json;
+
+use rouge_l_rust::intern::Interner;
+use rouge_l_rust::lcs::Scratch;
+use rouge_l_rust::summary::{self, SENTENCE_BREAK};
+
+use crate::config::Settings;
+use crate::format::ScoreFormat;
+use crate::input::Pair;
+use crate::output;
+
+/// Score the extractive oracle is built to maximize
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OracleMetric {
+    /// F-measure of --variant over the selected sentences in document order
+    #[default]
+    RougeL,
+    /// F-measure of the bigrams shared with the reference, counted within sentences (ROUGE-2)
+    Rouge2,
+}
+
+/// Source sentences picked for a reference and the score they reach
+pub struct Oracle {
+    /// Indices of the picked sentences, in document order
+    pub sentences: Vec<usize>,
+    pub f_measure: f64,
+}
+
+/// Build the extractive oracle of `source` for `references`: starting from
+/// no sentences, repeatedly add the source sentence that raises the score
+/// the most, until none does or `max_sentences` are picked
+///
+/// Sentences are split as for ROUGE-Lsum, one per line or between `<s>`
+/// tags. Against several references the best score counts.
+pub fn greedy(
+    source: &str,
+    references: &[String],
+    metric: OracleMetric,
+    settings: &Settings,
+    max_sentences: Option<usize>,
+) -> Oracle {
+    let mut interner = Interner::new();
+    let sentences: Vec<Vec<u32>> = summary::split_sentences(source)
+        .into_iter()
+        .map(|sentence| interner.tokenize(settings.tokenizer, sentence))
+        .collect();
+    let references: Vec<Vec<u32>> = references
+        .iter()
+        .map(|reference| interner.tokenize_sentences(settings.tokenizer, reference))
+        .collect();
+    let mut scorer = Scorer::new(metric, references, settings);
+
+    let mut oracle = Oracle {
+        sentences: Vec::new(),
+        f_measure: 0.0,
+    };
+    while max_sentences.is_none_or(|max| oracle.sentences.len() < max) {
+        let mut best: Option<(usize, f64)> = None;
+        for candidate in (0..sentences.len()).filter(|i| !oracle.sentences.contains(i)) {
+            let mut picked = oracle.sentences.clone();
+            picked.push(candidate);
+            picked.sort_unstable();
+            let f_measure = scorer.score(&picked.iter().map(|&i| sentences[i].as_slice()).collect::<Vec<_>>());
+            if best.is_none_or(|(_, best)| f_measure > best) {
+                best = Some((candidate, f_measure));
+            }
+        }
+        match best {
+            Some((candidate, f_measure)) if f_measure > oracle.f_measure => {
+                oracle.sentences.push(candidate);
+                oracle.sentences.sort_unstable();
+                oracle.f_measure = f_measure;
+            }
+            _ => break,
+        }
+    }
+    oracle
+}
+
+/// Scores picked sentences against the tokenized references
+struct Scorer<'a> {
+    metric: OracleMetric,
+    settings: &'a Settings,
+    /// Tokens of every reference, for ROUGE-L; only ROUGE-Lsum keeps their
+    /// sentence breaks
+    references: Vec<Vec<u32>>,
+    /// Bigram counts of every reference, for ROUGE-2
+    reference_bigrams: Vec<HashMap<(u32, u32), usize>>,
+    scratch: Scratch,
+}
+
+impl<'a> Scorer<'a> {
+    fn new(metric: OracleMetric, references: Vec<Vec<u32>>, settings: &'a Settings) -> Self {
+        let (references, reference_bigrams) = match metric {
+            OracleMetric::RougeL if settings.variant.splits_sentences() => (references, Vec::new()),
+            OracleMetric::RougeL => {
+                let words = references.iter().map(|tokens| summary::words(tokens).into_owned()).collect();
+                (words, Vec::new())
+            }
+            OracleMetric::Rouge2 => {
+                let bigrams = references.iter().map(|tokens| bigrams(summary::sentences(tokens))).collect();
+                (Vec::new(), bigrams)
+            }
+        };
+        Scorer {
+            metric,
+            settings,
+            references,
+            reference_bigrams,
+            scratch: Scratch::default(),
+        }
+    }
+
+    fn score(&mut self, sentences: &[&[u32]]) -> f64 {
+        match self.metric {
+            OracleMetric::RougeL => {
+                let variant = self.settings.variant;
+                let mut tokens = Vec::new();
+                for (i, sentence) in sentences.iter().enumerate() {
+                    if variant.splits_sentences() && i > 0 {
+                        tokens.push(SENTENCE_BREAK);
+                    }
+                    tokens.extend_from_slice(sentence);
+                }
+                variant
+                    .best_of_in(&mut self.scratch, &tokens, &self.references, self.settings.lcs_strategy)
+                    .f_measure
+            }
+            OracleMetric::Rouge2 => {
+                let candidate = bigrams(sentences.iter().copied());
+                let candidate_total: usize = candidate.values().sum();
+                self.reference_bigrams
+                    .iter()
+                    .map(|reference| {
+                        let overlap: usize = candidate
+                            .iter()
+                            .map(|(bigram, &count)| count.min(reference.get(bigram).copied().unwrap_or(0)))
+                            .sum();
+                        let reference_total: usize = reference.values().sum();
+                        match overlap {
+                            0 => 0.0,
+                            _ => 2.0 * overlap as f64 / (candidate_total + reference_total) as f64,
+                        }
+                    })
+                    .fold(0.0, f64::max)
+            }
+        }
+    }
+}
+
+/// Counts of the bigrams within each sentence
+fn bigrams<'s>(sentences: impl Iterator<Item = &'s [u32]>) -> HashMap<(u32, u32), usize> {
+    let mut counts = HashMap::new();
+    for sentence in sentences {
+        for window in sentence.windows(2) {
+            *counts.entry((window[0], window[1])).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Write the oracle of a pair as a JSON line with the picked sentence
+/// indices, their text one per line and the score they reach
+pub fn write_record(out: &mut dyn Write, pair: &Pair, oracle: &Oracle, score_format: &ScoreFormat) -> io::Result<()> {
+    let sentences = summary::split_sentences(&pair.candidate);
+    let text: Vec<&str> = oracle.sentences.iter().map(|&i| sentences[i].trim()).collect();
+    let line = json!({
+        "id": pair.id,
+        "sentences": oracle.sentences,
+        "oracle": text.join("\n"),
+        "f_measure": output::json_score(oracle.f_measure, score_format),
+    });
+    serde_json::to_writer(&mut *out, &line)?;
+    writeln!(out)
+}
@@ -0,0 +1,792 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use rouge_l_rust::summary::Variant;
+use rouge_l_rust::RougeLResult;
+
+use crate::alignment;
+use crate::batch::{Bootstrap, ScoredPair, Summary};
+use crate::config::Settings;
+use crate::display;
+use crate::filter::ScoreField;
+use crate::format::ScoreFormat;
+use crate::input::Pair;
+#[cfg(feature = "arrow")]
+use crate::ipc::IpcWriter;
+use crate::stats::{self, Buckets, Distribution};
+
+/// Width of the F-measure buckets of histograms unless --bucket-width is given
+pub const BUCKET_WIDTH: f64 = 0.1;
+
+/// Width in characters of the longest histogram bar
+const HISTOGRAM_WIDTH: usize = 50;
+
+/// Decimals ROUGE-1.5.5 prints every score with
+const ROUGE155_PRECISION: usize = 5;
+
+/// Inline stylesheet of the per-pair detail pages, which highlights the
+/// tokens on the LCS
+const DETAIL_STYLE: &str = "body { font-family: sans-serif; margin: 2em; } \
+    table { border-collapse: collapse; margin-bottom: 1.5em; } \
+    th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; } \
+    .texts { display: flex; gap: 2em; } \
+    .texts > div { flex: 1; } \
+    .texts p { line-height: 1.8; } \
+    mark { background: #c6efce; }";
+
+/// Inline stylesheet of the HTML report
+const HTML_STYLE: &str = "body { font-family: sans-serif; margin: 2em; } \
+    table { border-collapse: collapse; margin-bottom: 1.5em; } \
+    th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; } \
+    .histogram td:last-child { width: 20em; } \
+    .bar { background: #4a7ebb; height: 1em; }";
+
+/// Format used to print batch results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// One human-readable line per pair followed by the averages
+    #[default]
+    Text,
+    /// A single JSON document with per-pair results and the summary
+    Json,
+    /// One JSON object per line, per pair
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+    /// The averages keyed like the HuggingFace `evaluate` ROUGE module,
+    /// without per-pair results
+    HfEvaluate,
+    /// The report of the Perl ROUGE-1.5.5 toolkit: averages with bootstrap
+    /// confidence intervals, then one `Eval` line per pair as with its `-d`
+    Rouge155,
+    /// A standalone HTML page with the averages, score statistics, the
+    /// F-measure histogram and a table of per-pair results
+    Html,
+    /// A binary Arrow IPC stream of per-pair results, without the summary
+    #[cfg(feature = "arrow")]
+    ArrowIpc,
+}
+
+/// A `FORMAT:PATH` output given with `--output`, e.g. `ndjson:scores.jsonl`
+#[derive(Debug, Clone)]
+pub struct OutputTarget {
+    pub format: OutputFormat,
+    pub path: PathBuf,
+}
+
+/// Error for an `--output` that is not `FORMAT:PATH`
+#[derive(Debug)]
+pub struct OutputTargetParseError(String);
+
+impl fmt::Display for OutputTargetParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for OutputTargetParseError {}
+
+impl FromStr for OutputTarget {
+    type Err = OutputTargetParseError;
+
+    fn from_str(target: &str) -> Result<OutputTarget, OutputTargetParseError> {
+        let Some((format, path)) = target.split_once(':').filter(|(_, path)| !path.is_empty()) else {
+            return Err(OutputTargetParseError(format!("expected FORMAT:PATH, got `{}`", target)));
+        };
+        let format = OutputFormat::from_str(format, true).map_err(|_| {
+            let names: Vec<String> = OutputFormat::value_variants()
+                .iter()
+                .filter_map(|format| Some(format.to_possible_value()?.get_name().to_string()))
+                .collect();
+            OutputTargetParseError(format!("unknown format `{}` (expected one of {})", format, names.join(", ")))
+        })?;
+        Ok(OutputTarget {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// Write batch results and their summary to every output, each in its own format
+///
+/// With `token_limit` set, CSV output gains a `truncated` column; the other
+/// formats mark truncated pairs either way. `bootstrap` only applies to the
+/// ROUGE-1.5.5 report.
+#[tracing::instrument(level = "debug", skip_all, fields(pairs = scores.len()))]
+pub fn write_results<'a>(
+    outputs: Vec<(&'a mut dyn Write, OutputFormat)>,
+    scores: &[ScoredPair],
+    score_format: &'a ScoreFormat,
+    signature: &'a str,
+    token_limit: bool,
+    bootstrap: Bootstrap,
+) -> io::Result<()> {
+    let id_width = scores.iter().map(|s| display::width(&s.id)).max().unwrap_or(0);
+    let mut writers = ResultWriters::new(outputs, score_format, signature, id_width, token_limit, bootstrap)?;
+    for scored in scores {
+        writers.write(scored)?;
+    }
+    writers.finish(&Summary::from_scores(scores))
+}
+
+/// Several [`ResultWriter`]s fed the same results, so one run can print a
+/// human-readable table while saving machine-readable files
+pub struct ResultWriters<'a> {
+    writers: Vec<ResultWriter<'a>>,
+}
+
+impl<'a> ResultWriters<'a> {
+    pub fn new(
+        outputs: Vec<(&'a mut dyn Write, OutputFormat)>,
+        score_format: &'a ScoreFormat,
+        signature: &'a str,
+        id_width: usize,
+        token_limit: bool,
+        bootstrap: Bootstrap,
+    ) -> io::Result<ResultWriters<'a>> {
+        let writers = outputs
+            .into_iter()
+            .map(|(out, format)| ResultWriter::new(out, format, score_format, signature, id_width, token_limit, bootstrap))
+            .collect::<io::Result<_>>()?;
+        Ok(ResultWriters { writers })
+    }
+
+    pub fn write(&mut self, scored: &ScoredPair) -> io::Result<()> {
+        self.writers.iter_mut().try_for_each(|writer| writer.write(scored))
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writers.iter_mut().try_for_each(ResultWriter::flush)
+    }
+
+    pub fn finish(self, summary: &Summary) -> io::Result<()> {
+        self.writers.into_iter().try_for_each(|writer| writer.finish(summary))
+    }
+
+    /// Link every pair of the HTML report written by writer `writer` to its
+    /// page in `dir`, a URL relative to the report, as written by
+    /// [`write_detail_pages`]
+    pub fn link_details(&mut self, writer: usize, dir: &'a str) {
+        self.writers[writer].details = Some(dir);
+    }
+
+    /// Count the F-measures of the JSON output and the HTML report in
+    /// buckets `width` wide instead of [`BUCKET_WIDTH`]
+    pub fn bucket_width(&mut self, width: f64) {
+        for writer in &mut self.writers {
+            writer.buckets = Buckets::new(width);
+        }
+    }
+}
+
+/// Destination of a [`ResultWriter`]
+enum Sink<'a> {
+    Plain(&'a mut dyn Write),
+    Csv(Box<csv::Writer<&'a mut dyn Write>>),
+    #[cfg(feature = "arrow")]
+    Ipc(Box<IpcWriter<'a>>),
+}
+
+/// Writes results one at a time, so they can be streamed as they are scored
+///
+/// Every format produces the same bytes as [`write_results`], except that
+/// text output pads ids to `id_width`, which a stream cannot know up front.
+/// The ROUGE-1.5.5 and HTML reports hold every result back until the
+/// averages are printed.
+pub struct ResultWriter<'a> {
+    sink: Sink<'a>,
+    format: OutputFormat,
+    score_format: &'a ScoreFormat,
+    signature: &'a str,
+    id_width: usize,
+    token_limit: bool,
+    bootstrap: Bootstrap,
+    held: Vec<ScoredPair>,
+    written: usize,
+    /// Directory of per-pair detail pages the HTML report links to
+    details: Option<&'a str>,
+    /// F-measures of the results written so far
+    buckets: Buckets,
+}
+
+impl<'a> ResultWriter<'a> {
+    pub fn new(
+        out: &'a mut dyn Write,
+        format: OutputFormat,
+        score_format: &'a ScoreFormat,
+        signature: &'a str,
+        id_width: usize,
+        token_limit: bool,
+        bootstrap: Bootstrap,
+    ) -> io::Result<ResultWriter<'a>> {
+        let sink = match format {
+            OutputFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(out);
+                let mut header = vec!["id", "f_measure", "precision", "recall", "signature"];
+                if token_limit {
+                    header.push("truncated");
+                }
+                writer.write_record(header)?;
+                Sink::Csv(Box::new(writer))
+            }
+            #[cfg(feature = "arrow")]
+            OutputFormat::ArrowIpc => Sink::Ipc(Box::new(IpcWriter::new(out, signature)?)),
+            OutputFormat::Json => {
+                write!(out, "{{\n  \"results\": [")?;
+                Sink::Plain(out)
+            }
+            OutputFormat::Text
+            | OutputFormat::Ndjson
+            | OutputFormat::HfEvaluate
+            | OutputFormat::Rouge155
+            | OutputFormat::Html => Sink::Plain(out),
+        };
+        Ok(ResultWriter {
+            sink,
+            format,
+            score_format,
+            signature,
+            id_width,
+            token_limit,
+            bootstrap,
+            held: Vec::new(),
+            written: 0,
+            details: None,
+            buckets: Buckets::new(BUCKET_WIDTH),
+        })
+    }
+
+    pub fn write(&mut self, scored: &ScoredPair) -> io::Result<()> {
+        let score_format = self.score_format;
+        self.buckets.add(scored.result.f_measure);
+        match &mut self.sink {
+            Sink::Csv(writer) => {
+                let mut record = vec![
+                    scored.id.clone(),
+                    score_format.format(scored.result.f_measure),
+                    score_format.format(scored.result.precision),
+                    score_format.format(scored.result.recall),
+                    self.signature.to_string(),
+                ];
+                if self.token_limit {
+                    record.push(scored.truncated.to_string());
+                }
+                writer.write_record(record)?
+            }
+            #[cfg(feature = "arrow")]
+            Sink::Ipc(writer) => writer.write(scored, score_format)?,
+            Sink::Plain(out) => match self.format {
+                OutputFormat::Text => writeln!(
+                    out,
+                    "{}  F-Measure: {}, Precision: {}, Recall: {}{}{}",
+                    display::pad_right(&scored.id, self.id_width),
+                    score_format.format(scored.result.f_measure),
+                    score_format.format(scored.result.precision),
+                    score_format.format(scored.result.recall),
+                    if scored.truncated { "  [truncated]" } else { "" },
+                    scored.n_best.as_ref().map_or(String::new(), |n_best| format!(
+                        "  [{}-best: best F-Measure {} at rank {}, mean F-Measure {}]",
+                        n_best.hypotheses,
+                        score_format.format(n_best.best.f_measure),
+                        n_best.best_rank,
+                        score_format.format(n_best.mean.f_measure),
+                    )),
+                )?,
+                OutputFormat::Ndjson => {
+                    let mut record = pair_json(scored, score_format);
+                    record["signature"] = Value::from(self.signature);
+                    serde_json::to_writer(&mut **out, &record)?;
+                    writeln!(out)?;
+                }
+                OutputFormat::Json => {
+                    let separator = if self.written == 0 { "\n" } else { ",\n" };
+                    let record = serde_json::to_string_pretty(&pair_json(scored, score_format))?;
+                    write!(out, "{}{}", separator, indent(&record, 4))?;
+                }
+                OutputFormat::HfEvaluate => {}
+                OutputFormat::Rouge155 | OutputFormat::Html => self.held.push(scored.clone()),
+                OutputFormat::Csv => unreachable!("CSV output always uses the CSV sink"),
+                #[cfg(feature = "arrow")]
+                OutputFormat::ArrowIpc => unreachable!("Arrow IPC output always uses the IPC sink"),
+            },
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Push the results written so far to the output; Arrow record batches
+    /// only go out once full
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Plain(out) => out.flush(),
+            Sink::Csv(writer) => writer.flush(),
+            #[cfg(feature = "arrow")]
+            Sink::Ipc(_) => Ok(()),
+        }
+    }
+
+    /// Write the summary and signature that close the output
+    pub fn finish(self, summary: &Summary) -> io::Result<()> {
+        let score_format = self.score_format;
+        match self.sink {
+            Sink::Csv(mut writer) => writer.flush(),
+            #[cfg(feature = "arrow")]
+            Sink::Ipc(writer) => writer.finish(),
+            Sink::Plain(out) => match self.format {
+                OutputFormat::Text => {
+                    writeln!(
+                        out,
+                        "\nAverage over {} pairs: F-Measure: {}, Precision: {}, Recall: {}",
+                        summary.count,
+                        score_format.format(summary.mean.f_measure),
+                        score_format.format(summary.mean.precision),
+                        score_format.format(summary.mean.recall),
+                    )?;
+                    if let Some(oracle) = &summary.oracle {
+                        writeln!(
+                            out,
+                            "Oracle over {} pairs (best of each n-best list): F-Measure: {}, Precision: {}, Recall: {}, \
+                             headroom: +{}",
+                            summary.count,
+                            score_format.format(oracle.f_measure),
+                            score_format.format(oracle.precision),
+                            score_format.format(oracle.recall),
+                            score_format.format(oracle.f_measure - summary.mean.f_measure),
+                        )?;
+                    }
+                    writeln!(out, "Signature: {}", self.signature)
+                }
+                OutputFormat::Json => {
+                    let mut summary_json = json!({
+                        "count": summary.count,
+                        "f_measure": json_score(summary.mean.f_measure, score_format),
+                        "precision": json_score(summary.mean.precision, score_format),
+                        "recall": json_score(summary.mean.recall, score_format),
+                        "histogram": buckets_json(&self.buckets),
+                    });
+                    if let Some(oracle) = &summary.oracle {
+                        summary_json["oracle"] = json!({
+                            "f_measure": json_score(oracle.f_measure, score_format),
+                            "precision": json_score(oracle.precision, score_format),
+                            "recall": json_score(oracle.recall, score_format),
+                        });
+                    }
+                    if self.written > 0 {
+                        write!(out, "\n  ")?;
+                    }
+                    writeln!(
+                        out,
+                        "],\n  \"summary\": {},\n  \"signature\": {}\n}}",
+                        indent(&serde_json::to_string_pretty(&summary_json)?, 2).trim_start(),
+                        Value::from(self.signature),
+                    )
+                }
+                OutputFormat::Ndjson => Ok(()),
+                // Keyed `rougeL` or `rougeLsum` after the variant scored, with
+                // the precision and recall of the aggregated `Score` beside
+                // the F-measure, which is all `evaluate` keeps of it
+                OutputFormat::HfEvaluate => {
+                    let mut scores = serde_json::Map::new();
+                    scores.insert(
+                        hf_evaluate_key(self.signature).to_string(),
+                        json!({
+                            "precision": json_score(summary.mean.precision, score_format),
+                            "recall": json_score(summary.mean.recall, score_format),
+                            "fmeasure": json_score(summary.mean.f_measure, score_format),
+                        }),
+                    );
+                    writeln!(out, "{}", serde_json::to_string_pretty(&Value::Object(scores))?)
+                }
+                OutputFormat::Rouge155 => write_rouge155(out, &self.held, summary, self.bootstrap, score_format),
+                OutputFormat::Html => {
+                    let (held, buckets) = (&self.held, &self.buckets);
+                    write_html(out, held, summary, self.signature, score_format, self.details, buckets)
+                }
+                OutputFormat::Csv => unreachable!("CSV output always uses the CSV sink"),
+                #[cfg(feature = "arrow")]
+                OutputFormat::ArrowIpc => unreachable!("Arrow IPC output always uses the IPC sink"),
+            },
+        }
+    }
+}
+
+/// Write the ROUGE-1.5.5 report for system `1`, which log parsers such as
+/// pyrouge's read with a regular expression; scores always have five decimals
+fn write_rouge155(
+    out: &mut dyn Write,
+    scores: &[ScoredPair],
+    summary: &Summary,
+    bootstrap: Bootstrap,
+    score_format: &ScoreFormat,
+) -> io::Result<()> {
+    let score_format = ScoreFormat::new(ROUGE155_PRECISION, score_format.rounding);
+    let score = |value| score_format.format(value);
+    let averages = [
+        ("R", summary.mean.recall, ScoreField::Recall),
+        ("P", summary.mean.precision, ScoreField::Precision),
+        ("F", summary.mean.f_measure, ScoreField::F),
+    ];
+
+    writeln!(out, "{}", "-".repeat(45))?;
+    for (name, mean, field) in averages {
+        let values: Vec<f64> = scores.iter().map(|scored| field.value(scored)).collect();
+        let (low, high) = bootstrap.interval(&values);
+        writeln!(
+            out,
+            "1 ROUGE-L Average_{}: {} ({}%-conf.int. {} - {})",
+            name,
+            score(mean),
+            bootstrap.confidence,
+            score(low),
+            score(high)
+        )?;
+    }
+    writeln!(out, "{}", ".".repeat(45))?;
+    for scored in scores {
+        writeln!(
+            out,
+            "1 ROUGE-L Eval {} R:{} P:{} F:{}",
+            scored.id,
+            score(scored.result.recall),
+            score(scored.result.precision),
+            score(scored.result.f_measure)
+        )?;
+    }
+    writeln!(out, "{}", "-".repeat(45))
+}
+
+/// Write a standalone HTML page for reading results in a browser or
+/// attaching them to a review; it loads nothing from the network
+fn write_html(
+    out: &mut dyn Write,
+    scores: &[ScoredPair],
+    summary: &Summary,
+    signature: &str,
+    score_format: &ScoreFormat,
+    details: Option<&str>,
+    buckets: &Buckets,
+) -> io::Result<()> {
+    let score = |value| score_format.format(value);
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>ROUGE-L report</title>")?;
+    writeln!(out, "<style>{}</style>\n</head>\n<body>", HTML_STYLE)?;
+    writeln!(out, "<h1>ROUGE-L report</h1>\n<p>Signature: <code>{}</code></p>", escape_html(signature))?;
+
+    writeln!(out, "<h2>Average over {} pairs</h2>", summary.count)?;
+    writeln!(out, "<table>\n<tr><th>F-Measure</th><th>Precision</th><th>Recall</th></tr>")?;
+    writeln!(
+        out,
+        "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n</table>",
+        score(summary.mean.f_measure),
+        score(summary.mean.precision),
+        score(summary.mean.recall)
+    )?;
+    if let Some(oracle) = &summary.oracle {
+        writeln!(
+            out,
+            "<p>Oracle (best of each n-best list): F-Measure {}, Precision {}, Recall {}</p>",
+            score(oracle.f_measure),
+            score(oracle.precision),
+            score(oracle.recall)
+        )?;
+    }
+
+    write!(out, "<h2>Statistics</h2>\n<table>\n<tr><th></th>")?;
+    for column in stats::COLUMNS {
+        write!(out, "<th>{}</th>", column)?;
+    }
+    writeln!(out, "</tr>")?;
+    for (name, field) in stats::SCORE_FIELDS {
+        write!(out, "<tr><th>{}</th>", name)?;
+        for value in Distribution::of(scores, field).summary() {
+            write!(out, "<td>{}</td>", score(value))?;
+        }
+        writeln!(out, "</tr>")?;
+    }
+    writeln!(out, "</table>")?;
+
+    let max = buckets.counts.iter().copied().max().unwrap_or(0).max(1);
+    writeln!(out, "<h2>F-Measure distribution</h2>\n<table class=\"histogram\">")?;
+    for (i, &count) in buckets.counts.iter().enumerate() {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td><div class=\"bar\" style=\"width: {:.1}%\"></div></td></tr>",
+            buckets.label(i),
+            count,
+            count as f64 * 100.0 / max as f64
+        )?;
+    }
+    writeln!(out, "</table>")?;
+
+    writeln!(out, "<h2>Pairs</h2>\n<table>")?;
+    writeln!(out, "<tr><th>Id</th><th>F-Measure</th><th>Precision</th><th>Recall</th><th>Notes</th></tr>")?;
+    for scored in scores {
+        let mut notes = Vec::new();
+        if scored.truncated {
+            notes.push("truncated".to_string());
+        }
+        if let Some(n_best) = &scored.n_best {
+            notes.push(format!(
+                "{}-best: best F-Measure {} at rank {}, mean F-Measure {}",
+                n_best.hypotheses,
+                score(n_best.best.f_measure),
+                n_best.best_rank,
+                score(n_best.mean.f_measure)
+            ));
+        }
+        let id = match details {
+            Some(dir) => format!("<a href=\"{}/{}\">{}</a>", dir, detail_page(scored), escape_html(&scored.id)),
+            None => escape_html(&scored.id),
+        };
+        writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            id,
+            score(scored.result.f_measure),
+            score(scored.result.precision),
+            score(scored.result.recall),
+            notes.join("; ")
+        )?;
+    }
+    writeln!(out, "</table>\n</body>\n</html>")
+}
+
+/// File name of the detail page of a pair, numbered by its input position
+/// since ids may hold any character
+fn detail_page(scored: &ScoredPair) -> String {
+    format!("{}.html", scored.index + 1)
+}
+
+/// Directory of the detail pages of the HTML report at `report`, e.g.
+/// `report_pairs` beside `report.html`
+pub fn details_dir(report: &Path) -> PathBuf {
+    let stem = report.file_stem().unwrap_or_default().to_string_lossy();
+    report.with_file_name(format!("{}_pairs", stem))
+}
+
+/// The last component of `path` as a relative URL
+pub fn relative_url(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut url = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => url.push(byte as char),
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+/// Write one HTML page per pair into the [`details_dir`] of `report`,
+/// showing the candidate beside the reference it matches best with the
+/// tokens of their LCS highlighted, and every score of the pair
+///
+/// Pages link back to the report and to the pages before and after them in
+/// the order of `scores`.
+pub fn write_detail_pages(report: &Path, pairs: &[Pair], scores: &[ScoredPair], settings: &Settings) -> io::Result<()> {
+    let dir = details_dir(report);
+    let report = format!("../{}", relative_url(report));
+    fs::create_dir_all(&dir)?;
+    let score = |value| settings.score_format.format(value);
+    for (position, scored) in scores.iter().enumerate() {
+        let pair = &pairs[scored.index];
+        let matched = alignment::best_match(pair, settings);
+        let mut out = BufWriter::new(File::create(dir.join(detail_page(scored)))?);
+        let id = escape_html(&scored.id);
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{} - ROUGE-L</title>", id)?;
+        writeln!(out, "<style>{}</style>\n</head>\n<body>", DETAIL_STYLE)?;
+
+        let mut links = vec![format!("<a href=\"{}\">Report</a>", report)];
+        if let Some(previous) = position.checked_sub(1).map(|i| &scores[i]) {
+            links.push(format!("<a href=\"{}\">Previous: {}</a>", detail_page(previous), escape_html(&previous.id)));
+        }
+        if let Some(next) = scores.get(position + 1) {
+            links.push(format!("<a href=\"{}\">Next: {}</a>", detail_page(next), escape_html(&next.id)));
+        }
+        writeln!(out, "<p>{}</p>\n<h1>{}</h1>", links.join(" | "), id)?;
+
+        writeln!(out, "<table>")?;
+        let mut rows = vec![
+            ("F-Measure", score(scored.result.f_measure)),
+            ("Precision", score(scored.result.precision)),
+            ("Recall", score(scored.result.recall)),
+            ("LCS tokens", matched.alignment.len().to_string()),
+            ("Candidate tokens", scored.candidate_tokens.to_string()),
+            ("Reference tokens", matched.reference_tokens.len().to_string()),
+            ("Reference", format!("{} of {}", matched.reference + 1, pair.references.len())),
+        ];
+        if let Some(limit) = settings.token_limit.filter(|_| scored.truncated) {
+            rows.push(("Truncated", format!("to {} tokens per text", limit.max_tokens)));
+        }
+        if let Some(n_best) = &scored.n_best {
+            rows.push(("Hypotheses", n_best.hypotheses.to_string()));
+            rows.push(("Best F-Measure", format!("{} at rank {}", score(n_best.best.f_measure), n_best.best_rank)));
+            rows.push(("Mean F-Measure", score(n_best.mean.f_measure)));
+        }
+        for (name, value) in rows {
+            writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", name, value)?;
+        }
+        writeln!(out, "</table>")?;
+
+        let on_lcs = |len: usize, side: fn(&(usize, usize)) -> usize| {
+            let mut marked = vec![false; len];
+            for position in matched.alignment.iter().map(side) {
+                marked[position] = true;
+            }
+            marked
+        };
+        let candidate = highlight(&matched.candidate_tokens, &on_lcs(matched.candidate_tokens.len(), |&(i, _)| i));
+        let reference = highlight(&matched.reference_tokens, &on_lcs(matched.reference_tokens.len(), |&(_, j)| j));
+        writeln!(out, "<div class=\"texts\">")?;
+        writeln!(out, "<div><h2>Candidate</h2>\n<p>{}</p></div>", candidate)?;
+        writeln!(out, "<div><h2>Reference</h2>\n<p>{}</p></div>", reference)?;
+        writeln!(out, "</div>\n</body>\n</html>")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Join tokens with spaces, marking those on the LCS
+fn highlight(tokens: &[String], marked: &[bool]) -> String {
+    tokens
+        .iter()
+        .zip(marked)
+        .map(|(token, &marked)| match marked {
+            true => format!("<mark>{}</mark>", escape_html(token)),
+            false => escape_html(token),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escape the characters HTML gives a meaning to in text and attributes
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Indent every line of `text` by `width` spaces
+fn indent(text: &str, width: usize) -> String {
+    let pad = " ".repeat(width);
+    text.lines()
+        .map(|line| format!("{}{}", pad, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Draw the F-measure distribution in buckets `width` wide as a horizontal
+/// ASCII bar chart
+pub fn write_histogram(out: &mut dyn Write, scores: &[ScoredPair], width: f64) -> io::Result<()> {
+    let buckets = Buckets::of(scores, ScoreField::F, width);
+    let max = buckets.counts.iter().copied().max().unwrap_or(0).max(1);
+    let count_width = max.to_string().len();
+
+    writeln!(out, "\nF-Measure distribution ({} pairs)", scores.len())?;
+    for (i, &count) in buckets.counts.iter().enumerate() {
+        let bar = (count as usize * HISTOGRAM_WIDTH).div_ceil(max as usize);
+        let line = format!("{} | {:>count_width$} {}", buckets.label(i), count, "#".repeat(bar));
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Print `rows` as a table whose columns are two spaces apart, the first
+/// `left` aligned left and the others, usually numbers, right
+pub fn write_columns<R: AsRef<[String]>>(out: &mut dyn Write, rows: &[R], left: usize) -> io::Result<()> {
+    let columns = rows.iter().map(|row| row.as_ref().len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            let width = |row: &R| row.as_ref().get(column).map_or(0, |cell| display::width(cell));
+            rows.iter().map(width).max().unwrap_or(0)
+        })
+        .collect();
+    for row in rows {
+        let mut line = String::new();
+        for (column, (cell, width)) in row.as_ref().iter().zip(&widths).enumerate() {
+            if column > 0 {
+                line.push_str("  ");
+            }
+            match column < left {
+                true => line.push_str(&display::pad_right(cell, *width)),
+                false => line.push_str(&display::pad_left(cell, *width)),
+            }
+        }
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Bucket counts as `{"low", "high", "count"}` objects in ascending order
+pub fn buckets_json(buckets: &Buckets) -> Value {
+    let buckets = buckets.counts.iter().enumerate().map(|(i, &count)| {
+        let (low, high) = buckets.bounds(i);
+        json!({ "low": low, "high": high, "count": count })
+    });
+    Value::Array(buckets.collect())
+}
+
+fn pair_json(scored: &ScoredPair, score_format: &ScoreFormat) -> Value {
+    let mut record = json!({
+        "id": scored.id,
+        "f_measure": json_score(scored.result.f_measure, score_format),
+        "precision": json_score(scored.result.precision, score_format),
+        "recall": json_score(scored.result.recall, score_format),
+    });
+    if scored.truncated {
+        record["truncated"] = Value::from(true);
+    }
+    if let Some(n_best) = &scored.n_best {
+        let scores = |result: &RougeLResult| {
+            json!({
+                "f_measure": json_score(result.f_measure, score_format),
+                "precision": json_score(result.precision, score_format),
+                "recall": json_score(result.recall, score_format),
+            })
+        };
+        record["n_best"] = json!({
+            "hypotheses": n_best.hypotheses,
+            "best": scores(&n_best.best),
+            "best_rank": n_best.best_rank,
+            "mean": scores(&n_best.mean),
+        });
+    }
+    record
+}
+
+/// Key of the `evaluate` ROUGE module for the variant a signature starts with
+fn hf_evaluate_key(signature: &str) -> &'static str {
+    match signature.split('|').next() == Some(Variant::RougeLsum.name()) {
+        true => "rougeLsum",
+        false => "rougeL",
+    }
+}
+
+/// Round a score for JSON output so machine-readable formats honour
+/// `--precision` and `--rounding` just like the text output
+pub fn json_score(value: f64, score_format: &ScoreFormat) -> Value {
+    let rounded = score_format.round(value);
+    if rounded.is_finite() {
+        Value::from(rounded)
+    } else {
+        Value::Null
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::intern::Vocab;
+use crate::lcs::{LcsStrategy, Scratch};
+use crate::summary::Variant;
+use crate::tokenize::Tokenizer;
+use crate::RougeLResult;
+
+/// Tokenized references keyed by their text
+///
+/// When many candidates share a reference, each distinct reference is
+/// tokenized and interned once; keep the set around to reuse it across
+/// calls. Candidates are interned into the same vocabulary so their IDs are
+/// comparable with the cached references.
+#[derive(Debug)]
+pub struct ReferenceSet {
+    vocab: Vocab,
+    references: HashMap<Arc<str>, Arc<[u32]>>,
+}
+
+impl ReferenceSet {
+    pub fn new(tokenizer: Tokenizer) -> Self {
+        ReferenceSet::with_variant(tokenizer, Variant::default())
+    }
+
+    /// Reference set tokenizing texts as `variant` scores them
+    pub fn with_variant(tokenizer: Tokenizer, variant: Variant) -> Self {
+        ReferenceSet {
+            vocab: Vocab::with_variant(tokenizer, variant),
+            references: HashMap::new(),
+        }
+    }
+
+    /// Token IDs of a reference text, tokenized only the first time it is seen
+    pub fn reference(&mut self, text: &str) -> Arc<[u32]> {
+        if let Some(tokens) = self.references.get(text) {
+            return Arc::clone(tokens);
+        }
+        let tokens: Arc<[u32]> = self.vocab.tokenize(text).into();
+        self.references.insert(text.into(), Arc::clone(&tokens));
+        tokens
+    }
+
+    /// Token IDs of a candidate text, in the same ID space as the references
+    pub fn candidate(&mut self, text: &str) -> Vec<u32> {
+        self.vocab.tokenize(text)
+    }
+
+    /// Score a candidate against a reference, reusing the cached reference
+    pub fn score(&mut self, candidate: &str, reference: &str, strategy: LcsStrategy) -> RougeLResult {
+        let candidate = self.candidate(candidate);
+        let reference = self.reference(reference);
+        self.vocab
+            .variant()
+            .best_of_in(&mut Scratch::default(), &candidate, &[reference], strategy)
+    }
+
+    /// Number of distinct references cached
+    pub fn len(&self) -> usize {
+        self.references.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use roxmltree::Document;
+
+use rouge_l_rust::summary::Variant;
+use rouge_l_rust::tokenize::Tokenizer;
+use rouge_l_rust::truncate::{TokenLimit, TruncationPolicy};
+
+use crate::batch::Bootstrap;
+use crate::input::{InputError, Pair};
+
+/// Settings equivalent to a ROUGE-1.5.5 command line such as
+/// `-n 2 -m -s -2 4 -u -c 95 -r 1000 -f A -p 0.5 -t 0`
+///
+/// ROUGE-1.5.5 always computes the summary-level ROUGE-L over sentences
+/// with lowercased alphanumeric tokens, so the variant is ROUGE-Lsum and the
+/// tokenizer `rouge-score`. `-c` and `-r` set the confidence intervals of
+/// the ROUGE-1.5.5 report, while options for other metrics or for reporting
+/// (`-n`, `-2`, `-u`, `-w`, `-d`, ...) do not change ROUGE-L and are
+/// accepted as-is. Multi-reference pairs keep their best score as with
+/// `-f B`, so `-f A`, which averages over the references and is ROUGE-1.5.5's
+/// default when `-f` is not given, is reported as unsupported; the two agree
+/// for single references.
+#[derive(Debug, Clone)]
+pub struct Rouge155Args {
+    pub variant: Variant,
+    pub tokenizer: Tokenizer,
+    /// `-l N`: only the first N words of each text are scored
+    pub token_limit: Option<TokenLimit>,
+    pub bootstrap: Bootstrap,
+    /// Options that would change the scores but have no equivalent here,
+    /// each with a short description
+    pub unsupported: Vec<String>,
+}
+
+/// Error for a `--rouge155-args` string that cannot be mapped
+#[derive(Debug)]
+pub struct Rouge155ParseError(String);
+
+impl fmt::Display for Rouge155ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Rouge155ParseError {}
+
+/// Options of `ROUGE-1.5.5.pl` that take a value
+const VALUE_OPTIONS: &str = "bceflnprtwz23";
+/// Options of `ROUGE-1.5.5.pl` that are plain flags
+const FLAG_OPTIONS: &str = "adEhHmMsuUvx";
+
+impl FromStr for Rouge155Args {
+    type Err = Rouge155ParseError;
+
+    /// Parse the options the way the Perl script's `getopts` does, so flags
+    /// may be bundled (`-ms`) and values attached (`-n2`)
+    fn from_str(args: &str) -> Result<Rouge155Args, Rouge155ParseError> {
+        let mut options = Vec::new();
+        let mut words = args.split_whitespace();
+        while let Some(word) = words.next() {
+            let Some(letters) = word.strip_prefix('-').filter(|letters| !letters.is_empty()) else {
+                return Err(Rouge155ParseError(format!("unexpected argument `{}`; only options are accepted", word)));
+            };
+            for (at, option) in letters.char_indices() {
+                if FLAG_OPTIONS.contains(option) {
+                    options.push((option, None));
+                } else if VALUE_OPTIONS.contains(option) {
+                    let attached = &letters[at + option.len_utf8()..];
+                    let value = match attached {
+                        "" => words
+                            .next()
+                            .ok_or_else(|| Rouge155ParseError(format!("option -{} needs a value", option)))?,
+                        _ => attached,
+                    };
+                    options.push((option, Some(value)));
+                    break;
+                } else {
+                    return Err(Rouge155ParseError(format!("unknown ROUGE-1.5.5 option -{}", option)));
+                }
+            }
+        }
+
+        let mut result = Rouge155Args {
+            variant: Variant::RougeLsum,
+            tokenizer: Tokenizer::RougeScore,
+            token_limit: None,
+            bootstrap: Bootstrap::default(),
+            unsupported: Vec::new(),
+        };
+        let mut scoring_formula = "A";
+        for (option, value) in options {
+            let value = value.unwrap_or_default();
+            let number = |value: &str| {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| Rouge155ParseError(format!("option -{} expects a number, got `{}`", option, value)))
+            };
+            match option {
+                'x' => return Err(Rouge155ParseError("option -x turns off ROUGE-L, the only score computed".into())),
+                'l' => {
+                    let words = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|&words| words > 0)
+                        .ok_or_else(|| Rouge155ParseError(format!("option -l expects a word count, got `{}`", value)))?;
+                    result.token_limit = Some(TokenLimit {
+                        max_tokens: words as usize,
+                        policy: TruncationPolicy::TruncateTail,
+                    });
+                }
+                'p' if number(value)? != 0.5 => {
+                    result.unsupported.push(format!("-p {} (F-measure weight other than 0.5)", value))
+                }
+                'f' if value == "A" || value == "B" => scoring_formula = value,
+                'f' => return Err(Rouge155ParseError(format!("option -f expects A or B, got `{}`", value))),
+                'c' => {
+                    result.bootstrap.confidence = Some(number(value)?)
+                        .filter(|confidence| *confidence > 0.0 && *confidence < 100.0)
+                        .ok_or_else(|| Rouge155ParseError(format!("option -c expects a percentage, got `{}`", value)))?;
+                }
+                'r' => {
+                    result.bootstrap.resamples = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&resamples| resamples > 0)
+                        .ok_or_else(|| Rouge155ParseError(format!("option -r expects a resample count, got `{}`", value)))?;
+                }
+                'n' | '2' | 'w' => {
+                    number(value)?;
+                }
+                'm' => result.unsupported.push("-m (Porter stemming)".into()),
+                's' => result.unsupported.push("-s (stopword removal)".into()),
+                'b' => result.unsupported.push(format!("-b {} (byte length limit)", value)),
+                _ => {}
+            }
+        }
+        if scoring_formula == "A" {
+            result.unsupported.push(
+                "-f A (averaging over the references, the default without -f; multi-reference pairs keep their \
+                 best score as with -f B)"
+                    .into(),
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Read the pairs of a ROUGE-1.5.5 XML evaluation configuration, as written
+/// by pyrouge or for DUC/TAC runs
+///
+/// Every `<EVAL>` pairs each of its peers with all of its models, which
+/// become the references. Pairs take the `ID` of their evaluation, suffixed
+/// with `.PEER` when it has several peers. Peer and model files are resolved
+/// against `PEER-ROOT` and `MODEL-ROOT` and read in the evaluation's
+/// `INPUT-FORMAT`: `SPL` (or `SIMPLE`) files hold one sentence per line, and
+/// `SEE` files one `<a ...>sentence</a>` anchor per line, the default.
+pub fn read_config(path: &Path) -> Result<Vec<Pair>, InputError> {
+    let invalid = |message: String| InputError::Invalid(path.to_path_buf(), message);
+    let text = fs::read_to_string(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+    let document = Document::parse(&text).map_err(|e| invalid(e.to_string()))?;
+
+    let mut pairs = Vec::new();
+    for eval in document.root_element().children().filter(|node| node.has_tag_name("EVAL")) {
+        let id = eval.attribute("ID").ok_or_else(|| invalid("<EVAL> without an ID".into()))?;
+        let child = |name: &str| eval.children().find(|node| node.has_tag_name(name));
+        let root = |name: &str| {
+            child(name)
+                .and_then(|node| node.text())
+                .map(|text| PathBuf::from(text.trim()))
+                .ok_or_else(|| invalid(format!("evaluation {} has no <{}>", id, name)))
+        };
+        let (peer_root, model_root) = (root("PEER-ROOT")?, root("MODEL-ROOT")?);
+        let see = match child("INPUT-FORMAT").and_then(|node| node.attribute("TYPE")) {
+            None | Some("SEE") => true,
+            Some("SPL") | Some("SIMPLE") => false,
+            Some(other) => return Err(invalid(format!("evaluation {} has unsupported INPUT-FORMAT {}", id, other))),
+        };
+        let files = |list: &str, tag: &str, root: &Path| {
+            child(list)
+                .into_iter()
+                .flat_map(|node| node.children())
+                .filter(|node| node.has_tag_name(tag))
+                .map(|node| {
+                    let name = node.text().map(str::trim).unwrap_or_default();
+                    let text = read_document(&root.join(name), see)?;
+                    Ok((node.attribute("ID").unwrap_or(name).to_string(), text))
+                })
+                .collect::<Result<Vec<(String, String)>, InputError>>()
+        };
+
+        let peers = files("PEERS", "P", &peer_root)?;
+        let references: Vec<String> = files("MODELS", "M", &model_root)?
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect();
+        if peers.is_empty() || references.is_empty() {
+            return Err(invalid(format!("evaluation {} needs at least one peer and one model", id)));
+        }
+        let several = peers.len() > 1;
+        for (peer, candidate) in peers {
+            pairs.push(Pair {
+                id: if several { format!("{}.{}", id, peer) } else { id.to_string() },
+                candidate,
+                references: references.clone(),
+                hypotheses: Vec::new(),
+            });
+        }
+    }
+    Ok(pairs)
+}
+
+/// Read a peer or model summary as one sentence per line
+fn read_document(path: &Path, see: bool) -> Result<String, InputError> {
+    let text = fs::read_to_string(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+    if !see {
+        return Ok(text);
+    }
+    Ok(text.lines().filter_map(see_sentence).collect::<Vec<_>>().join("\n"))
+}
+
+/// The sentence of a SEE line such as
+/// `<a name="1">[1]</a> <a href="#1" id=1>The sentence.</a>`
+fn see_sentence(line: &str) -> Option<&str> {
+    let anchor = &line[line.find("<a href=")?..];
+    let sentence = &anchor[anchor.find('>')? + 1..];
+    Some(sentence.split("</a>").next().unwrap_or(sentence))
+}
@@ -0,0 +1,174 @@
+//! Reusable scorers for embedders scoring many pairs
+
+use std::fmt;
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::ThreadPool;
+
+use crate::intern::Interner;
+use crate::lcs::{LcsStrategy, Scratch};
+use crate::tokenize::Tokenizer;
+use crate::{rouge_l_from_tokens_in, RougeLResult};
+
+/// How a [`RougeL`] scorer is set up, including the threads it may use
+#[derive(Debug, Clone, Default)]
+pub struct RougeLConfig {
+    tokenizer: Tokenizer,
+    strategy: LcsStrategy,
+    scratch_capacity: usize,
+    #[cfg(feature = "parallel")]
+    threads: Option<usize>,
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl RougeLConfig {
+    pub fn new(tokenizer: Tokenizer, strategy: LcsStrategy) -> Self {
+        RougeLConfig {
+            tokenizer,
+            strategy,
+            ..RougeLConfig::default()
+        }
+    }
+
+    /// Preallocate buffers for texts of up to `tokens` tokens
+    pub fn scratch_capacity(mut self, tokens: usize) -> Self {
+        self.scratch_capacity = tokens;
+        self
+    }
+
+    /// Give the scorer its own pool of `threads` workers for
+    /// [`RougeL::score_many`] instead of rayon's global pool
+    #[cfg(feature = "parallel")]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Run [`RougeL::score_many`] on a pool the application already manages;
+    /// takes precedence over [`RougeLConfig::threads`]
+    #[cfg(feature = "parallel")]
+    pub fn thread_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Create the scorer, starting its thread pool if one was requested
+    pub fn build(self) -> Result<RougeL, BuildError> {
+        #[cfg(feature = "parallel")]
+        let pool = match (self.pool, self.threads) {
+            (Some(pool), _) => Some(pool),
+            (None, Some(threads)) => Some(Arc::new(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build()
+                    .map_err(BuildError::ThreadPool)?,
+            )),
+            (None, None) => None,
+        };
+        Ok(RougeL {
+            tokenizer: self.tokenizer,
+            strategy: self.strategy,
+            interner: Interner::new(),
+            candidate: Vec::with_capacity(self.scratch_capacity),
+            reference: Vec::with_capacity(self.scratch_capacity),
+            scratch: Scratch::with_capacity(self.scratch_capacity),
+            #[cfg(feature = "parallel")]
+            pool,
+        })
+    }
+}
+
+/// Error raised while building a [`RougeL`] scorer
+#[derive(Debug)]
+pub enum BuildError {
+    #[cfg(feature = "parallel")]
+    ThreadPool(rayon::ThreadPoolBuildError),
+}
+
+impl fmt::Display for BuildError {
+    // Without the `parallel` feature there is nothing that can fail
+    #[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            #[cfg(feature = "parallel")]
+            BuildError::ThreadPool(ref err) => write!(f, "cannot start thread pool: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Scorer that keeps its interner, token buffers and LCS scratch between
+/// pairs, for embedders scoring millions of them
+///
+/// After the first few pairs have grown the buffers, scoring a pair only
+/// allocates for tokens the interner has not seen yet.
+#[derive(Debug)]
+pub struct RougeL {
+    tokenizer: Tokenizer,
+    strategy: LcsStrategy,
+    interner: Interner,
+    candidate: Vec<u32>,
+    reference: Vec<u32>,
+    scratch: Scratch,
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl RougeL {
+    pub fn new(tokenizer: Tokenizer, strategy: LcsStrategy) -> Self {
+        RougeL::with_scratch_capacity(tokenizer, strategy, 0)
+    }
+
+    /// Scorer with buffers preallocated for texts of up to `tokens` tokens
+    pub fn with_scratch_capacity(tokenizer: Tokenizer, strategy: LcsStrategy, tokens: usize) -> Self {
+        RougeLConfig::new(tokenizer, strategy)
+            .scratch_capacity(tokens)
+            .build()
+            .expect("no thread pool to start")
+    }
+
+    /// Calculate ROUGE-L score (F-measure, Precision, Recall)
+    pub fn score(&mut self, candidate: &str, reference: &str) -> RougeLResult {
+        self.interner.tokenize_into(self.tokenizer, candidate, &mut self.candidate);
+        self.interner.tokenize_into(self.tokenizer, reference, &mut self.reference);
+        rouge_l_from_tokens_in(&mut self.scratch, &self.candidate, &self.reference, self.strategy)
+    }
+
+    /// Score `(candidate, reference)` pairs, returning results in input order
+    ///
+    /// Tokenizing is sequential; with the `parallel` feature the pairs are
+    /// then scored on the configured pool, or rayon's global pool if none
+    /// was configured.
+    pub fn score_many(&mut self, pairs: &[(&str, &str)]) -> Vec<RougeLResult> {
+        let tokenized: Vec<(Vec<u32>, Vec<u32>)> = pairs
+            .iter()
+            .map(|(candidate, reference)| {
+                (
+                    self.interner.tokenize(self.tokenizer, candidate),
+                    self.interner.tokenize(self.tokenizer, reference),
+                )
+            })
+            .collect();
+        let strategy = self.strategy;
+        let score = |scratch: &mut Scratch, (candidate, reference): &(Vec<u32>, Vec<u32>)| {
+            rouge_l_from_tokens_in(scratch, candidate, reference, strategy)
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            let score_all = || tokenized.par_iter().map_init(Scratch::default, score).collect();
+            match &self.pool {
+                Some(pool) => pool.install(score_all),
+                None => score_all(),
+            }
+        }
+        #[cfg(not(feature = "parallel"))]
+        tokenized.iter().map(|pair| score(&mut self.scratch, pair)).collect()
+    }
+}
@@ -0,0 +1,396 @@
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{ConnectInfo, DefaultBodyLimit, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::{watch, Semaphore};
+
+use crate::batch::{self, PairError};
+use crate::cli::ServeArgs;
+use crate::config::Settings;
+use crate::filter::ScoreField;
+use crate::format::ScoreFormat;
+use crate::gate::{self, Gate, Refusal};
+#[cfg(feature = "grpc")]
+use crate::grpc;
+use crate::input::{self, Pair, RecordFields};
+use crate::output::{self, OutputFormat};
+use crate::stats::{self, Buckets, Distribution};
+
+/// Settings and limits shared by every request
+struct Server {
+    settings: Settings,
+    signature: String,
+    max_batch_pairs: usize,
+    /// One permit per `POST /batch` request that may be scored at once
+    batches: Semaphore,
+    /// Set once a shutdown signal arrived and in-flight requests are draining
+    draining: AtomicBool,
+    gate: Arc<Gate>,
+}
+
+/// Unix socket to serve HTTP on, given as `unix://PATH`
+#[derive(Debug, Clone)]
+pub struct SocketPath(pub PathBuf);
+
+impl FromStr for SocketPath {
+    type Err = String;
+
+    fn from_str(url: &str) -> Result<SocketPath, String> {
+        match url.strip_prefix("unix://") {
+            Some(path) if !path.is_empty() => Ok(SocketPath(PathBuf::from(path))),
+            _ => Err(format!("expected unix://PATH, got `{}`", url)),
+        }
+    }
+}
+
+/// Why a request was not scored, sent back as `{"error": "..."}`
+#[derive(Debug)]
+enum ApiError {
+    /// The body could not be read, e.g. because it is over the size limit
+    Body(BytesRejection),
+    /// The body is not JSON or a record lacks its texts
+    BadRequest(String),
+    /// Turned away before the body was read
+    Refused(Refusal),
+    /// A batch with more pairs than the server accepts
+    TooManyPairs { pairs: usize, limit: usize },
+    /// A pair breaks the token or memory limits of the server
+    Unprocessable(PairError),
+    Internal(String),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Body(rejection) => f.write_str(&rejection.body_text()),
+            ApiError::BadRequest(message) | ApiError::Internal(message) => f.write_str(message),
+            ApiError::Refused(refusal) => write!(f, "{}", refusal),
+            ApiError::TooManyPairs { pairs, limit } => write!(
+                f,
+                "batch of {} pairs is over the limit of {}; split it into smaller requests",
+                pairs, limit
+            ),
+            ApiError::Unprocessable(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            ApiError::Body(rejection) => rejection.status(),
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Refused(Refusal::Unauthorized) => StatusCode::UNAUTHORIZED,
+            ApiError::Refused(Refusal::RateLimited(_)) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Refused(Refusal::Busy) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::TooManyPairs { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let mut response = (status, Json(json!({ "error": self.to_string() }))).into_response();
+        let headers = response.headers_mut();
+        match self {
+            ApiError::Refused(Refusal::Unauthorized) => {
+                headers.insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            }
+            ApiError::Refused(Refusal::RateLimited(retry)) => {
+                headers.insert(header::RETRY_AFTER, gate::retry_secs(retry).into());
+            }
+            ApiError::Refused(Refusal::Busy) => {
+                headers.insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+            }
+            _ => {}
+        }
+        response
+    }
+}
+
+/// Serve `POST /score`, `POST /batch` and `POST /stats` on the `--host` and
+/// `--port`, or the `--listen` Unix socket, and the gRPC service on
+/// `--grpc-port`, until SIGTERM or Ctrl-C
+///
+/// All take records shaped like the lines of a JSONL `--input`, with
+/// `candidate`, `reference` and optional `id` fields: `/score` one record,
+/// answered with the NDJSON result line, `/batch` an array of them, answered
+/// with the JSON output of `batch`, and `/stats` an array answered with the
+/// statistics of each score and the F-measure histogram. Records without an
+/// id are numbered from 1. Bodies over `--max-request-size` and arrays over
+/// `--max-batch-pairs` are refused with `413`, and no more than
+/// `--batch-concurrency` arrays are scored at once, each in parallel.
+///
+/// With `--auth-token-file`, scoring requests and RPCs without an
+/// `Authorization: Bearer` header holding one of its tokens are refused with
+/// `401` or `UNAUTHENTICATED`. Clients over `--rate-limit` requests a minute
+/// are refused with `429` or `RESOURCE_EXHAUSTED`, and requests beyond
+/// `--max-concurrent-requests` in flight with `503` or `UNAVAILABLE`. The
+/// health checks stay open to probes.
+///
+/// `GET /healthz` answers as long as the process runs, and `GET /readyz`
+/// until shutdown starts. On a shutdown signal both servers stop accepting
+/// connections and return once the requests in flight are answered.
+pub fn run(args: &ServeArgs, settings: Settings) -> io::Result<()> {
+    let gate = Arc::new(Gate::new(args)?);
+    let server = Arc::new(Server {
+        settings,
+        signature: settings.signature(),
+        max_batch_pairs: args.max_batch_pairs as usize,
+        batches: Semaphore::new(args.batch_concurrency as usize),
+        draining: AtomicBool::new(false),
+        gate: gate.clone(),
+    });
+    let app = Router::new()
+        .route("/score", post(score))
+        .route("/batch", post(score_batch))
+        .route("/stats", post(score_stats))
+        .route_layer(middleware::from_fn_with_state(server.clone(), guard))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .layer(DefaultBodyLimit::max((args.max_request_size as usize) << 20))
+        .with_state(server.clone());
+    let addr = SocketAddr::from((args.host, args.port));
+    tokio::runtime::Runtime::new()?.block_on(async {
+        let (stop, stopping) = watch::channel(false);
+        tokio::spawn(async move {
+            if let Err(err) = shutdown_signal().await {
+                eprintln!("warning: cannot listen for shutdown signals: {}", err);
+                return;
+            }
+            eprintln!("shutting down; draining in-flight requests");
+            server.draining.store(true, Ordering::SeqCst);
+            let _ = stop.send(true);
+        });
+        let http = async {
+            if let Some(SocketPath(path)) = &args.listen {
+                return serve_unix(path, app, stopped(stopping.clone())).await;
+            }
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            eprintln!("listening on http://{}", listener.local_addr()?);
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(stopped(stopping.clone()))
+                .await
+        };
+        #[cfg(feature = "grpc")]
+        let http = async {
+            let grpc = async {
+                match args.grpc_port {
+                    Some(port) => {
+                        grpc::serve((args.host, port).into(), settings, gate, stopped(stopping.clone())).await
+                    }
+                    None => Ok(()),
+                }
+            };
+            tokio::try_join!(http, grpc).map(|_| ())
+        };
+        http.await
+    })
+}
+
+/// Serve HTTP on a Unix socket at `path` until `shutdown` resolves, then
+/// remove the socket
+///
+/// A socket left behind by a server that is gone is replaced; one that
+/// still accepts connections is not. Clients on the socket share one rate
+/// limit, having no address to tell them apart.
+#[cfg(unix)]
+async fn serve_unix(path: &Path, app: Router, shutdown: impl Future<Output = ()> + Send + 'static) -> io::Result<()> {
+    use std::fs;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixStream;
+
+    if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("another server is listening on {}", path.display()),
+            ));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    eprintln!("listening on unix://{}", path.display());
+    let served = axum::serve(listener, app).with_graceful_shutdown(shutdown).await;
+    let _ = fs::remove_file(path);
+    served
+}
+
+#[cfg(not(unix))]
+async fn serve_unix(_path: &Path, _app: Router, _shutdown: impl Future<Output = ()> + Send + 'static) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Unix sockets are not supported on this platform",
+    ))
+}
+
+/// Resolves on SIGTERM, as sent by orchestrators to stop a container, or on Ctrl-C
+async fn shutdown_signal() -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            interrupted = tokio::signal::ctrl_c() => interrupted,
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await
+}
+
+/// Resolves once shutdown starts
+async fn stopped(mut stopping: watch::Receiver<bool>) {
+    let _ = stopping.wait_for(|&stopping| stopping).await;
+}
+
+/// Pass a scoring request through the `Gate`, holding its slot until it is answered
+async fn guard(State(server): State<Arc<Server>>, request: Request, next: Next) -> Result<Response, ApiError> {
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let authorization = request.headers().get(header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+    server.gate.admit(client, authorization).map_err(ApiError::Refused)?;
+    let _slot = server.gate.slot().map_err(ApiError::Refused)?;
+    Ok(next.run(request).await)
+}
+
+/// Liveness: the process is up and answering
+async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness: new requests are welcome until shutdown starts
+async fn readyz(State(server): State<Arc<Server>>) -> (StatusCode, Json<Value>) {
+    if server.draining.load(Ordering::SeqCst) {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "status": "draining" })))
+    } else {
+        (StatusCode::OK, Json(json!({ "status": "ready" })))
+    }
+}
+
+#[tracing::instrument(name = "POST /score", skip_all)]
+async fn score(
+    State(server): State<Arc<Server>>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, ApiError> {
+    let pair = input::record_pair(&parse_body(body)?, &RecordFields::default(), 1).map_err(ApiError::BadRequest)?;
+    server.render(vec![pair], OutputFormat::Ndjson).await
+}
+
+#[tracing::instrument(name = "POST /batch", skip_all, fields(pairs = tracing::field::Empty))]
+async fn score_batch(
+    State(server): State<Arc<Server>>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Response, ApiError> {
+    let pairs = server.batch_pairs(body)?;
+    let _turn = server.batches.acquire().await.map_err(|e| ApiError::Internal(e.to_string()))?;
+    server.clone().render(pairs, OutputFormat::Json).await
+}
+
+#[tracing::instrument(name = "POST /stats", skip_all, fields(pairs = tracing::field::Empty))]
+async fn score_stats(
+    State(server): State<Arc<Server>>,
+    body: Result<Bytes, BytesRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let pairs = server.batch_pairs(body)?;
+    let _turn = server.batches.acquire().await.map_err(|e| ApiError::Internal(e.to_string()))?;
+    let span = tracing::Span::current();
+    let server = server.clone();
+    let body = tokio::task::spawn_blocking(move || {
+        let _request = span.enter();
+        let (scores, _) = batch::score_pairs(&pairs, &server.settings, None, None).map_err(ApiError::Unprocessable)?;
+        let mut body = json!({ "count": scores.len() });
+        for (name, field) in stats::SCORE_FIELDS {
+            let key = name.to_lowercase().replace('-', "_");
+            body[key] = distribution_json(&Distribution::of(&scores, field), &server.settings.score_format);
+        }
+        body["histogram"] = output::buckets_json(&Buckets::of(&scores, ScoreField::F, output::BUCKET_WIDTH));
+        body["signature"] = Value::from(server.signature.as_str());
+        Ok(body)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))??;
+    Ok(Json(body))
+}
+
+impl Server {
+    /// The records of a `/batch` or `/stats` body, within the batch size limit
+    fn batch_pairs(&self, body: Result<Bytes, BytesRejection>) -> Result<Vec<Pair>, ApiError> {
+        let Value::Array(records) = parse_body(body)? else {
+            return Err(ApiError::BadRequest("expected an array of records".into()));
+        };
+        tracing::Span::current().record("pairs", records.len());
+        if records.len() > self.max_batch_pairs {
+            return Err(ApiError::TooManyPairs {
+                pairs: records.len(),
+                limit: self.max_batch_pairs,
+            });
+        }
+        records
+            .iter()
+            .enumerate()
+            .map(|(index, record)| {
+                input::record_pair(record, &RecordFields::default(), index + 1)
+                    .map_err(|message| ApiError::BadRequest(format!("record {}: {}", index + 1, message)))
+            })
+            .collect()
+    }
+
+    /// Score the pairs off the async workers and write them as `format`
+    async fn render(self: Arc<Self>, pairs: Vec<Pair>, format: OutputFormat) -> Result<Response, ApiError> {
+        let span = tracing::Span::current();
+        let body = tokio::task::spawn_blocking(move || {
+            let _request = span.enter();
+            let (scores, _) = batch::score_pairs(&pairs, &self.settings, None, None).map_err(ApiError::Unprocessable)?;
+            let mut body = Vec::new();
+            output::write_results(
+                vec![(&mut body, format)],
+                &scores,
+                &self.settings.score_format,
+                &self.signature,
+                self.settings.token_limit.is_some(),
+                self.settings.bootstrap,
+            )
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+            Ok(body)
+        })
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))??;
+        Ok(([(header::CONTENT_TYPE, "application/json")], body).into_response())
+    }
+}
+
+/// The statistics of a score as a JSON object, rounded like the scores
+fn distribution_json(distribution: &Distribution, score_format: &ScoreFormat) -> Value {
+    let score = |value| output::json_score(value, score_format);
+    json!({
+        "mean": score(distribution.mean()),
+        "variance": score(distribution.variance()),
+        "std_dev": score(distribution.std_dev()),
+        "min": score(distribution.min()),
+        "p25": score(distribution.percentile(stats::QUARTILES.0)),
+        "median": score(distribution.median()),
+        "p75": score(distribution.percentile(stats::QUARTILES.1)),
+        "max": score(distribution.max()),
+    })
+}
+
+fn parse_body(body: Result<Bytes, BytesRejection>) -> Result<Value, ApiError> {
+    let body = body.map_err(ApiError::Body)?;
+    serde_json::from_slice(&body).map_err(|e| ApiError::BadRequest(format!("invalid JSON: {}", e)))
+}
+
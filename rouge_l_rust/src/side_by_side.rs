@@ -0,0 +1,132 @@
+use std::env;
+use std::io::{self, IsTerminal, Write};
+
+use crate::alignment::Matched;
+use crate::display;
+
+/// Width used when neither --width nor `COLUMNS` gives one
+const DEFAULT_WIDTH: usize = 80;
+
+/// Width of the gutter between the columns, e.g. ` | `
+const GUTTER: usize = 3;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// Consecutive tokens shown together: either a run on the LCS, present on
+/// both sides, or the gap between two such runs
+struct Block<'a> {
+    candidate: Vec<&'a str>,
+    reference: Vec<&'a str>,
+    kept: bool,
+}
+
+/// Terminal width to render at: `width` when given, else `COLUMNS`, else 80
+pub fn width(width: Option<u16>) -> usize {
+    width
+        .map(usize::from)
+        .or_else(|| env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+/// Print the candidate and reference of `matched`, one of `references`, in
+/// two columns of `width` characters in all, each LCS run level with its
+/// counterpart
+///
+/// The gutter marks blocks like `diff -y`: `|` where both sides differ, `<`
+/// for candidate tokens missing from the reference, `>` for reference
+/// tokens missing from the candidate. On a terminal, and unless `NO_COLOR`
+/// is set, removed tokens are red and added ones green.
+pub fn render(out: &mut dyn Write, matched: &Matched, references: usize, width: usize) -> io::Result<()> {
+    let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let column = (width.saturating_sub(GUTTER) / 2).max(1);
+    let reference = match references {
+        1 => "Reference".to_string(),
+        _ => format!("Reference {} of {}", matched.reference + 1, references),
+    };
+    writeln!(out, "{:<column$}   {}", "Candidate", reference, column = column)?;
+    writeln!(out, "{}", "-".repeat(column * 2 + GUTTER))?;
+    for block in blocks(matched) {
+        let left = wrap(&block.candidate, column);
+        let right = wrap(&block.reference, column);
+        let gutter = match (block.kept, block.candidate.is_empty(), block.reference.is_empty()) {
+            (true, _, _) => ' ',
+            (false, _, true) => '<',
+            (false, true, _) => '>',
+            (false, false, false) => '|',
+        };
+        for row in 0..left.len().max(right.len()) {
+            let left = left.get(row).map_or("", String::as_str);
+            let right = right.get(row).map_or("", String::as_str);
+            let pad = " ".repeat(column.saturating_sub(display::width(left)));
+            let line = match (color, block.kept) {
+                (true, false) => format!("{RED}{left}{RESET}{pad} {gutter} {GREEN}{right}{RESET}"),
+                _ => format!("{left}{pad} {gutter} {right}"),
+            };
+            writeln!(out, "{}", line.trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+/// Split both texts at the LCS into alternating gaps and kept runs
+fn blocks(matched: &Matched) -> Vec<Block<'_>> {
+    let (candidate, reference) = (&matched.candidate_tokens, &matched.reference_tokens);
+    let mut blocks: Vec<Block> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let ends = std::iter::once((candidate.len(), reference.len()));
+    for (next_i, next_j) in matched.alignment.iter().copied().chain(ends) {
+        if next_i > i || next_j > j {
+            blocks.push(Block {
+                candidate: candidate[i..next_i].iter().map(String::as_str).collect(),
+                reference: reference[j..next_j].iter().map(String::as_str).collect(),
+                kept: false,
+            });
+        }
+        if next_i < candidate.len() {
+            match blocks.last_mut() {
+                Some(last) if last.kept => {
+                    last.candidate.push(&candidate[next_i]);
+                    last.reference.push(&reference[next_j]);
+                }
+                _ => blocks.push(Block {
+                    candidate: vec![&candidate[next_i]],
+                    reference: vec![&reference[next_j]],
+                    kept: true,
+                }),
+            }
+        }
+        (i, j) = (next_i + 1, next_j + 1);
+    }
+    blocks
+}
+
+/// Fill lines of at most `width` columns with the tokens, splitting tokens
+/// wider than a line
+fn wrap(tokens: &[&str], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_len = 0;
+    for token in tokens {
+        let mut pieces = display::split(token, width);
+        let last = pieces.pop().unwrap_or_default();
+        if line_len > 0 && line_len + 1 + display::width(token) > width {
+            lines.push(std::mem::take(&mut line));
+            line_len = 0;
+        }
+        if !pieces.is_empty() {
+            lines.extend(pieces.into_iter().map(str::to_string));
+        }
+        if line_len > 0 {
+            line.push(' ');
+            line_len += 1;
+        }
+        line.push_str(last);
+        line_len += display::width(last);
+    }
+    if line_len > 0 {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::batch::ScoredPair;
+use crate::format::ScoreFormat;
+use crate::input::{InputError, Pair, RecordFields};
+
+/// Prefix marking an `--input` that names a SQLite database, e.g. `sqlite:evals.db`
+pub const PREFIX: &str = "sqlite:";
+
+/// Run `query` on the database at `path` and read one pair per result row
+///
+/// Result columns are matched by the field names, so a query can rename them
+/// with `AS`; rows without an id column are identified by their 1-based row
+/// number. Integer and real values are read as text, while NULL and BLOB
+/// texts are errors. The database is opened read-only.
+pub fn read_pairs(path: &Path, query: &str, fields: &RecordFields) -> Result<Vec<Pair>, InputError> {
+    let invalid = |err: rusqlite::Error| InputError::Invalid(path.to_path_buf(), err.to_string());
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(invalid)?;
+    let mut statement = connection.prepare(query).map_err(invalid)?;
+
+    let required = |name: &str| {
+        statement
+            .column_index(name)
+            .map_err(|_| InputError::Invalid(path.to_path_buf(), format!("query returns no column `{}`", name)))
+    };
+    let candidate = required(&fields.candidate)?;
+    let reference = required(&fields.reference)?;
+    let id = statement.column_index(&fields.id).ok();
+
+    let mut rows = statement.query([]).map_err(invalid)?;
+    let mut pairs = Vec::new();
+    while let Some(row) = rows.next().map_err(invalid)? {
+        let number = pairs.len() + 1;
+        let parse_error = |name: &str, problem: &str| InputError::Parse {
+            path: path.to_path_buf(),
+            line: number,
+            message: format!("column `{}` {}", name, problem),
+            record: None,
+        };
+        let text = |index: usize, name: &str| match row.get_ref(index).map_err(invalid)? {
+            ValueRef::Null => Ok(None),
+            ValueRef::Integer(value) => Ok(Some(value.to_string())),
+            ValueRef::Real(value) => Ok(Some(value.to_string())),
+            ValueRef::Text(bytes) => String::from_utf8(bytes.to_vec())
+                .map(Some)
+                .map_err(|_| parse_error(name, "is not valid UTF-8")),
+            ValueRef::Blob(_) => Err(parse_error(name, "is a BLOB")),
+        };
+        let required_text = |index: usize, name: &str| text(index, name)?.ok_or_else(|| parse_error(name, "is null"));
+
+        let id = match id {
+            Some(index) => text(index, &fields.id)?,
+            None => None,
+        };
+        pairs.push(Pair {
+            id: id.unwrap_or_else(|| number.to_string()),
+            candidate: required_text(candidate, &fields.candidate)?,
+            references: vec![required_text(reference, &fields.reference)?],
+            hypotheses: Vec::new(),
+        });
+    }
+    Ok(pairs)
+}
+
+/// Error raised while writing scores back into a SQLite database
+#[derive(Debug)]
+pub struct WriteError {
+    path: PathBuf,
+    source: rusqlite::Error,
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot write scores to {}: {}", self.path.display(), self.source)
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// Store every result in `table`, creating it if needed, and return the
+/// number of rows written
+///
+/// Rows are keyed by id, so rescoring replaces the earlier scores of a pair.
+/// Scores are rounded as they are printed and stored with the signature of
+/// the settings that produced them, all in one transaction.
+pub fn write_scores(
+    path: &Path,
+    table: &str,
+    scores: &[ScoredPair],
+    score_format: &ScoreFormat,
+    signature: &str,
+) -> Result<usize, WriteError> {
+    let fail = |source| WriteError {
+        path: path.to_path_buf(),
+        source,
+    };
+    let table = format!("\"{}\"", table.replace('"', "\"\""));
+    let mut connection = Connection::open(path).map_err(fail)?;
+    let transaction = connection.transaction().map_err(fail)?;
+    transaction
+        .execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (id TEXT PRIMARY KEY, f_measure REAL NOT NULL, \
+                 precision REAL NOT NULL, recall REAL NOT NULL, truncated INTEGER NOT NULL, signature TEXT NOT NULL)",
+                table
+            ),
+            [],
+        )
+        .map_err(fail)?;
+    {
+        let mut insert = transaction
+            .prepare(&format!(
+                "INSERT OR REPLACE INTO {} (id, f_measure, precision, recall, truncated, signature) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                table
+            ))
+            .map_err(fail)?;
+        for scored in scores {
+            insert
+                .execute(params![
+                    scored.id,
+                    score_format.round(scored.result.f_measure),
+                    score_format.round(scored.result.precision),
+                    score_format.round(scored.result.recall),
+                    scored.truncated,
+                    signature,
+                ])
+                .map_err(fail)?;
+        }
+    }
+    transaction.commit().map_err(fail)?;
+    Ok(scores.len())
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::compress;
+use crate::input::{InputError, Pair};
+
+/// Questions of a SQuAD gold file paired with their predicted answers
+#[derive(Debug, Default)]
+pub struct SquadPairs {
+    pub pairs: Vec<Pair>,
+    /// Ids of the questions with no prediction, scored as an empty answer
+    pub unanswered: Vec<String>,
+}
+
+/// Pair every question of a SQuAD v1.1 or v2.0 gold file with its answer
+/// from a predictions file mapping question ids to answer text
+///
+/// The distinct gold answers of a question become its references, so a
+/// prediction keeps its best score over them as in the official evaluation
+/// script. Questions follow the gold file order; an unanswerable SQuAD 2.0
+/// question gets a single empty reference, and predictions for ids missing
+/// from the gold file are ignored.
+pub fn read_pairs(gold: &Path, predictions: &Path) -> Result<SquadPairs, InputError> {
+    let answers = match read_json(predictions)? {
+        Value::Object(answers) => answers,
+        _ => return Err(invalid(predictions, "expected an object mapping question ids to answers")),
+    };
+    let dataset = read_json(gold)?;
+
+    let mut result = SquadPairs::default();
+    for article in list(gold, &dataset, "data")? {
+        for paragraph in list(gold, article, "paragraphs")? {
+            for question in list(gold, paragraph, "qas")? {
+                let Some(id) = question.get("id").and_then(Value::as_str) else {
+                    return Err(invalid(gold, "question without a string `id`"));
+                };
+                let mut references = Vec::new();
+                for answer in list(gold, question, "answers")? {
+                    let Some(text) = answer.get("text").and_then(Value::as_str) else {
+                        return Err(invalid(gold, &format!("answer to question {} without a string `text`", id)));
+                    };
+                    if !references.iter().any(|reference| reference == text) {
+                        references.push(text.to_string());
+                    }
+                }
+                if references.is_empty() {
+                    references.push(String::new());
+                }
+                let candidate = match answers.get(id) {
+                    Some(Value::String(answer)) => answer.clone(),
+                    Some(_) => {
+                        return Err(invalid(predictions, &format!("answer to question {} is not a string", id)))
+                    }
+                    None => {
+                        result.unanswered.push(id.to_string());
+                        String::new()
+                    }
+                };
+                result.pairs.push(Pair {
+                    id: id.to_string(),
+                    candidate,
+                    references,
+                    hypotheses: Vec::new(),
+                });
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn read_json(path: &Path) -> Result<Value, InputError> {
+    let text = compress::read_to_string(path).map_err(|e| InputError::Io(path.to_path_buf(), e))?;
+    serde_json::from_str(&text).map_err(|e| invalid(path, &e.to_string()))
+}
+
+/// The list under `key` in a SQuAD object
+fn list<'a>(path: &Path, value: &'a Value, key: &str) -> Result<&'a [Value], InputError> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .ok_or_else(|| invalid(path, &format!("missing `{}` list", key)))
+}
+
+fn invalid(path: &Path, message: &str) -> InputError {
+    InputError::Invalid(path.to_path_buf(), message.to_string())
+}
@@ -0,0 +1,342 @@
+use std::io::{self, Write};
+
+use crate::batch::ScoredPair;
+use crate::filter::ScoreField;
+use crate::format::ScoreFormat;
+use crate::output;
+
+/// Percentiles listed besides the median in statistics tables
+pub const QUARTILES: (f64, f64) = (25.0, 75.0);
+
+/// Headings of the statistics in [`Distribution::summary`] order
+pub const COLUMNS: [&str; 7] = ["Mean", "Std dev", "Min", "P25", "Median", "P75", "Max"];
+
+/// Score fields described by `--stats`, the report and `POST /stats`
+pub const SCORE_FIELDS: [(&str, ScoreField); 3] = [
+    ("F-Measure", ScoreField::F),
+    ("Precision", ScoreField::Precision),
+    ("Recall", ScoreField::Recall),
+];
+
+/// Descriptive statistics of one metric over a set of pairs
+///
+/// Every statistic of an empty set is 0, as are the averages of an empty
+/// batch.
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    /// Values in input order, so sums come out as in the running averages
+    values: Vec<f64>,
+    sorted: Vec<f64>,
+}
+
+impl Distribution {
+    pub fn new(values: Vec<f64>) -> Distribution {
+        let mut sorted = values.clone();
+        sorted.sort_by(f64::total_cmp);
+        Distribution { values, sorted }
+    }
+
+    /// Distribution of `field` over the scored pairs
+    pub fn of(scores: &[ScoredPair], field: ScoreField) -> Distribution {
+        Distribution::new(scores.iter().map(|scored| field.value(scored)).collect())
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        self.values.iter().sum::<f64>() / self.values.len() as f64
+    }
+
+    /// Population variance, dividing by the number of values like NumPy's
+    /// `var`: a batch is the whole set being described, not a sample
+    pub fn variance(&self) -> f64 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        let mean = self.mean();
+        self.values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / self.values.len() as f64
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    pub fn min(&self) -> f64 {
+        self.sorted.first().copied().unwrap_or(0.0)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.sorted.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn median(&self) -> f64 {
+        self.percentile(50.0)
+    }
+
+    /// Value below which `percentile` percent of the values fall,
+    /// interpolating linearly between the two nearest ranks like NumPy's
+    /// default `percentile`
+    pub fn percentile(&self, percentile: f64) -> f64 {
+        let Some(last) = self.sorted.len().checked_sub(1) else {
+            return 0.0;
+        };
+        let rank = percentile.clamp(0.0, 100.0) / 100.0 * last as f64;
+        let (below, above) = (rank.floor() as usize, rank.ceil() as usize);
+        let weight = rank - below as f64;
+        self.sorted[below] + (self.sorted[above] - self.sorted[below]) * weight
+    }
+
+    /// The statistics shown in tables, headed by [`COLUMNS`]
+    pub fn summary(&self) -> [f64; 7] {
+        [
+            self.mean(),
+            self.std_dev(),
+            self.min(),
+            self.percentile(QUARTILES.0),
+            self.median(),
+            self.percentile(QUARTILES.1),
+            self.max(),
+        ]
+    }
+}
+
+/// Seeded splitmix64 generator behind resampling, so the same seed always
+/// draws the same samples on every platform
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// An index below `n`, which must not be 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A fair coin toss
+    pub fn coin(&mut self) -> bool {
+        self.next_u64() >> 63 == 1
+    }
+}
+
+/// Means of bootstrap resamples of a set of values
+#[derive(Debug, Clone)]
+pub struct Resampled {
+    /// Mean of the values themselves
+    pub mean: f64,
+    /// Mean of every resample, sorted
+    pub means: Vec<f64>,
+}
+
+impl Resampled {
+    /// Bounds holding the middle `confidence` percent of the resampled means
+    ///
+    /// As in ROUGE-1.5.5, each bound is the resampled mean at the rank of
+    /// its quantile, rounded down.
+    pub fn interval(&self, confidence: f64) -> (f64, f64) {
+        if self.means.is_empty() {
+            return (0.0, 0.0);
+        }
+        let tail = (1.0 - confidence / 100.0) / 2.0;
+        let at = |quantile: f64| self.means[((quantile * self.means.len() as f64) as usize).min(self.means.len() - 1)];
+        (at(tail), at(1.0 - tail))
+    }
+}
+
+/// Draw `iters` samples of `values` with replacement from a generator seeded
+/// with `seed`, and take the mean of each
+///
+/// Resampling the per-pair differences between two systems gives a paired
+/// bootstrap test of the difference of their means.
+#[tracing::instrument(name = "bootstrap", level = "debug", skip_all, fields(resamples = iters))]
+pub fn bootstrap(values: &[f64], iters: usize, seed: u64) -> Resampled {
+    let mean = Distribution::new(values.to_vec()).mean();
+    if values.is_empty() {
+        return Resampled { mean, means: Vec::new() };
+    }
+    let mut rng = Rng::new(seed);
+    let mut means: Vec<f64> = (0..iters)
+        .map(|_| {
+            let sum: f64 = (0..values.len()).map(|_| values[rng.below(values.len())]).sum();
+            sum / values.len() as f64
+        })
+        .collect();
+    means.sort_by(f64::total_cmp);
+    Resampled { mean, means }
+}
+
+/// Two-sided p-value of the approximate randomization test that paired
+/// `differences` have a mean of 0
+///
+/// Under that hypothesis either system was as likely to produce either
+/// score of a pair, so every difference may flip its sign. Of `iters` random
+/// sign flips drawn from `seed`, the share whose mean is at least as far from
+/// 0 as the observed one estimates the p-value, counting the observed
+/// assignment so it is never 0.
+#[tracing::instrument(name = "approximate_randomization", level = "debug", skip_all, fields(iters))]
+pub fn approximate_randomization(differences: &[f64], iters: usize, seed: u64) -> f64 {
+    let observed = differences.iter().sum::<f64>().abs();
+    let mut rng = Rng::new(seed);
+    let extreme = (0..iters)
+        .filter(|_| {
+            let sum: f64 = differences
+                .iter()
+                .map(|&difference| if rng.coin() { -difference } else { difference })
+                .sum();
+            // Tolerate the rounding of sums taken in another order
+            sum.abs() >= observed * (1.0 - 1e-12)
+        })
+        .count();
+    (extreme + 1) as f64 / (iters + 1) as f64
+}
+
+/// Counts of a score in equal-width intervals over [0, 1]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buckets {
+    width: f64,
+    pub counts: Vec<u64>,
+}
+
+impl Buckets {
+    /// Empty buckets `width` wide, in (0, 1]; the last one is narrower when
+    /// `width` does not divide 1
+    pub fn new(width: f64) -> Buckets {
+        // Widths such as 0.1 are not exact in binary
+        let buckets = (1.0 / width - 1e-9).ceil().max(1.0) as usize;
+        Buckets {
+            width,
+            counts: vec![0; buckets],
+        }
+    }
+
+    /// Count the values of `field` over the scored pairs into buckets
+    /// `width` wide
+    pub fn of(scores: &[ScoredPair], field: ScoreField, width: f64) -> Buckets {
+        let mut buckets = Buckets::new(width);
+        for scored in scores {
+            buckets.add(field.value(scored));
+        }
+        buckets
+    }
+
+    /// Count `value`; a perfect 1.0, or any larger value, lands in the last
+    /// bucket
+    pub fn add(&mut self, value: f64) {
+        let bucket = (value.max(0.0) * (1.0 / self.width)) as usize;
+        let last = self.counts.len() - 1;
+        self.counts[bucket.min(last)] += 1;
+    }
+
+    /// Lower and upper bound of bucket `i`
+    pub fn bounds(&self, i: usize) -> (f64, f64) {
+        let edge = |i: usize| ((i as f64 * self.width).min(1.0) * 1e9).round() / 1e9;
+        (edge(i), edge(i + 1))
+    }
+
+    /// Bucket `i` as `LOW-HIGH`, e.g. `0.3-0.4`, with as many decimals as
+    /// the width needs
+    pub fn label(&self, i: usize) -> String {
+        let decimals = (1..6)
+            .find(|&decimals| {
+                let scaled = self.width * 10f64.powi(decimals);
+                (scaled - scaled.round()).abs() < 1e-6
+            })
+            .unwrap_or(6) as usize;
+        let (low, high) = self.bounds(i);
+        format!("{:.decimals$}-{:.decimals$}", low, high, decimals = decimals)
+    }
+}
+
+/// Pearson's linear correlation of paired values, NaN when either side is
+/// constant
+pub fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    let (mean_x, mean_y) = (Distribution::new(x.to_vec()).mean(), Distribution::new(y.to_vec()).mean());
+    let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+    for (&x, &y) in x.iter().zip(y) {
+        covariance += (x - mean_x) * (y - mean_y);
+        variance_x += (x - mean_x).powi(2);
+        variance_y += (y - mean_y).powi(2);
+    }
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return f64::NAN;
+    }
+    covariance / (variance_x * variance_y).sqrt()
+}
+
+/// Spearman's rank correlation: Pearson's over the ranks of the values,
+/// tied values sharing the mean of their ranks
+pub fn spearman(x: &[f64], y: &[f64]) -> f64 {
+    pearson(&ranks(x), &ranks(y))
+}
+
+/// Kendall's tau-b, the balance of concordant and discordant pairs of
+/// pairs, corrected for ties on either side; NaN when either side is constant
+pub fn kendall_tau(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len().min(y.len());
+    let (mut concordant, mut discordant, mut tied_x, mut tied_y) = (0u64, 0u64, 0u64, 0u64);
+    for i in 0..n {
+        for j in i + 1..n {
+            let (dx, dy) = (x[i] - x[j], y[i] - y[j]);
+            match (dx == 0.0, dy == 0.0) {
+                (true, true) => {}
+                (true, false) => tied_x += 1,
+                (false, true) => tied_y += 1,
+                (false, false) if (dx > 0.0) == (dy > 0.0) => concordant += 1,
+                (false, false) => discordant += 1,
+            }
+        }
+    }
+    let untied_x = (concordant + discordant + tied_y) as f64;
+    let untied_y = (concordant + discordant + tied_x) as f64;
+    if untied_x == 0.0 || untied_y == 0.0 {
+        return f64::NAN;
+    }
+    (concordant as f64 - discordant as f64) / (untied_x * untied_y).sqrt()
+}
+
+/// 1-based rank of every value, ties getting the mean of the ranks they span
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        // Ranks start + 1 to end
+        let rank = (start + 1 + end) as f64 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// Print the mean, standard deviation, extremes and quartiles of every score
+/// as a table with a row per metric
+pub fn write_table(out: &mut dyn Write, scores: &[ScoredPair], score_format: &ScoreFormat) -> io::Result<()> {
+    let mut header = vec![String::new()];
+    header.extend(COLUMNS.map(String::from));
+    let mut rows = vec![header];
+    for (name, field) in SCORE_FIELDS {
+        let mut row = vec![name.to_string()];
+        row.extend(Distribution::of(scores, field).summary().map(|value| score_format.format(value)));
+        rows.push(row);
+    }
+    writeln!(out, "\nScore statistics ({} pairs)", scores.len())?;
+    output::write_columns(out, &rows, 1)
+}
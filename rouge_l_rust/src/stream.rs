@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender};
+use std::thread;
+
+use rouge_l_rust::intern::Vocab;
+use rouge_l_rust::lcs::Scratch;
+use rouge_l_rust::summary;
+
+use crate::batch::{self, PreparedPair, ScoreStats, ScoredPair, Summary, SummaryBuilder};
+use crate::checkpoint::Checkpoint;
+use crate::config::Settings;
+use crate::filter::{self, Filter};
+use crate::input::{ErrorLog, Pair, Records};
+use crate::output::ResultWriters;
+
+/// Work handed between stages, or the error that ended the input
+type Step<T> = Result<T, Box<dyn Error + Send>>;
+
+/// Chunks of work handed between stages
+type Chunk<T> = Step<Positioned<T>>;
+
+/// Items of a chunk, with the input position just past the last record read
+/// for it, malformed records included
+struct Positioned<T> {
+    end: usize,
+    items: Vec<T>,
+}
+
+/// Chunks held by the reader and tokenizer stages and their channels, each
+/// stage working on one while its channel buffers one more
+const STAGE_CHUNKS: usize = 4;
+
+/// A pair after interning, tagged with its position in the input
+struct Tokenized {
+    index: usize,
+    id: String,
+    pair: PreparedPair,
+}
+
+/// Results of a chunk, `None` for pairs dropped by the prefilter
+struct Scored {
+    /// Input position just past the last record read for the chunk
+    end: usize,
+    results: Vec<Option<ScoredPair>>,
+}
+
+/// Score a JSON Lines or Parquet file in a reader → tokenizer → scorer → writer pipeline
+///
+/// Stages run on their own threads joined by bounded channels, so no more
+/// than about `in_flight` records are held in memory however large the file
+/// is. Several chunks are scored at once, one per rayon worker, and put back
+/// in input order before they reach `writer`, so the output is the same for
+/// any thread count. Pairs that the bag-of-words bound shows cannot pass
+/// `filters` are not scored, results failing them are dropped and left out
+/// of the summary, and malformed records go to `errors` when given.
+///
+/// With `checkpoint`, the run starts at its position with its summary and
+/// counts, and saves it to the given path after every chunk it writes.
+pub fn run(
+    reader: Records,
+    errors: Option<&mut ErrorLog>,
+    filters: &[Filter],
+    settings: &Settings,
+    in_flight: usize,
+    checkpoint: Option<(&Path, Checkpoint)>,
+    writer: &mut ResultWriters,
+) -> Result<(Summary, ScoreStats), Box<dyn Error>> {
+    let scoring = scoring_chunks();
+    let chunk_size = (in_flight / (STAGE_CHUNKS + scoring)).max(1);
+    let (pair_tx, pair_rx) = sync_channel(1);
+    let (token_tx, token_rx) = sync_channel(1);
+    // The scorer takes a permit per chunk and the writer returns it once the
+    // chunk is written, bounding the chunks being scored or awaiting reorder
+    let (permit_tx, permit_rx) = sync_channel(scoring);
+    let (score_tx, score_rx) = channel();
+    let min_f = filter::min_f_measure(filters);
+    let start = checkpoint.as_ref().map_or(0, |(_, checkpoint)| checkpoint.position);
+
+    thread::scope(|scope| {
+        scope.spawn(move || read(reader, start, errors, chunk_size, pair_tx));
+        scope.spawn(move || tokenize(pair_rx, settings, token_tx));
+        scope.spawn(move || score(token_rx, min_f, settings, permit_tx, score_tx));
+        write(score_rx, permit_rx, filters, checkpoint, writer)
+    })
+}
+
+/// Chunks scored concurrently: one per worker of the current rayon pool
+fn scoring_chunks() -> usize {
+    #[cfg(feature = "parallel")]
+    return rayon::current_num_threads();
+    #[cfg(not(feature = "parallel"))]
+    1
+}
+
+/// Index-based reorder buffer releasing items strictly by sequence number,
+/// however out of order they arrive
+struct Reorder<T> {
+    next: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<T> Reorder<T> {
+    fn new() -> Self {
+        Reorder {
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, seq: usize, item: T) {
+        self.pending.insert(seq, item);
+    }
+
+    /// The next item in sequence, once it has arrived
+    fn pop(&mut self) -> Option<T> {
+        let item = self.pending.remove(&self.next)?;
+        self.next += 1;
+        Some(item)
+    }
+}
+
+/// Read records from position `start` on, in chunks tagged with their
+/// position in the input, which counts malformed records too
+///
+/// Malformed records at the end of the input are sent as an empty chunk, so
+/// a checkpoint moves past them as well.
+fn read(
+    reader: Records,
+    start: usize,
+    mut errors: Option<&mut ErrorLog>,
+    chunk_size: usize,
+    tx: SyncSender<Chunk<(usize, Pair)>>,
+) {
+    let mut chunk = Vec::with_capacity(chunk_size);
+    let (mut position, mut sent) = (start, start);
+    for (index, pair) in reader.enumerate().skip(start) {
+        position = index + 1;
+        let err = match (pair, errors.as_deref_mut()) {
+            (Ok(pair), _) => {
+                chunk.push((index, pair));
+                None
+            }
+            (Err(err), Some(errors)) => errors.skip(err).err(),
+            (Err(err), None) => Some(err),
+        };
+        if let Some(err) = err {
+            let _ = tx.send(Err(Box::new(err)));
+            return;
+        }
+        if chunk.len() == chunk_size {
+            let full = std::mem::replace(&mut chunk, Vec::with_capacity(chunk_size));
+            sent = position;
+            if tx.send(Ok(Positioned { end: position, items: full })).is_err() {
+                return;
+            }
+        }
+    }
+    if !chunk.is_empty() || position > sent {
+        let _ = tx.send(Ok(Positioned { end: position, items: chunk }));
+    }
+}
+
+/// Intern every pair into one vocabulary and apply the limits; unlike
+/// `batch`, references are not cached, since distinct references would grow
+/// without bound
+fn tokenize(rx: Receiver<Chunk<(usize, Pair)>>, settings: &Settings, tx: SyncSender<Chunk<Tokenized>>) {
+    let mut vocab = Vocab::with_variant(settings.tokenizer, settings.variant);
+    for chunk in rx {
+        let chunk = chunk.and_then(|Positioned { end, items: pairs }| {
+            let _span = tracing::debug_span!("tokenize", pairs = pairs.len()).entered();
+            let items = pairs
+                .into_iter()
+                .map(|(index, pair)| {
+                    let candidate = vocab.tokenize(&pair.candidate);
+                    let references = pair.references.iter().map(|text| vocab.tokenize(text).into()).collect();
+                    let hypotheses = pair.hypotheses.iter().map(|text| vocab.tokenize(text)).collect();
+                    let prepared = batch::prepare_pair(settings, &pair.id, candidate, references, hypotheses)
+                        .map_err(|err| Box::new(err) as Box<dyn Error + Send>)?;
+                    Ok(Tokenized {
+                        index,
+                        id: pair.id,
+                        pair: prepared,
+                    })
+                })
+                .collect::<Step<Vec<Tokenized>>>()?;
+            Ok(Positioned { end, items })
+        });
+        if tx.send(chunk).is_err() {
+            return;
+        }
+    }
+}
+
+/// Score chunks as they arrive, each on its own rayon worker when the
+/// `parallel` feature is on
+fn score(
+    rx: Receiver<Chunk<Tokenized>>,
+    min_f: Option<f64>,
+    settings: &Settings,
+    permits: SyncSender<()>,
+    tx: Sender<(usize, Step<Scored>)>,
+) {
+    let score_chunk = move |chunk: Chunk<Tokenized>| {
+        chunk.map(|Positioned { end, items: pairs }| {
+            let _span = tracing::debug_span!("score", pairs = pairs.len()).entered();
+            let mut scratch = Scratch::default();
+            let results = pairs
+                .iter()
+                .map(|tokenized| {
+                    let pair = &tokenized.pair;
+                    if min_f.is_some_and(|min_f| batch::best_bound(pair) < min_f) {
+                        return None;
+                    }
+                    let (result, n_best) = batch::score_prepared(settings, &mut scratch, pair);
+                    Some(ScoredPair {
+                        index: tokenized.index,
+                        id: tokenized.id.clone(),
+                        candidate_tokens: summary::token_count(&pair.candidate),
+                        truncated: pair.truncated,
+                        result,
+                        n_best,
+                    })
+                })
+                .collect();
+            Scored { end, results }
+        })
+    };
+
+    #[cfg(feature = "parallel")]
+    rayon::in_place_scope(|scope| {
+        for (seq, chunk) in rx.into_iter().enumerate() {
+            if permits.send(()).is_err() {
+                return;
+            }
+            let tx = tx.clone();
+            scope.spawn(move |_| {
+                let _ = tx.send((seq, score_chunk(chunk)));
+            });
+        }
+    });
+    #[cfg(not(feature = "parallel"))]
+    for (seq, chunk) in rx.into_iter().enumerate() {
+        if permits.send(()).is_err() || tx.send((seq, score_chunk(chunk))).is_err() {
+            return;
+        }
+    }
+}
+
+fn write(
+    rx: Receiver<(usize, Step<Scored>)>,
+    permits: Receiver<()>,
+    filters: &[Filter],
+    mut checkpoint: Option<(&Path, Checkpoint)>,
+    writer: &mut ResultWriters,
+) -> Result<(Summary, ScoreStats), Box<dyn Error>> {
+    let (mut summary, mut stats) = match &checkpoint {
+        Some((_, checkpoint)) => (checkpoint.summary.clone(), checkpoint.stats),
+        None => (SummaryBuilder::default(), ScoreStats::default()),
+    };
+    let mut reorder = Reorder::new();
+    for (seq, chunk) in rx {
+        reorder.insert(seq, chunk);
+        while let Some(chunk) = reorder.pop() {
+            let chunk = chunk.map_err(|err| err as Box<dyn Error>)?;
+            let _span = tracing::debug_span!("write", pairs = chunk.results.len()).entered();
+            for scored in chunk.results {
+                stats.pairs += 1;
+                let Some(scored) = scored else {
+                    stats.skipped += 1;
+                    continue;
+                };
+                stats.truncated += usize::from(scored.truncated);
+                if filters.iter().all(|filter| filter.matches(&scored)) {
+                    writer.write(&scored)?;
+                    summary.add_scored(&scored);
+                }
+            }
+            if let Some((path, checkpoint)) = &mut checkpoint {
+                // Results must reach the output before the checkpoint counts them
+                writer.flush()?;
+                checkpoint.position = chunk.end;
+                checkpoint.summary = summary.clone();
+                checkpoint.stats = stats;
+                checkpoint.save(path)?;
+            }
+            let _ = permits.recv();
+        }
+    }
+    Ok((summary.finish(), stats))
+}
@@ -0,0 +1,238 @@
+//! Summary-level ROUGE-L (ROUGE-Lsum) over sentence-split texts
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::intern::Interner;
+use crate::lcs::{LcsStrategy, Scratch};
+use crate::tokenize::Tokenizer;
+use crate::{best_result, calculate_rouge_l, rouge_l_best_of_in, scores_from_lcs, RougeLResult};
+
+/// Token ID separating the sentences of a text tokenized for ROUGE-Lsum;
+/// never handed out by an [`Interner`]
+pub const SENTENCE_BREAK: u32 = u32::MAX;
+
+/// Which ROUGE-L score is computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Variant {
+    /// One LCS over each whole text
+    #[default]
+    RougeL,
+    /// Union LCS of every reference sentence with the candidate sentences
+    /// (`rougeLsum` in Python `rouge-score`)
+    RougeLsum,
+}
+
+impl Variant {
+    /// Name used on the command line and at the start of signatures
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::RougeL => "rouge-l",
+            Variant::RougeLsum => "rouge-lsum",
+        }
+    }
+
+    /// Whether texts are split into sentences before tokenization
+    pub fn splits_sentences(&self) -> bool {
+        *self == Variant::RougeLsum
+    }
+
+    /// Score two texts
+    pub fn calculate(
+        &self,
+        candidate: &str,
+        reference: &str,
+        tokenizer: Tokenizer,
+        strategy: LcsStrategy,
+    ) -> RougeLResult {
+        match self {
+            Variant::RougeL => calculate_rouge_l(candidate, reference, tokenizer, strategy),
+            Variant::RougeLsum => calculate_rouge_lsum(candidate, reference, tokenizer),
+        }
+    }
+
+    /// Score interned tokens against several references, keeping the best
+    /// F-measure; ROUGE-Lsum tokens carry [`SENTENCE_BREAK`]s
+    #[tracing::instrument(
+        name = "lcs",
+        level = "trace",
+        skip_all,
+        fields(candidate_tokens = candidate_words.len(), references = references.len())
+    )]
+    pub fn best_of_in<R: AsRef<[u32]>>(
+        &self,
+        scratch: &mut Scratch,
+        candidate_words: &[u32],
+        references: &[R],
+        strategy: LcsStrategy,
+    ) -> RougeLResult {
+        match self {
+            Variant::RougeL => rouge_l_best_of_in(scratch, candidate_words, references, strategy),
+            Variant::RougeLsum => best_result(
+                references
+                    .iter()
+                    .map(|reference| rouge_lsum_from_tokens(candidate_words, reference.as_ref())),
+            ),
+        }
+    }
+}
+
+/// Split a text into the sentences ROUGE-Lsum compares
+///
+/// Texts with `<s>`…`</s>` tags, as in preprocessed CNN/DailyMail data, are
+/// split on the tags and anything outside them is dropped; other texts have
+/// one sentence per line. Blank sentences are skipped.
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let sentences: Vec<&str> = if text.contains("<s>") {
+        text.split("<s>")
+            .skip(1)
+            .map(|tagged| tagged.split("</s>").next().unwrap_or(tagged))
+            .collect()
+    } else {
+        text.lines().collect()
+    };
+    sentences.into_iter().filter(|sentence| !sentence.trim().is_empty()).collect()
+}
+
+/// The sentences of tokens separated by [`SENTENCE_BREAK`], without empty ones
+pub fn sentences(tokens: &[u32]) -> impl Iterator<Item = &[u32]> {
+    tokens
+        .split(|&token| token == SENTENCE_BREAK)
+        .filter(|sentence| !sentence.is_empty())
+}
+
+/// Number of tokens, not counting sentence breaks
+pub fn token_count(tokens: &[u32]) -> usize {
+    tokens.iter().filter(|&&token| token != SENTENCE_BREAK).count()
+}
+
+/// Length of the longest sentence, the whole text when it has no breaks
+pub fn longest_sentence(tokens: &[u32]) -> usize {
+    sentences(tokens).map(<[u32]>::len).max().unwrap_or(0)
+}
+
+/// The tokens with sentence breaks removed, borrowed when there are none
+pub fn words(tokens: &[u32]) -> Cow<'_, [u32]> {
+    if tokens.contains(&SENTENCE_BREAK) {
+        Cow::Owned(tokens.iter().copied().filter(|&token| token != SENTENCE_BREAK).collect())
+    } else {
+        Cow::Borrowed(tokens)
+    }
+}
+
+/// Calculate ROUGE-Lsum, splitting both texts with [`split_sentences`]
+pub fn calculate_rouge_lsum(candidate: &str, reference: &str, tokenizer: Tokenizer) -> RougeLResult {
+    let mut interner = Interner::new();
+    let candidate_words = interner.tokenize_sentences(tokenizer, candidate);
+    let reference_words = interner.tokenize_sentences(tokenizer, reference);
+    rouge_lsum_from_tokens(&candidate_words, &reference_words)
+}
+
+/// ROUGE-Lsum of token IDs whose sentences are separated by [`SENTENCE_BREAK`]
+///
+/// As in `rouge-score`, each reference sentence contributes the union of its
+/// LCSs with every candidate sentence, and a token of that union counts as a
+/// hit only while unmatched occurrences of it remain in both texts. Each LCS
+/// is recovered from a full DP table with `rouge-score`'s tie-breaking, since
+/// a different choice among equally long LCSs changes the union.
+pub fn rouge_lsum_from_tokens(candidate_words: &[u32], reference_words: &[u32]) -> RougeLResult {
+    let (candidate_len, reference_len) = (token_count(candidate_words), token_count(reference_words));
+    if candidate_len == 0 || reference_len == 0 {
+        return RougeLResult::new(0.0, 0.0, 0.0);
+    }
+    let hits = sentence_contributions(candidate_words, reference_words)
+        .iter()
+        .map(|sentence| sentence.hits)
+        .sum();
+    scores_from_lcs(hits, candidate_len, reference_len)
+}
+
+/// What one reference sentence adds to ROUGE-Lsum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SentenceContribution {
+    pub tokens: usize,
+    /// Tokens of the sentence on its LCS with some candidate sentence
+    pub union: usize,
+    /// Tokens of the union counted as hits, once the candidate and reference
+    /// occurrences of each token are used up
+    pub hits: usize,
+}
+
+/// The contribution of every non-empty reference sentence to ROUGE-Lsum, in
+/// order; their hits sum to the LCS length behind the scores
+///
+/// A sentence with few hits for its tokens is one the candidate failed to
+/// cover. Sentences are consumed in order, so an early sentence can use up
+/// the occurrences of a token a later one also matches.
+pub fn sentence_contributions(candidate_words: &[u32], reference_words: &[u32]) -> Vec<SentenceContribution> {
+    let counts = |tokens: &[u32]| {
+        let mut counts = HashMap::new();
+        for sentence in sentences(tokens) {
+            for &token in sentence {
+                *counts.entry(token).or_insert(0usize) += 1;
+            }
+        }
+        counts
+    };
+    let (mut candidate_counts, mut reference_counts) = (counts(candidate_words), counts(reference_words));
+    let candidate_sentences: Vec<&[u32]> = sentences(candidate_words).collect();
+
+    let mut contributions = Vec::new();
+    for reference in sentences(reference_words) {
+        let mut in_union = vec![false; reference.len()];
+        for candidate in &candidate_sentences {
+            mark_lcs(reference, candidate, &mut in_union);
+        }
+        let mut contribution = SentenceContribution {
+            tokens: reference.len(),
+            union: 0,
+            hits: 0,
+        };
+        for (token, _) in reference.iter().zip(&in_union).filter(|(_, &hit)| hit) {
+            contribution.union += 1;
+            if let (Some(left_c), Some(left_r)) = (candidate_counts.get_mut(token), reference_counts.get_mut(token)) {
+                if *left_c > 0 && *left_r > 0 {
+                    contribution.hits += 1;
+                    *left_c -= 1;
+                    *left_r -= 1;
+                }
+            }
+        }
+        contributions.push(contribution);
+    }
+    contributions
+}
+
+/// Set `marked[i]` for every position of `reference` on its LCS with
+/// `candidate`, backtracking like `rouge-score`: on a mismatch, step back in
+/// the candidate only when that keeps a strictly longer LCS
+fn mark_lcs(reference: &[u32], candidate: &[u32], marked: &mut [bool]) {
+    let width = candidate.len() + 1;
+    let mut table = vec![0u32; (reference.len() + 1) * width];
+    for (i, &r) in reference.iter().enumerate() {
+        for (j, &c) in candidate.iter().enumerate() {
+            table[(i + 1) * width + j + 1] = if r == c {
+                table[i * width + j] + 1
+            } else {
+                table[i * width + j + 1].max(table[(i + 1) * width + j])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (reference.len(), candidate.len());
+    while i > 0 && j > 0 {
+        if reference[i - 1] == candidate[j - 1] {
+            marked[i - 1] = true;
+            i -= 1;
+            j -= 1;
+        } else if table[i * width + j - 1] > table[(i - 1) * width + j] {
+            j -= 1;
+        } else {
+            i -= 1;
+        }
+    }
+}
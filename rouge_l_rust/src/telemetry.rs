@@ -0,0 +1,90 @@
+use std::env;
+use std::error::Error;
+
+use tracing_subscriber::filter::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+
+use crate::cli::Cli;
+
+/// Environment variable holding the span filter, in `RUST_LOG` syntax
+pub const LOG_ENV: &str = "ROUGE_LOG";
+
+/// The installed span subscriber; dropping it flushes the spans still
+/// waiting to be exported
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+/// Report the spans of the scoring stages: with `ROUGE_LOG` set (e.g.
+/// `ROUGE_LOG=debug`), each span is printed to stderr with its busy and idle
+/// time as it closes, and with `--otlp-endpoint` (`otlp` feature) they are
+/// exported over OTLP/HTTP, at `info` level unless `ROUGE_LOG` says otherwise
+///
+/// Without either, no subscriber is installed and the spans cost next to
+/// nothing.
+#[cfg_attr(not(feature = "otlp"), allow(unused_variables))]
+pub fn init(cli: &Cli) -> Result<Telemetry, Box<dyn Error>> {
+    let log = env::var_os(LOG_ENV).is_some();
+    let telemetry = Telemetry {
+        #[cfg(feature = "otlp")]
+        provider: cli.otlp_endpoint.as_deref().map(otlp_provider).transpose()?,
+    };
+    #[cfg(feature = "otlp")]
+    let export = telemetry.provider.is_some();
+    #[cfg(not(feature = "otlp"))]
+    let export = false;
+    if !log && !export {
+        return Ok(telemetry);
+    }
+
+    let filter = if log {
+        EnvFilter::try_from_env(LOG_ENV).map_err(|e| format!("invalid {}: {}", LOG_ENV, e))?
+    } else {
+        EnvFilter::new("info")
+    };
+    let stderr = log.then(|| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_span_events(FmtSpan::CLOSE)
+    });
+    let registry = tracing_subscriber::registry().with(filter).with(stderr);
+    #[cfg(feature = "otlp")]
+    let registry = registry.with(telemetry.provider.as_ref().map(|provider| {
+        use opentelemetry::trace::TracerProvider;
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+    }));
+    registry.try_init()?;
+    Ok(telemetry)
+}
+
+/// Batch exporter sending spans to the OTLP/HTTP collector at `endpoint`,
+/// e.g. `http://localhost:4318`
+#[cfg(feature = "otlp")]
+fn otlp_provider(endpoint: &str) -> Result<opentelemetry_sdk::trace::SdkTracerProvider, Box<dyn Error>> {
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+    let resource = opentelemetry_sdk::Resource::builder()
+        .with_service_name(env!("CARGO_PKG_NAME"))
+        .build();
+    Ok(opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .build())
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("warning: cannot export the remaining spans: {}", err);
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::batch::Summary;
+use crate::config::Settings;
+
+/// Version record TensorBoard expects first in every event file
+const FILE_VERSION: &str = "brain.Event:2";
+
+/// Write the averages as scalars at `step` to a new event file in `logdir`,
+/// returning its path
+///
+/// Scalars are tagged `rouge_l/f_measure`, `rouge_l/precision`,
+/// `rouge_l/recall` and `rouge_l/pairs` (`rouge_lsum/` for Lsum).
+/// TensorBoard reads every event file of a directory, so each evaluation
+/// during training adds its own file and the steps line up with the
+/// training curves logged there.
+pub fn write_scalars(logdir: &Path, step: i64, summary: &Summary, settings: &Settings) -> io::Result<PathBuf> {
+    fs::create_dir_all(logdir)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let host = env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+    let path = logdir.join(format!("events.out.tfevents.{}.{}.{}.0.v2", now.as_secs(), host, process::id()));
+    let wall_time = now.as_secs_f64();
+    let prefix = settings.variant.name().replace('-', "_");
+    let scalars = [
+        ("f_measure", summary.mean.f_measure),
+        ("precision", summary.mean.precision),
+        ("recall", summary.mean.recall),
+        ("pairs", summary.count as f64),
+    ];
+
+    let mut out = BufWriter::new(File::create_new(&path)?);
+    write_record(&mut out, &event(wall_time, 0, |event| string_field(event, 3, FILE_VERSION)))?;
+    let mut values = Vec::new();
+    for (name, value) in scalars {
+        let mut message = Vec::new();
+        string_field(&mut message, 1, &format!("{}/{}", prefix, name));
+        // simple_value is a float
+        key(&mut message, 2, 5);
+        message.extend_from_slice(&(value as f32).to_le_bytes());
+        bytes_field(&mut values, 1, &message);
+    }
+    write_record(&mut out, &event(wall_time, step, |event| bytes_field(event, 5, &values)))?;
+    out.flush()?;
+    Ok(path)
+}
+
+/// An `Event` message with its wall time and step, and the fields added by
+/// `body`
+fn event(wall_time: f64, step: i64, body: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+    let mut event = Vec::new();
+    key(&mut event, 1, 1);
+    event.extend_from_slice(&wall_time.to_le_bytes());
+    key(&mut event, 2, 0);
+    varint(&mut event, step as u64);
+    body(&mut event);
+    event
+}
+
+// Protocol buffer encoding of the few field types an event needs
+
+fn key(out: &mut Vec<u8>, field: u32, wire_type: u32) {
+    varint(out, u64::from(field << 3 | wire_type));
+}
+
+fn varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn bytes_field(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    key(out, field, 2);
+    varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn string_field(out: &mut Vec<u8>, field: u32, text: &str) {
+    bytes_field(out, field, text.as_bytes());
+}
+
+/// Frame `data` as a TFRecord: its length, the checksum of the length, the
+/// data and its checksum
+fn write_record(out: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    let length = (data.len() as u64).to_le_bytes();
+    out.write_all(&length)?;
+    out.write_all(&masked_crc(&length).to_le_bytes())?;
+    out.write_all(data)?;
+    out.write_all(&masked_crc(data).to_le_bytes())
+}
+
+/// CRC-32C of `data`, masked as TFRecord stores it
+fn masked_crc(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+        }
+    }
+    let crc = !crc;
+    crc.rotate_right(15).wrapping_add(0xA282_EAD8)
+}
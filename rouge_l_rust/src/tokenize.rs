@@ -0,0 +1,129 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Strategy used to split text into the tokens compared by the LCS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tokenizer {
+    /// Lowercase and split on whitespace (same as the Java implementation)
+    #[default]
+    Whitespace,
+    /// Lowercase and keep only ASCII alphanumeric runs (Python `rouge-score`)
+    RougeScore,
+}
+
+/// Tokens of one text, stored as byte spans into its lowercased form
+///
+/// The lowercased text borrows the input when it has nothing to lowercase,
+/// so tokenizing already-normalized text allocates only the span vector.
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    normalized: Cow<'a, str>,
+    spans: Vec<Range<usize>>,
+}
+
+impl Tokens<'_> {
+    /// The lowercased text the spans index into
+    pub fn normalized(&self) -> &str {
+        &self.normalized
+    }
+
+    /// Byte range of every token within [`Tokens::normalized`]
+    pub fn spans(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Token `i` as a slice of the normalized text
+    pub fn get(&self, i: usize) -> Option<&str> {
+        self.spans.get(i).map(|span| &self.normalized[span.clone()])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        self.spans.iter().map(|span| &self.normalized[span.clone()])
+    }
+}
+
+impl Tokenizer {
+    /// Name used on the command line and in signatures
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tokenizer::Whitespace => "whitespace",
+            Tokenizer::RougeScore => "rouge-score",
+        }
+    }
+
+    /// Tokenize text into spans of its lowercased form
+    pub fn tokenize<'a>(&self, text: &'a str) -> Tokens<'a> {
+        let normalized = lowercase(text);
+        let mut spans = Vec::new();
+        self.for_each_span(&normalized, |span| spans.push(span));
+        Tokens { normalized, spans }
+    }
+
+    /// Call `f` with every token of `text` without allocating per token
+    pub fn for_each_token(&self, text: &str, mut f: impl FnMut(&str)) {
+        let normalized = lowercase(text);
+        self.for_each_span(&normalized, |span| f(&normalized[span]));
+    }
+
+    /// Call `f` with the byte range of every token of already lowercased text
+    fn for_each_span(&self, normalized: &str, f: impl FnMut(Range<usize>)) {
+        match self {
+            // Non-ASCII text may hold Unicode whitespace, which needs decoding
+            Tokenizer::Whitespace if normalized.is_ascii() => {
+                runs(normalized.bytes().enumerate(), |b| !(b as char).is_whitespace(), normalized.len(), f)
+            }
+            Tokenizer::Whitespace => runs(normalized.char_indices(), |c| !c.is_whitespace(), normalized.len(), f),
+            // Every byte of a multi-byte character fails the test, so bytes suffice
+            Tokenizer::RougeScore => runs(
+                normalized.bytes().enumerate(),
+                |b| b.is_ascii_lowercase() || b.is_ascii_digit(),
+                normalized.len(),
+                f,
+            ),
+        }
+    }
+}
+
+/// Call `f` with the range of every maximal run of items passing `keep`
+fn runs<T>(
+    items: impl Iterator<Item = (usize, T)>,
+    keep: impl Fn(T) -> bool,
+    end: usize,
+    mut f: impl FnMut(Range<usize>),
+) {
+    let mut start = None;
+    for (i, item) in items {
+        match (keep(item), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                f(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        f(s..end);
+    }
+}
+
+/// `text` lowercased, borrowed when lowercasing would not change it
+fn lowercase(text: &str) -> Cow<'_, str> {
+    if text.bytes().any(|b| b.is_ascii_uppercase() || !b.is_ascii()) {
+        Cow::Owned(text.to_lowercase())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
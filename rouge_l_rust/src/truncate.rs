@@ -0,0 +1,90 @@
+//! Token limits that keep multi-megabyte inputs from triggering quadratic
+//! LCS blowups
+
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// What happens to a text with more tokens than the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TruncationPolicy {
+    /// Refuse to score the pair
+    #[default]
+    Error,
+    /// Drop leading tokens, keeping the last N
+    TruncateHead,
+    /// Drop trailing tokens, keeping the first N
+    TruncateTail,
+}
+
+/// Largest number of tokens scored per text, and the policy beyond it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenLimit {
+    pub max_tokens: usize,
+    pub policy: TruncationPolicy,
+}
+
+/// A text over the limit under [`TruncationPolicy::Error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyTokens {
+    pub tokens: usize,
+    pub max_tokens: usize,
+}
+
+impl fmt::Display for TooManyTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} tokens, above the limit of {}", self.tokens, self.max_tokens)
+    }
+}
+
+impl std::error::Error for TooManyTokens {}
+
+impl TokenLimit {
+    /// The tokens to score, and whether any were dropped to get them
+    pub fn apply<'a, T>(&self, tokens: &'a [T]) -> Result<(&'a [T], bool), TooManyTokens> {
+        self.apply_counting(tokens, |_| true)
+    }
+
+    /// Like [`TokenLimit::apply`], counting only the items `counts` accepts
+    /// towards the limit, so markers such as sentence breaks ride along free
+    pub fn apply_counting<'a, T>(
+        &self,
+        tokens: &'a [T],
+        counts: impl Fn(&T) -> bool,
+    ) -> Result<(&'a [T], bool), TooManyTokens> {
+        let max = self.max_tokens;
+        let total = tokens.iter().filter(|&token| counts(token)).count();
+        if total <= max {
+            return Ok((tokens, false));
+        }
+        // Index just past the `n`th counted item
+        let end_of = |n: usize| match n {
+            0 => 0,
+            _ => tokens
+                .iter()
+                .enumerate()
+                .filter(|(_, token)| counts(token))
+                .nth(n - 1)
+                .map_or(tokens.len(), |(i, _)| i + 1),
+        };
+        match self.policy {
+            TruncationPolicy::Error => Err(TooManyTokens {
+                tokens: total,
+                max_tokens: max,
+            }),
+            TruncationPolicy::TruncateHead => Ok((&tokens[end_of(total - max)..], true)),
+            TruncationPolicy::TruncateTail => Ok((&tokens[..end_of(max)], true)),
+        }
+    }
+
+    /// Signature component, e.g. `max:512-tail`
+    pub fn name(&self) -> String {
+        match self.policy {
+            TruncationPolicy::Error => format!("max:{}", self.max_tokens),
+            TruncationPolicy::TruncateHead => format!("max:{}-head", self.max_tokens),
+            TruncationPolicy::TruncateTail => format!("max:{}-tail", self.max_tokens),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Bar, BarChart, BarGroup, Block, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use rouge_l_rust::lcs;
+use rouge_l_rust::tokenize::Tokenizer;
+
+use crate::batch::{ScoredPair, Summary};
+use crate::config::Settings;
+use crate::filter::ScoreField;
+use crate::format::ScoreFormat;
+use crate::input::Pair;
+
+use crate::output::BUCKET_WIDTH;
+use crate::stats::Buckets;
+
+/// Rows moved by PageUp/PageDown
+const PAGE: usize = 10;
+
+/// Browse batch results interactively until the user quits
+pub fn run(pairs: &[Pair], scores: &[ScoredPair], settings: &Settings) -> io::Result<()> {
+    let mut app = App::new(pairs, scores, settings);
+    ratatui::run(|terminal| app.run(terminal))
+}
+
+/// Tokens of the selected example, flagged with whether they are part of the LCS
+struct Detail {
+    index: usize,
+    candidate: Vec<(String, bool)>,
+    reference: Vec<(String, bool)>,
+}
+
+struct App<'a> {
+    pairs: &'a [Pair],
+    scores: &'a [ScoredPair],
+    tokenizer: Tokenizer,
+    score_format: ScoreFormat,
+    signature: String,
+    table: TableState,
+    histogram: Buckets,
+    detail: Option<Detail>,
+}
+
+impl<'a> App<'a> {
+    fn new(pairs: &'a [Pair], scores: &'a [ScoredPair], settings: &Settings) -> Self {
+        let histogram = Buckets::of(scores, ScoreField::F, BUCKET_WIDTH);
+        let mut table = TableState::default();
+        if !scores.is_empty() {
+            table.select(Some(0));
+        }
+        App {
+            pairs,
+            scores,
+            tokenizer: settings.tokenizer,
+            score_format: settings.score_format,
+            signature: settings.signature(),
+            table,
+            histogram,
+            detail: None,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            self.refresh_detail();
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let current = self.table.selected().unwrap_or(0);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.select(current.saturating_add(1)),
+                KeyCode::Up | KeyCode::Char('k') => self.select(current.saturating_sub(1)),
+                KeyCode::PageDown => self.select(current.saturating_add(PAGE)),
+                KeyCode::PageUp => self.select(current.saturating_sub(PAGE)),
+                KeyCode::Home | KeyCode::Char('g') => self.select(0),
+                KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+                _ => {}
+            }
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        if !self.scores.is_empty() {
+            self.table.select(Some(index.min(self.scores.len() - 1)));
+        }
+    }
+
+    /// Recompute the token highlighting when the selection moves
+    fn refresh_detail(&mut self) {
+        let Some(index) = self.table.selected() else {
+            self.detail = None;
+            return;
+        };
+        if self.detail.as_ref().is_some_and(|d| d.index == index) {
+            return;
+        }
+        let pair = &self.pairs[self.scores[index].index];
+        let candidate_tokens = self.tokenizer.tokenize(&pair.candidate);
+        let candidate: Vec<&str> = candidate_tokens.iter().collect();
+        // With several references, show the one the candidate matches best,
+        // the earliest on ties like the score itself
+        let f_measure = |reference: &[String], lcs: usize| 2.0 * lcs as f64 / (candidate.len() + reference.len()).max(1) as f64;
+        let (reference, alignment) = pair
+            .references
+            .iter()
+            .map(|text| {
+                let reference: Vec<String> = self.tokenizer.tokenize(text).iter().map(str::to_string).collect();
+                let alignment = lcs::alignment(&candidate, &reference.iter().map(String::as_str).collect::<Vec<_>>());
+                (reference, alignment)
+            })
+            .min_by(|(a, a_alignment), (b, b_alignment)| {
+                f_measure(b, b_alignment.len()).total_cmp(&f_measure(a, a_alignment.len()))
+            })
+            .unwrap_or_default();
+
+        let mut candidate_matched = vec![false; candidate.len()];
+        let mut reference_matched = vec![false; reference.len()];
+        for &(i, j) in &alignment {
+            candidate_matched[i] = true;
+            reference_matched[j] = true;
+        }
+        self.detail = Some(Detail {
+            index,
+            candidate: candidate.into_iter().map(str::to_string).zip(candidate_matched).collect(),
+            reference: reference.into_iter().zip(reference_matched).collect(),
+        });
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [top, bottom] =
+            Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(frame.area());
+        let [table_area, histogram_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(top);
+
+        self.draw_table(frame, table_area);
+        self.draw_histogram(frame, histogram_area);
+        self.draw_detail(frame, bottom);
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let fmt = &self.score_format;
+        let rows = self.scores.iter().map(|s| {
+            Row::new([
+                s.id.clone(),
+                fmt.format(s.result.f_measure),
+                fmt.format(s.result.precision),
+                fmt.format(s.result.recall),
+            ])
+        });
+        let summary = Summary::from_scores(self.scores);
+        let title = format!(
+            " {} pairs, mean F-Measure {} (q quit, ↑/↓ select) ",
+            summary.count,
+            fmt.format(summary.mean.f_measure)
+        );
+        let score_width = fmt.precision as u16 + 3;
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(score_width.max(9)),
+                Constraint::Length(score_width.max(9)),
+                Constraint::Length(score_width.max(9)),
+            ],
+        )
+        .header(
+            Row::new(["Id", "F-Measure", "Precision", "Recall"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::bordered()
+                .title(title)
+                .title_bottom(format!(" {} ", self.signature)),
+        );
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    fn draw_histogram(&self, frame: &mut Frame, area: Rect) {
+        let bars: Vec<Bar> = self
+            .histogram
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| Bar::default().value(count).label(Line::from(self.histogram.label(i))))
+            .collect();
+        let chart = BarChart::default()
+            .block(Block::bordered().title(" F-Measure distribution "))
+            .direction(Direction::Horizontal)
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(1)
+            .bar_gap(0);
+        frame.render_widget(chart, area);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+        let Some(detail) = &self.detail else {
+            frame.render_widget(Paragraph::new("No results").block(Block::bordered()), area);
+            return;
+        };
+        let scored = &self.scores[detail.index];
+        let fmt = &self.score_format;
+        let lines = vec![
+            Line::from(format!(
+                "F-Measure: {}, Precision: {}, Recall: {}",
+                fmt.format(scored.result.f_measure),
+                fmt.format(scored.result.precision),
+                fmt.format(scored.result.recall),
+            )),
+            Line::default(),
+            Line::from(Span::styled("Candidate:", Style::default().add_modifier(Modifier::BOLD))),
+            highlighted(&detail.candidate),
+            Line::default(),
+            Line::from(Span::styled("Reference:", Style::default().add_modifier(Modifier::BOLD))),
+            highlighted(&detail.reference),
+        ];
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(format!(" {} (green: in LCS, red: unmatched) ", scored.id)));
+        frame.render_widget(paragraph, area);
+    }
+}
+
+fn highlighted(tokens: &[(String, bool)]) -> Line<'_> {
+    let mut spans = Vec::with_capacity(tokens.len() * 2);
+    for (i, (token, matched)) in tokens.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" "));
+        }
+        let color = if *matched { Color::Green } else { Color::Red };
+        spans.push(Span::styled(token.as_str(), Style::default().fg(color)));
+    }
+    Line::from(spans)
+}
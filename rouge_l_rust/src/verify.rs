@@ -0,0 +1,212 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::json;
+
+use rouge_l_rust::RougeLResult;
+
+use crate::batch::{self, PairError};
+use crate::config::Settings;
+use crate::input::Pair;
+
+/// Helper run by the Python interpreter, embedded so `verify` works outside
+/// a checkout of the repository
+const SCRIPT: &str = include_str!("../scripts/python_rouge.py");
+
+/// Python ROUGE-L implementations `verify` can compare against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PythonRouge {
+    /// Google's `rouge-score`, which most papers report
+    #[default]
+    RougeScore,
+    /// `py-rouge`, a port of the ROUGE-1.5.5 Perl script; always summary-level
+    PyRouge,
+}
+
+impl PythonRouge {
+    /// Name used on the command line and by the helper script
+    pub fn name(&self) -> &'static str {
+        match self {
+            PythonRouge::RougeScore => "rouge-score",
+            PythonRouge::PyRouge => "py-rouge",
+        }
+    }
+}
+
+/// A pair scored differently by the two implementations
+#[derive(Debug)]
+pub struct Discrepancy {
+    pub id: String,
+    pub ours: RougeLResult,
+    pub theirs: RougeLResult,
+}
+
+#[derive(Debug)]
+pub struct VerifyReport {
+    pub pairs: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+/// Error raised while scoring the pairs on either side
+#[derive(Debug)]
+pub enum VerifyError {
+    Pair(PairError),
+    /// The interpreter could not be started
+    Spawn(String, io::Error),
+    Io(io::Error),
+    /// The helper failed, having explained why on stderr
+    Failed(ExitStatus),
+    /// Line `n` of the helper output is not a score
+    Output(usize, String),
+    /// The helper answered fewer pairs than it was sent
+    Missing { answered: usize, pairs: usize },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Pair(err) => write!(f, "{}", err),
+            VerifyError::Spawn(python, err) => write!(f, "cannot run {}: {}", python, err),
+            VerifyError::Io(err) => write!(f, "cannot talk to the Python helper: {}", err),
+            VerifyError::Failed(status) => write!(f, "the Python helper failed ({})", status),
+            VerifyError::Output(line, message) => write!(f, "Python helper output line {}: {}", line, message),
+            VerifyError::Missing { answered, pairs } => {
+                write!(f, "the Python helper scored {} of {} pairs", answered, pairs)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// One output line of the helper
+#[derive(Deserialize)]
+struct PythonScore {
+    f_measure: f64,
+    precision: f64,
+    recall: f64,
+}
+
+/// Score every pair here and with `against` run by `python`, with the same
+/// variant and tokenizer, and collect the pairs where any of F-measure,
+/// precision or recall differ by more than `tolerance`
+///
+/// n-best lists are compared on their rank-1 candidate.
+pub fn compare(
+    pairs: &[Pair],
+    settings: &Settings,
+    python: &str,
+    against: PythonRouge,
+    tolerance: f64,
+) -> Result<VerifyReport, VerifyError> {
+    let (scores, _) = batch::score_pairs(pairs, settings, None, None).map_err(VerifyError::Pair)?;
+    let theirs = python_scores(pairs, settings, python, against)?;
+    let differs = |a: f64, b: f64| (a - b).abs() > tolerance;
+    let discrepancies = scores
+        .into_iter()
+        .filter_map(|scored| {
+            let ours = scored.result;
+            let theirs = theirs[scored.index].clone();
+            (differs(ours.f_measure, theirs.f_measure)
+                || differs(ours.precision, theirs.precision)
+                || differs(ours.recall, theirs.recall))
+            .then_some(Discrepancy {
+                id: scored.id,
+                ours,
+                theirs,
+            })
+        })
+        .collect();
+    Ok(VerifyReport {
+        pairs: pairs.len(),
+        discrepancies,
+    })
+}
+
+/// Run the helper script, feeding it the pairs on stdin from another thread
+/// while its scores are read back, so neither side blocks on a full pipe
+fn python_scores(
+    pairs: &[Pair],
+    settings: &Settings,
+    python: &str,
+    against: PythonRouge,
+) -> Result<Vec<RougeLResult>, VerifyError> {
+    let mut child = Command::new(python)
+        .arg("-c")
+        .arg(SCRIPT)
+        .args([against.name(), settings.variant.name(), settings.tokenizer.name()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| VerifyError::Spawn(python.to_string(), e))?;
+    let stdin = child.stdin.take().expect("stdin is piped");
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    let (fed, lines) = thread::scope(|scope| {
+        let feeder = scope.spawn(move || -> io::Result<()> {
+            let mut stdin = BufWriter::new(stdin);
+            for pair in pairs {
+                let record = json!({ "candidate": pair.candidate, "references": pair.references });
+                writeln!(stdin, "{}", record)?;
+            }
+            stdin.flush()
+        });
+        let lines: io::Result<Vec<String>> = BufReader::new(stdout).lines().collect();
+        (feeder.join().expect("feeder thread panicked"), lines)
+    });
+    let status = child.wait().map_err(VerifyError::Io)?;
+    if !status.success() {
+        return Err(VerifyError::Failed(status));
+    }
+    fed.map_err(VerifyError::Io)?;
+
+    let scores = lines
+        .map_err(VerifyError::Io)?
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let score: PythonScore =
+                serde_json::from_str(line).map_err(|e| VerifyError::Output(index + 1, e.to_string()))?;
+            Ok(RougeLResult::new(score.f_measure, score.precision, score.recall))
+        })
+        .collect::<Result<Vec<_>, VerifyError>>()?;
+    if scores.len() != pairs.len() {
+        return Err(VerifyError::Missing {
+            answered: scores.len(),
+            pairs: pairs.len(),
+        });
+    }
+    Ok(scores)
+}
+
+/// Print the totals and the first `show` discrepancies, with every score
+/// that differs
+pub fn print_report(report: &VerifyReport, against: PythonRouge, tolerance: f64, show: usize, signature: &str) {
+    println!("=== ROUGE-L Verification ===\n");
+    println!("Signature:   {}", signature);
+    println!("Against:     {}", against.name());
+    println!("Pairs:       {}", report.pairs);
+    println!("Tolerance:   {:e}", tolerance);
+    println!("Mismatches:  {}", report.discrepancies.len());
+
+    for discrepancy in report.discrepancies.iter().take(show) {
+        println!("\n{}", discrepancy.id);
+        let fields = [
+            ("f_measure", discrepancy.ours.f_measure, discrepancy.theirs.f_measure),
+            ("precision", discrepancy.ours.precision, discrepancy.theirs.precision),
+            ("recall", discrepancy.ours.recall, discrepancy.theirs.recall),
+        ];
+        for (name, ours, theirs) in fields {
+            if (ours - theirs).abs() > tolerance {
+                println!("  {:<10} ours {:.6}  {} {:.6}  diff {:+.2e}", name, ours, against.name(), theirs, ours - theirs);
+            }
+        }
+    }
+    if report.discrepancies.len() > show {
+        println!("\n... and {} more", report.discrepancies.len() - show);
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::process::{Command, ExitStatus, Stdio};
+
+use serde_json::{json, Value};
+
+use crate::batch::{ScoredPair, Summary};
+use crate::config::Settings;
+use crate::filter::ScoreField;
+use crate::input::Pair;
+use crate::stats::Buckets;
+
+/// Helper run by the Python interpreter, embedded like the one of `verify`
+const SCRIPT: &str = include_str!("../scripts/wandb_log.py");
+
+/// Width of the buckets of the logged F-measure histogram
+const BUCKET_WIDTH: f64 = 0.05;
+
+/// Where the results of a run are logged
+#[derive(Debug, Clone)]
+pub struct WandbRun {
+    pub project: String,
+    pub entity: Option<String>,
+    /// Existing run to resume, such as the training run being evaluated
+    pub run_id: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Error raised while handing the results to the helper
+#[derive(Debug)]
+pub enum WandbError {
+    /// The interpreter could not be started
+    Spawn(String, io::Error),
+    Io(io::Error),
+    /// The helper failed, having explained why on stderr
+    Failed(ExitStatus),
+}
+
+impl fmt::Display for WandbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WandbError::Spawn(python, err) => write!(f, "cannot run {}: {}", python, err),
+            WandbError::Io(err) => write!(f, "cannot talk to the W&B helper: {}", err),
+            WandbError::Failed(status) => write!(f, "the W&B helper failed ({})", status),
+        }
+    }
+}
+
+impl std::error::Error for WandbError {}
+
+/// Log the averages, a histogram of the F-measures and a table of the
+/// `worst` lowest-scoring pairs to `run` through the `wandb` package of
+/// `python`
+///
+/// Metrics go in the `rouge_l/` section (`rouge_lsum/` for Lsum), so a
+/// resumed training run shows them beside its own curves. The helper's
+/// output goes to stderr, keeping the results on stdout parseable.
+pub fn log(
+    run: &WandbRun,
+    pairs: &[Pair],
+    scores: &[ScoredPair],
+    settings: &Settings,
+    worst: usize,
+    python: &str,
+) -> Result<(), WandbError> {
+    let summary = Summary::from_scores(scores);
+    let buckets = Buckets::of(scores, ScoreField::F, BUCKET_WIDTH);
+    let mut edges: Vec<f64> = (0..buckets.counts.len()).map(|i| buckets.bounds(i).0).collect();
+    edges.push(1.0);
+    let mut ranked: Vec<&ScoredPair> = scores.iter().collect();
+    ranked.sort_by(|a, b| a.result.f_measure.total_cmp(&b.result.f_measure).then(a.index.cmp(&b.index)));
+    let rows: Vec<Value> = ranked
+        .into_iter()
+        .take(worst)
+        .map(|scored| {
+            let pair = &pairs[scored.index];
+            json!([
+                scored.id,
+                scored.result.f_measure,
+                scored.result.precision,
+                scored.result.recall,
+                pair.candidate,
+                pair.references.join("\n"),
+            ])
+        })
+        .collect();
+    let payload = json!({
+        "project": run.project,
+        "entity": run.entity,
+        "run_id": run.run_id,
+        "name": run.name,
+        "prefix": settings.variant.name().replace('-', "_"),
+        "metrics": {
+            "f_measure": summary.mean.f_measure,
+            "precision": summary.mean.precision,
+            "recall": summary.mean.recall,
+            "pairs": summary.count,
+        },
+        "signature": settings.signature(),
+        "histogram": { "counts": buckets.counts, "edges": edges },
+        "worst": {
+            "columns": ["id", "f_measure", "precision", "recall", "candidate", "references"],
+            "rows": rows,
+        },
+    });
+
+    let mut child = Command::new(python)
+        .arg("-c")
+        .arg(SCRIPT)
+        .stdin(Stdio::piped())
+        .stdout(io::stderr())
+        .spawn()
+        .map_err(|e| WandbError::Spawn(python.to_string(), e))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // Wait for the helper even when it stopped reading early, to report its status
+    let written = serde_json::to_writer(&mut stdin, &payload)
+        .map_err(io::Error::from)
+        .and_then(|_| stdin.flush());
+    drop(stdin);
+    let status = child.wait().map_err(WandbError::Io)?;
+    if !status.success() {
+        return Err(WandbError::Failed(status));
+    }
+    written.map_err(WandbError::Io)
+}
@@ -0,0 +1,74 @@
+//! JavaScript bindings generated by wasm-bindgen, so browser-based
+//! annotation and evaluation tools can score text client-side
+//!
+//! Built with the `wasm` feature by `wasm-pack`, which writes an npm package
+//! exporting `scoreRougeL` with TypeScript declarations.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::lcs::LcsStrategy;
+use crate::reference::ReferenceSet;
+use crate::summary::Variant;
+use crate::tokenize::Tokenizer;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT_TYPES: &str = r#"
+export interface ScoreOptions {
+    /** "whitespace" (default) or "rouge-score" */
+    tokenizer?: "whitespace" | "rouge-score";
+    /** "rouge-l" (default) or "rouge-lsum" */
+    variant?: "rouge-l" | "rouge-lsum";
+}
+
+export interface RougeLScore {
+    fMeasure: number;
+    precision: number;
+    recall: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "ScoreOptions")]
+    pub type ScoreOptions;
+
+    #[wasm_bindgen(typescript_type = "RougeLScore")]
+    pub type RougeLScore;
+}
+
+/// `ScoreOptions` as read from JavaScript; missing fields keep their default
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Options {
+    tokenizer: Tokenizer,
+    variant: Variant,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Score {
+    f_measure: f64,
+    precision: f64,
+    recall: f64,
+}
+
+/// Score a candidate against a reference, throwing on unknown options
+#[wasm_bindgen(js_name = scoreRougeL)]
+pub fn score_rouge_l(candidate: &str, reference: &str, options: Option<ScoreOptions>) -> Result<RougeLScore, JsError> {
+    let options: Options = match options {
+        Some(options) if !options.is_null() && !options.is_undefined() => {
+            serde_wasm_bindgen::from_value(options.into()).map_err(|e| JsError::new(&format!("invalid options: {}", e)))?
+        }
+        _ => Options::default(),
+    };
+    let result =
+        ReferenceSet::with_variant(options.tokenizer, options.variant).score(candidate, reference, LcsStrategy::Auto);
+    let score = Score {
+        f_measure: result.f_measure,
+        precision: result.precision,
+        recall: result.recall,
+    };
+    Ok(serde_wasm_bindgen::to_value(&score)?.unchecked_into())
+}
@@ -0,0 +1,54 @@
+use std::error::Error;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{EventKind, RecursiveMode, Watcher};
+
+/// Quiet period used to coalesce the burst of events an editor save produces
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Call `rescore` once up front and then again after every change to one of
+/// the watched files or to a file in one of the watched directories, until
+/// the watcher shuts down
+pub fn watch_paths(
+    paths: &[&Path],
+    mut rescore: impl FnMut(&[String]) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for path in paths {
+        watcher.watch(path, RecursiveMode::NonRecursive)?;
+    }
+
+    rescore(&[])?;
+    while let Ok(event) = rx.recv() {
+        let mut changed = Vec::new();
+        collect_changes(event?, &mut changed);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_changes(event?, &mut changed);
+        }
+        if !changed.is_empty() {
+            rescore(&changed)?;
+        }
+    }
+    Ok(())
+}
+
+fn collect_changes(event: notify::Event, changed: &mut Vec<String>) {
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return;
+    }
+    for path in event.paths {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        if !changed.contains(&name) {
+            changed.push(name);
+        }
+    }
+}